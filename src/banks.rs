@@ -0,0 +1,709 @@
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{City, Household, Plot, Shop};
+use crate::config::ScenarioConfig;
+use crate::events::{LoanEvent, TransferEvent};
+use crate::ledger::{Account, TransferKind};
+
+/// Parâmetros dos bancos. Juros e prazos contam dias simulados.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BanksConfig {
+    /// Capital com que cada banco começa, vindo de fora da economia.
+    pub capital: usize,
+    /// Juros por dia pagos sobre os depósitos e cobrados sobre os
+    /// empréstimos, somados à taxa básica do banco central.
+    pub deposit_rate: f32,
+    pub loan_rate: f32,
+    /// Fração dos depósitos que o banco guarda em caixa e não empresta.
+    pub reserve_ratio: f32,
+    /// Valor de cada empréstimo a uma casa e a uma loja.
+    pub household_loan: usize,
+    pub shop_loan: usize,
+    /// Prazo dos empréstimos; a dívida é paga em parcelas diárias.
+    pub loan_days: u32,
+    /// Parcelas atrasadas seguidas a partir das quais o banco executa a
+    /// dívida.
+    pub default_after: u32,
+    /// Nota de crédito mínima para emprestar sem garantia.
+    pub min_score: f32,
+    /// Quanto a nota sobe ao quitar um empréstimo, cai a cada parcela
+    /// atrasada e cai no calote.
+    pub repaid_score: f32,
+    pub missed_score: f32,
+    pub default_score: f32,
+    /// Quanto vale, como garantia, cada lote de um membro da casa.
+    pub plot_collateral: usize,
+    /// Ouro por membro que a casa guarda no caixa; o que passa disso vai
+    /// para o banco.
+    pub household_keep: usize,
+    /// Abaixo disto por membro, a casa saca e, sem depósito, pede um
+    /// empréstimo.
+    pub household_borrow_below: usize,
+    /// O mesmo para o caixa das lojas.
+    pub shop_keep: usize,
+    pub shop_borrow_below: usize,
+}
+
+impl Default for BanksConfig {
+    fn default() -> Self {
+        Self {
+            capital: 1000,
+            deposit_rate: 0.0,
+            loan_rate: 0.04,
+            reserve_ratio: 0.2,
+            household_loan: 60,
+            shop_loan: 150,
+            loan_days: 2,
+            default_after: 2,
+            min_score: 0.4,
+            repaid_score: 0.1,
+            missed_score: 0.05,
+            default_score: 0.4,
+            plot_collateral: 20,
+            household_keep: 60,
+            household_borrow_below: 30,
+            shop_keep: 400,
+            shop_borrow_below: 30,
+        }
+    }
+}
+
+/// Banco de uma cidade: guarda os depósitos das casas e das lojas e lhes
+/// empresta ouro a juros.
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bank {
+    pub name: String,
+    /// Ouro em caixa: o capital mais os depósitos e as parcelas recebidas,
+    /// menos o que foi emprestado e sacado.
+    pub cash: usize,
+    /// Taxa básica do banco central do país, repassada a cada dia.
+    #[serde(default)]
+    pub base_rate: f32,
+    /// Moeda de outros países em caixa, pelo nome da moeda: o que o banco
+    /// recebeu ao pagar importações de lá, e com que paga as próximas.
+    #[serde(default)]
+    pub foreign: BTreeMap<String, usize>,
+}
+
+/// Conta de uma casa ou loja no banco da cidade.
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Credit {
+    /// Saldo depositado, com os juros.
+    pub deposit: usize,
+    pub loan: Option<Loan>,
+    /// Nota de crédito, de 0 a 1.
+    pub score: f32,
+}
+
+impl Default for Credit {
+    fn default() -> Self {
+        Self {
+            deposit: 0,
+            loan: None,
+            score: 0.5,
+        }
+    }
+}
+
+/// Empréstimo em aberto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Loan {
+    /// Quanto falta pagar, com os juros.
+    pub balance: usize,
+    /// Parcelas que faltam, contando a de hoje.
+    pub days_left: u32,
+    /// Parcelas atrasadas seguidas.
+    pub missed: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoanOutcome {
+    Granted,
+    Repaid,
+    /// O banco executou a dívida; o valor do evento é o que ele perdeu.
+    Defaulted,
+}
+
+impl Bank {
+    /// Quanto o banco pode emprestar sem descer do encaixe sobre os
+    /// depósitos.
+    pub fn lendable(&self, deposits: usize, reserve_ratio: f32) -> usize {
+        let reserve = (deposits as f32 * reserve_ratio).ceil() as usize;
+        self.cash.saturating_sub(reserve)
+    }
+
+    /// Quanto o banco guarda da moeda de fora `currency`.
+    pub fn holding(&self, currency: &str) -> usize {
+        self.foreign.get(currency).copied().unwrap_or(0)
+    }
+
+    /// Tira `amount` da moeda de fora guardada, que deixa de constar quando
+    /// acaba.
+    pub fn take_foreign(&mut self, currency: &str, amount: usize) {
+        if let Some(held) = self.foreign.get_mut(currency) {
+            *held -= amount;
+            if *held == 0 {
+                self.foreign.remove(currency);
+            }
+        }
+    }
+
+    pub fn deposit(&mut self, credit: &mut Credit, cash: &mut usize, amount: usize) -> usize {
+        let amount = amount.min(*cash);
+        *cash -= amount;
+        credit.deposit += amount;
+        self.cash += amount;
+        amount
+    }
+
+    /// Saca até `amount` do depósito, até onde o caixa do banco alcança.
+    pub fn withdraw(&mut self, credit: &mut Credit, cash: &mut usize, amount: usize) -> usize {
+        let amount = amount.min(credit.deposit).min(self.cash);
+        credit.deposit -= amount;
+        self.cash -= amount;
+        *cash += amount;
+        amount
+    }
+
+    pub fn lend(&mut self, credit: &mut Credit, cash: &mut usize, amount: usize, days: u32) {
+        let amount = amount.min(self.cash);
+        self.cash -= amount;
+        *cash += amount;
+        credit.loan = Some(Loan {
+            balance: amount,
+            days_left: days.max(1),
+            missed: 0,
+        });
+    }
+
+    /// Cobra a parcela do dia, até onde o caixa do devedor alcança. Na
+    /// última parcela vence todo o saldo.
+    pub fn collect(&mut self, loan: &mut Loan, cash: &mut usize) -> usize {
+        let due = loan.balance.div_ceil(loan.days_left.max(1) as usize);
+        let paid = due.min(*cash);
+        *cash -= paid;
+        loan.balance -= paid;
+        self.cash += paid;
+        loan.days_left = loan.days_left.saturating_sub(1).max(1);
+        if paid < due {
+            loan.missed += 1;
+        } else {
+            loan.missed = 0;
+        }
+        paid
+    }
+}
+
+impl Credit {
+    /// Juros de um dia: os do depósito arredondados para baixo e os da
+    /// dívida para cima.
+    pub fn accrue(&mut self, deposit_rate: f32, loan_rate: f32) {
+        self.deposit += (self.deposit as f32 * deposit_rate).floor() as usize;
+        if let Some(loan) = &mut self.loan {
+            loan.balance += (loan.balance as f32 * loan_rate).ceil() as usize;
+        }
+    }
+
+    /// Executa a dívida: o depósito abate o que puder e o resto é perdido.
+    /// Devolve o que o banco perdeu.
+    pub fn write_off(&mut self) -> usize {
+        let Some(loan) = self.loan.take() else {
+            return 0;
+        };
+        let set_off = loan.balance.min(self.deposit);
+        self.deposit -= set_off;
+        loan.balance - set_off
+    }
+
+    fn rate(&mut self, change: f32) {
+        self.score = (self.score + change).clamp(0.0, 1.0);
+    }
+}
+
+/// Banco de cada cidade.
+pub fn banks_by_city(banks: &Query<(Entity, &mut Bank, &Parent)>) -> HashMap<Entity, Entity> {
+    banks
+        .iter()
+        .map(|(entity, _, city)| (city.get(), entity))
+        .collect()
+}
+
+/// Lança o ouro movido entre o banco e um cliente.
+fn send(
+    transfers: &mut EventWriter<TransferEvent>,
+    from: Entity,
+    to: Entity,
+    amount: usize,
+    kind: TransferKind,
+) {
+    if amount > 0 {
+        transfers.send(TransferEvent {
+            from: Account::Agent(from),
+            to: Account::Agent(to),
+            amount,
+            kind,
+        });
+    }
+}
+
+/// Fecha a conta de uma casa ou loja que sai da economia: o depósito volta
+/// para o caixa, até onde o banco tem, e paga o que puder da dívida; o resto
+/// é calote.
+pub fn close_account(
+    bank: Option<(Entity, Mut<Bank>)>,
+    holder: Entity,
+    city: Entity,
+    credit: &mut Credit,
+    cash: &mut usize,
+    transfers: &mut EventWriter<TransferEvent>,
+    loans: &mut EventWriter<LoanEvent>,
+) {
+    let Some((bank_entity, mut bank)) = bank else {
+        return;
+    };
+    let deposit = credit.deposit;
+    let withdrawn = bank.withdraw(credit, cash, deposit);
+    send(
+        transfers,
+        bank_entity,
+        holder,
+        withdrawn,
+        TransferKind::Withdrawal,
+    );
+    let Some(mut loan) = credit.loan else {
+        return;
+    };
+    loan.days_left = 1;
+    let paid = bank.collect(&mut loan, cash);
+    send(
+        transfers,
+        holder,
+        bank_entity,
+        paid,
+        TransferKind::Repayment,
+    );
+    credit.loan = Some(loan).filter(|loan| loan.balance > 0);
+    let lost = credit.write_off();
+    loans.send(LoanEvent {
+        city,
+        amount: if lost > 0 { lost } else { paid },
+        outcome: if lost > 0 {
+            LoanOutcome::Defaulted
+        } else {
+            LoanOutcome::Repaid
+        },
+    });
+}
+
+/// Balcão do banco, a cada tick. Casas e lojas com mais ouro do que guardam
+/// depositam o excesso; com pouco, sacam do depósito e, sem ele, pedem um
+/// empréstimo. O banco empresta a quem tem nota de crédito suficiente ou
+/// garantia (o depósito e os lotes dos membros da casa) que cubra o valor,
+/// sem descer do encaixe sobre os depósitos da cidade. As casas são atendidas
+/// pelo nome e as lojas na ordem da cidade.
+#[allow(clippy::too_many_arguments)]
+pub fn banking_system(
+    mut households: Query<(Entity, &mut Household, &mut Credit, &Parent), Without<Shop>>,
+    mut shops: Query<(&mut Shop, &mut Credit, &Parent)>,
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    cities: Query<(Entity, &City)>,
+    plots: Query<&Plot>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut loans: EventWriter<LoanEvent>,
+) {
+    let config = &config.banks;
+    let by_city = banks_by_city(&banks);
+    let mut deposits: HashMap<Entity, usize> = HashMap::new();
+    let accounts = households
+        .iter()
+        .map(|(_, _, credit, city)| (credit, city))
+        .chain(shops.iter().map(|(_, credit, city)| (credit, city)));
+    for (credit, city) in accounts {
+        *deposits.entry(city.get()).or_insert(0) += credit.deposit;
+    }
+    let mut plots_owned: HashMap<Entity, usize> = HashMap::new();
+    for owner in plots.iter().filter_map(|plot| plot.owner) {
+        *plots_owned.entry(owner).or_insert(0) += 1;
+    }
+
+    let mut order: Vec<(String, Entity)> = households
+        .iter()
+        .map(|(entity, household, _, _)| (household.name.clone(), entity))
+        .collect();
+    order.sort();
+    for (_, entity) in order {
+        let Ok((_, mut household, mut credit, city)) = households.get_mut(entity) else {
+            continue;
+        };
+        let city = city.get();
+        let Some((bank_entity, mut bank, _)) = by_city
+            .get(&city)
+            .and_then(|&bank| banks.get_mut(bank).ok())
+        else {
+            continue;
+        };
+        let members = household.members.len();
+        if members == 0 {
+            continue;
+        }
+        let household = &mut *household;
+        let keep = config.household_keep * members;
+        let floor = config.household_borrow_below * members;
+        if household.purse > keep {
+            let excess = household.purse - keep;
+            let amount = bank.deposit(&mut credit, &mut household.purse, excess);
+            *deposits.entry(city).or_insert(0) += amount;
+            send(
+                &mut transfers,
+                entity,
+                bank_entity,
+                amount,
+                TransferKind::Deposit,
+            );
+            continue;
+        }
+        if household.purse >= floor {
+            continue;
+        }
+        let short = keep - household.purse;
+        let amount = bank.withdraw(&mut credit, &mut household.purse, short);
+        *deposits.entry(city).or_insert(0) -= amount;
+        send(
+            &mut transfers,
+            bank_entity,
+            entity,
+            amount,
+            TransferKind::Withdrawal,
+        );
+        if household.purse >= floor || credit.loan.is_some() {
+            continue;
+        }
+        let collateral = credit.deposit
+            + household
+                .members
+                .iter()
+                .filter_map(|member| plots_owned.get(member))
+                .sum::<usize>()
+                * config.plot_collateral;
+        let amount = config.household_loan;
+        let trusted = credit.score >= config.min_score || collateral >= amount;
+        let reserves = deposits.get(&city).copied().unwrap_or(0);
+        if trusted && bank.lendable(reserves, config.reserve_ratio) >= amount {
+            bank.lend(&mut credit, &mut household.purse, amount, config.loan_days);
+            send(
+                &mut transfers,
+                bank_entity,
+                entity,
+                amount,
+                TransferKind::Loan,
+            );
+            loans.send(LoanEvent {
+                city,
+                amount,
+                outcome: LoanOutcome::Granted,
+            });
+        }
+    }
+
+    let mut cities: Vec<(Entity, &City)> = cities.iter().collect();
+    cities.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    for (city, city_component) in cities {
+        let Some((bank_entity, mut bank, _)) = by_city
+            .get(&city)
+            .and_then(|&bank| banks.get_mut(bank).ok())
+        else {
+            continue;
+        };
+        for &entity in &city_component.shops {
+            let Ok((mut shop, mut credit, _)) = shops.get_mut(entity) else {
+                continue;
+            };
+            if shop.cash > config.shop_keep {
+                let excess = shop.cash - config.shop_keep;
+                let amount = bank.deposit(&mut credit, &mut shop.cash, excess);
+                *deposits.entry(city).or_insert(0) += amount;
+                send(
+                    &mut transfers,
+                    entity,
+                    bank_entity,
+                    amount,
+                    TransferKind::Deposit,
+                );
+                continue;
+            }
+            if shop.cash >= config.shop_borrow_below {
+                continue;
+            }
+            let short = config.shop_keep - shop.cash;
+            let amount = bank.withdraw(&mut credit, &mut shop.cash, short);
+            *deposits.entry(city).or_insert(0) -= amount;
+            send(
+                &mut transfers,
+                bank_entity,
+                entity,
+                amount,
+                TransferKind::Withdrawal,
+            );
+            if shop.cash >= config.shop_borrow_below || credit.loan.is_some() {
+                continue;
+            }
+            let amount = config.shop_loan;
+            let trusted = credit.score >= config.min_score || credit.deposit >= amount;
+            let reserves = deposits.get(&city).copied().unwrap_or(0);
+            if trusted && bank.lendable(reserves, config.reserve_ratio) >= amount {
+                bank.lend(&mut credit, &mut shop.cash, amount, config.loan_days);
+                send(
+                    &mut transfers,
+                    bank_entity,
+                    entity,
+                    amount,
+                    TransferKind::Loan,
+                );
+                loans.send(LoanEvent {
+                    city,
+                    amount,
+                    outcome: LoanOutcome::Granted,
+                });
+            }
+        }
+    }
+}
+
+/// Fecha o dia de cada conta: credita os juros do depósito, soma os da
+/// dívida (ambos acima da taxa básica) e cobra a parcela. Quem quita sobe a
+/// nota de crédito, quem atrasa perde um pouco dela e quem atrasa
+/// `default_after` parcelas seguidas tem a dívida executada: o depósito
+/// abate o que puder, o resto é perdido pelo banco, a nota cai e os lotes
+/// dos membros da casa passam para o banco, que os aluga.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn interest_system(
+    mut households: Query<(Entity, &mut Household, &mut Credit, &Parent), Without<Shop>>,
+    mut shops: Query<(Entity, &mut Shop, &mut Credit, &Parent)>,
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    mut plots: Query<&mut Plot>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut loans: EventWriter<LoanEvent>,
+) {
+    let config = &config.banks;
+    let by_city = banks_by_city(&banks);
+    // Banco que toma os lotes de cada membro das casas executadas
+    let mut seized: HashMap<Entity, Entity> = HashMap::new();
+
+    let accounts = households
+        .iter_mut()
+        .map(|(entity, household, credit, city)| {
+            let members = household.members.clone();
+            let cash = household.map_unchanged(|household| &mut household.purse);
+            (entity, cash, credit, city.get(), members)
+        })
+        .chain(shops.iter_mut().map(|(entity, shop, credit, city)| {
+            let cash = shop.map_unchanged(|shop| &mut shop.cash);
+            (entity, cash, credit, city.get(), Vec::new())
+        }));
+    for (entity, mut cash, mut credit, city, members) in accounts {
+        let Some((bank_entity, mut bank, _)) = by_city
+            .get(&city)
+            .and_then(|&bank| banks.get_mut(bank).ok())
+        else {
+            continue;
+        };
+        credit.accrue(
+            config.deposit_rate + bank.base_rate,
+            config.loan_rate + bank.base_rate,
+        );
+        let Some(mut loan) = credit.loan else {
+            continue;
+        };
+        let paid = bank.collect(&mut loan, &mut cash);
+        send(
+            &mut transfers,
+            entity,
+            bank_entity,
+            paid,
+            TransferKind::Repayment,
+        );
+        if loan.balance == 0 {
+            credit.loan = None;
+            credit.rate(config.repaid_score);
+            loans.send(LoanEvent {
+                city,
+                amount: paid,
+                outcome: LoanOutcome::Repaid,
+            });
+            continue;
+        }
+        credit.loan = Some(loan);
+        if loan.missed == 0 {
+            continue;
+        }
+        credit.rate(-config.missed_score);
+        if loan.missed < config.default_after {
+            continue;
+        }
+        let lost = credit.write_off();
+        credit.rate(-config.default_score);
+        seized.extend(members.into_iter().map(|member| (member, bank_entity)));
+        loans.send(LoanEvent {
+            city,
+            amount: lost,
+            outcome: LoanOutcome::Defaulted,
+        });
+    }
+
+    for mut plot in plots.iter_mut() {
+        // A meação era com o antigo dono; o banco só aceita ouro
+        if let Some(&bank) = plot.owner.and_then(|owner| seized.get(&owner)) {
+            plot.owner = Some(bank);
+            plot.sharecropped = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loans_are_repaid_in_installments_or_written_off() {
+        let mut bank = Bank {
+            cash: 100,
+            ..default()
+        };
+        let mut credit = Credit::default();
+        let mut cash = 50;
+        assert_eq!(bank.deposit(&mut credit, &mut cash, 40), 40);
+        assert_eq!((bank.cash, credit.deposit, cash), (140, 40, 10));
+        // Encaixe de metade dos depósitos
+        assert_eq!(bank.lendable(40, 0.5), 120);
+
+        bank.lend(&mut credit, &mut cash, 60, 2);
+        credit.accrue(0.1, 0.1);
+        assert_eq!(credit.deposit, 44);
+        let mut loan = credit.loan.unwrap();
+        assert_eq!(loan.balance, 66);
+        assert_eq!(bank.collect(&mut loan, &mut cash), 33);
+        assert_eq!((loan.balance, loan.missed, cash), (33, 0, 37));
+
+        // Sem caixa, a parcela atrasa e o depósito abate a dívida executada
+        let mut broke = 0;
+        assert_eq!(bank.collect(&mut loan, &mut broke), 0);
+        assert_eq!(loan.missed, 1);
+        credit.loan = Some(loan);
+        assert_eq!(credit.write_off(), 0);
+        assert_eq!(credit.deposit, 11);
+        assert_eq!(bank.withdraw(&mut credit, &mut cash, 100), 11);
+        assert_eq!(credit.deposit, 0);
+    }
+
+    #[test]
+    fn defaulted_plots_pass_to_the_bank_which_rents_them_out() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::calendar::Calendar;
+        use crate::components::{Person, PersonActions, TerrainType};
+        use crate::items::{ItemCatalog, MarketPrices};
+
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(ItemCatalog::default());
+        world.init_resource::<MarketPrices>();
+        world.init_resource::<Calendar>();
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<LoanEvent>>();
+        let city = world.spawn(City::default()).id();
+        let bank = world
+            .spawn(Bank {
+                cash: 100,
+                ..default()
+            })
+            .id();
+        world.entity_mut(city).add_child(bank);
+
+        // Uma parcela já atrasada e nada na bolsa: a próxima executa a dívida
+        let debtor = Entity::from_raw(1000);
+        let neighbour = world.spawn(Person::default()).id();
+        world.entity_mut(city).add_child(neighbour);
+        let household = world
+            .spawn((
+                Household {
+                    members: vec![debtor],
+                    ..default()
+                },
+                Credit {
+                    loan: Some(Loan {
+                        balance: 50,
+                        days_left: 5,
+                        missed: 1,
+                    }),
+                    ..default()
+                },
+            ))
+            .id();
+        world.entity_mut(city).add_child(household);
+        let mut plot = |owner: Entity, fertility: f32| {
+            let plot = world
+                .spawn(Plot {
+                    terrain: TerrainType::Grassland,
+                    fertility,
+                    owner: Some(owner),
+                    farmer: None,
+                    crop: None,
+                    sharecropped: false,
+                })
+                .id();
+            world.entity_mut(city).add_child(plot);
+            plot
+        };
+        let seized = plot(debtor, 1.0);
+        let kept = plot(neighbour, 0.5);
+
+        world.run_system_once(interest_system).unwrap();
+        assert_eq!(world.get::<Plot>(seized).unwrap().owner, Some(bank));
+        assert_eq!(world.get::<Plot>(kept).unwrap().owner, Some(neighbour));
+        assert!(world.get::<Credit>(household).unwrap().loan.is_none());
+        let outcomes: Vec<_> = world
+            .resource_mut::<Events<LoanEvent>>()
+            .drain()
+            .map(|event| event.outcome)
+            .collect();
+        assert_eq!(outcomes, vec![LoanOutcome::Defaulted]);
+
+        // Sem ouro para o aluguel, não há meação com o banco; com ele, o
+        // aluguel vai para o caixa do banco
+        let rent = ScenarioConfig::default().land.rent;
+        let tenant = |world: &mut World, gold: usize| {
+            let person = world
+                .spawn(Person {
+                    action: PersonActions::Seeding,
+                    gold,
+                    ..default()
+                })
+                .id();
+            world.entity_mut(city).add_child(person);
+            person
+        };
+        let broke = tenant(&mut world, rent - 1);
+        world
+            .run_system_once(crate::land::allot_plots_system)
+            .unwrap();
+        assert_eq!(world.get::<Plot>(seized).unwrap().farmer, None);
+        assert_eq!(world.get::<Plot>(seized).unwrap().owner, Some(bank));
+        world.despawn(broke);
+
+        let tenant = tenant(&mut world, rent);
+        world
+            .run_system_once(crate::land::allot_plots_system)
+            .unwrap();
+        let plot = world.get::<Plot>(seized).unwrap();
+        assert_eq!((plot.owner, plot.farmer), (Some(bank), Some(tenant)));
+        assert!(!plot.sharecropped);
+        assert_eq!(world.get::<Person>(tenant).unwrap().gold, 0);
+        assert_eq!(world.get::<Bank>(bank).unwrap().cash, 100 + rent);
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SimClock;
+use crate::config::ScenarioConfig;
+
+/// Parâmetros do calendário. A duração do dia vem de `day_secs`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalendarConfig {
+    /// Dias simulados em cada estação; um ano tem quatro estações.
+    pub days_per_season: u64,
+    /// Quanto a fome aumenta em cada estação, multiplicando a taxa normal.
+    pub hunger: SeasonFactors,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            days_per_season: 1,
+            hunger: SeasonFactors {
+                spring: 1.0,
+                summer: 1.0,
+                autumn: 1.0,
+                winter: 1.5,
+            },
+        }
+    }
+}
+
+impl CalendarConfig {
+    /// Segundos simulados em um ano, com dias de `day_secs`.
+    pub fn year_secs(&self, day_secs: f32) -> f32 {
+        (self.days_per_season * 4) as f32 * day_secs
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Season {
+    #[default]
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Season; 4] = [
+        Season::Spring,
+        Season::Summer,
+        Season::Autumn,
+        Season::Winter,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Season::Spring => "Primavera",
+            Season::Summer => "Verão",
+            Season::Autumn => "Outono",
+            Season::Winter => "Inverno",
+        }
+    }
+}
+
+/// Um fator para cada estação.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeasonFactors {
+    pub spring: f32,
+    pub summer: f32,
+    pub autumn: f32,
+    pub winter: f32,
+}
+
+impl Default for SeasonFactors {
+    fn default() -> Self {
+        Self {
+            spring: 1.0,
+            summer: 1.0,
+            autumn: 1.0,
+            winter: 1.0,
+        }
+    }
+}
+
+impl SeasonFactors {
+    pub fn get(&self, season: Season) -> f32 {
+        match season {
+            Season::Spring => self.spring,
+            Season::Summer => self.summer,
+            Season::Autumn => self.autumn,
+            Season::Winter => self.winter,
+        }
+    }
+
+    pub fn values(&self) -> [(&'static str, f32); 4] {
+        [
+            ("spring", self.spring),
+            ("summer", self.summer),
+            ("autumn", self.autumn),
+            ("winter", self.winter),
+        ]
+    }
+}
+
+/// Data simulada, contada a partir do ano 1, primeiro dia da primavera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Date {
+    pub year: u64,
+    pub season: Season,
+    /// Dia dentro da estação, a partir de 1.
+    pub day: u64,
+    pub hour: u32,
+}
+
+impl Default for Date {
+    fn default() -> Self {
+        Self {
+            year: 1,
+            season: Season::Spring,
+            day: 1,
+            hour: 0,
+        }
+    }
+}
+
+impl Date {
+    /// Data correspondente ao tick atual do relógio.
+    pub fn from_clock(clock: &SimClock, days_per_season: u64) -> Self {
+        let days_per_season = days_per_season.max(1);
+        let day = clock.day();
+        let season = (day / days_per_season) % 4;
+        let hour = (clock.elapsed_secs() % clock.day_secs) / clock.day_secs * 24.0;
+        Self {
+            year: day / (days_per_season * 4) + 1,
+            season: Season::ALL[season as usize],
+            day: day % days_per_season + 1,
+            hour: hour as u32,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, dia {}, ano {}, {:02}h",
+            self.season.name(),
+            self.day,
+            self.year,
+            self.hour
+        )
+    }
+}
+
+/// Data do tick atual, recalculada a partir do `SimClock` a cada tick.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct Calendar {
+    pub date: Date,
+}
+
+impl Calendar {
+    pub fn season(&self) -> Season {
+        self.date.season
+    }
+}
+
+pub fn update_calendar_system(
+    mut calendar: ResMut<Calendar>,
+    clock: Res<SimClock>,
+    config: Res<ScenarioConfig>,
+) {
+    calendar.date = Date::from_clock(&clock, config.calendar.days_per_season);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_follow_days_and_seasons() {
+        let mut clock = SimClock::new(1.0, 100.0);
+        assert_eq!(Date::from_clock(&clock, 2), Date::default());
+
+        // Meio do segundo dia da primavera
+        clock.tick = 150;
+        let date = Date::from_clock(&clock, 2);
+        assert_eq!((date.season, date.day, date.hour), (Season::Spring, 2, 12));
+
+        // O oitavo dia fecha o inverno e o nono abre o ano 2
+        clock.tick = 700;
+        assert_eq!(Date::from_clock(&clock, 2).season, Season::Winter);
+        clock.tick = 800;
+        let date = Date::from_clock(&clock, 2);
+        assert_eq!((date.year, date.season, date.day), (2, Season::Spring, 1));
+    }
+}
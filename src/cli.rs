@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+
+/// Opções de linha de comando aceitas pelo binário.
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub scenario: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub ticks: Option<u64>,
+    pub until_day: Option<u64>,
+    pub realtime: bool,
+    pub load: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub metrics: Option<PathBuf>,
+    pub audit: bool,
+}
+
+impl CliArgs {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut cli = CliArgs::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scenario" => cli.scenario = Some(parse_path(&arg, args.next())?),
+                "--load" => cli.load = Some(parse_path(&arg, args.next())?),
+                "--save" => cli.save = Some(parse_path(&arg, args.next())?),
+                "--metrics" => cli.metrics = Some(parse_path(&arg, args.next())?),
+                "--seed" => cli.seed = Some(parse_number(&arg, args.next())?),
+                "--ticks" => cli.ticks = Some(parse_number(&arg, args.next())?),
+                "--until-day" => cli.until_day = Some(parse_number(&arg, args.next())?),
+                "--realtime" => cli.realtime = true,
+                "--audit" => cli.audit = true,
+                other => return Err(format!("unknown argument `{}`", other)),
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u64, String> {
+    let value = value.ok_or_else(|| format!("{} expects a number", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for {}", value, flag))
+}
+
+fn parse_path(flag: &str, value: Option<String>) -> Result<PathBuf, String> {
+    value
+        .map(PathBuf::from)
+        .ok_or_else(|| format!("{} expects a file path", flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        CliArgs::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_every_flag() {
+        let cli = parse(&[
+            "--scenario",
+            "scenarios/default.toml",
+            "--seed",
+            "7",
+            "--ticks",
+            "100",
+            "--until-day",
+            "3",
+            "--realtime",
+            "--load",
+            "in.ron",
+            "--save",
+            "out.ron",
+            "--metrics",
+            "metrics.csv",
+            "--audit",
+        ])
+        .unwrap();
+        assert_eq!(cli.scenario, Some(PathBuf::from("scenarios/default.toml")));
+        assert_eq!(
+            (cli.seed, cli.ticks, cli.until_day),
+            (Some(7), Some(100), Some(3))
+        );
+        assert!(cli.realtime && cli.audit);
+        assert_eq!(cli.load, Some(PathBuf::from("in.ron")));
+        assert_eq!(cli.save, Some(PathBuf::from("out.ron")));
+        assert_eq!(cli.metrics, Some(PathBuf::from("metrics.csv")));
+
+        let cli = parse(&[]).unwrap();
+        assert!(cli.scenario.is_none() && cli.ticks.is_none() && !cli.realtime);
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_bad_or_missing_values() {
+        assert_eq!(
+            parse(&["--tick", "5"]).unwrap_err(),
+            "unknown argument `--tick`"
+        );
+        assert_eq!(
+            parse(&["--seed", "abc"]).unwrap_err(),
+            "invalid value `abc` for --seed"
+        );
+        assert_eq!(
+            parse(&["--until-day", "-1"]).unwrap_err(),
+            "invalid value `-1` for --until-day"
+        );
+        assert_eq!(parse(&["--ticks"]).unwrap_err(), "--ticks expects a number");
+        assert_eq!(
+            parse(&["--save"]).unwrap_err(),
+            "--save expects a file path"
+        );
+    }
+}
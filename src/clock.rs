@@ -0,0 +1,156 @@
+use bevy::{app::AppExit, prelude::*};
+
+/// Relógio da simulação, avançado em ticks de duração fixa.
+///
+/// Todos os sistemas leem o tempo daqui em vez de `Time`, de modo que o
+/// resultado não depende da taxa de quadros nem de quanto tempo real passou.
+#[derive(Resource, Debug, Clone)]
+pub struct SimClock {
+    pub tick: u64,
+    /// Duração simulada de um tick, em segundos.
+    pub tick_secs: f32,
+    /// Duração simulada de um dia, em segundos.
+    pub day_secs: f32,
+}
+
+impl SimClock {
+    pub fn new(tick_secs: f32, day_secs: f32) -> Self {
+        Self {
+            tick: 0,
+            tick_secs,
+            day_secs,
+        }
+    }
+
+    /// Tempo simulado de um tick, equivalente ao antigo `time.delta_secs()`.
+    pub fn delta_secs(&self) -> f32 {
+        self.tick_secs
+    }
+
+    pub fn elapsed_secs(&self) -> f32 {
+        (self.tick as f64 * self.tick_secs as f64) as f32
+    }
+
+    /// Dia simulado do tick, contado em ticks inteiros como `every_day`, de
+    /// modo que o dia vira no mesmo tick em que os sistemas diários rodam.
+    pub fn day(&self) -> u64 {
+        self.tick / self.ticks_for(self.day_secs)
+    }
+
+    /// Quantos ticks cabem em `secs` segundos simulados (no mínimo 1).
+    pub fn ticks_for(&self, secs: f32) -> u64 {
+        ((secs / self.tick_secs).round() as u64).max(1)
+    }
+}
+
+/// Limites de execução passados por `--ticks` e `--until-day`.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RunLimit {
+    pub ticks: Option<u64>,
+    pub until_day: Option<u64>,
+}
+
+pub fn advance_clock_system(mut clock: ResMut<SimClock>) {
+    clock.tick += 1;
+}
+
+/// Encerra o App quando algum dos limites de execução é alcançado.
+pub fn run_limit_system(
+    clock: Res<SimClock>,
+    limit: Res<RunLimit>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let ticks_reached = limit.ticks.is_some_and(|ticks| clock.tick >= ticks);
+    let day_reached = limit.until_day.is_some_and(|day| clock.day() >= day);
+    if ticks_reached || day_reached {
+        println!("Encerrando no tick {} (dia {})", clock.tick, clock.day());
+        exit.send(AppExit::Success);
+    }
+}
+
+/// Condição de execução: verdadeira a cada `secs` segundos simulados.
+pub fn every_secs(secs: f32) -> impl FnMut(Res<SimClock>) -> bool + Clone {
+    move |clock: Res<SimClock>| clock.tick > 0 && clock.tick.is_multiple_of(clock.ticks_for(secs))
+}
+
+/// Condição de execução: verdadeira no primeiro tick de cada dia simulado.
+pub fn every_day(clock: Res<SimClock>) -> bool {
+    clock.tick > 0 && clock.tick.is_multiple_of(clock.ticks_for(clock.day_secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// Mundo com um relógio de 10 ticks por dia, parado em `tick`.
+    fn world(tick: u64, limit: RunLimit) -> World {
+        let mut world = World::new();
+        let mut clock = SimClock::new(0.5, 5.0);
+        clock.tick = tick;
+        world.insert_resource(clock);
+        world.insert_resource(limit);
+        world.init_resource::<Events<AppExit>>();
+        world
+    }
+
+    fn exits(world: &mut World) -> bool {
+        world.run_system_once(run_limit_system).unwrap();
+        !world.resource::<Events<AppExit>>().is_empty()
+    }
+
+    #[test]
+    fn run_limit_stops_at_the_tick_or_the_day() {
+        let ticks = || RunLimit {
+            ticks: Some(25),
+            ..default()
+        };
+        assert!(!exits(&mut world(24, ticks())));
+        assert!(exits(&mut world(25, ticks())));
+
+        let days = || RunLimit {
+            until_day: Some(3),
+            ..default()
+        };
+        assert!(!exits(&mut world(29, days())));
+        assert!(exits(&mut world(30, days())));
+
+        // Sem limites, roda para sempre
+        assert!(!exits(&mut world(1_000_000, RunLimit::default())));
+    }
+
+    #[test]
+    fn conditions_fire_on_their_ticks_but_not_the_first() {
+        let fires = |tick: u64| {
+            let mut world = world(tick, RunLimit::default());
+            let day = world.run_system_once(every_day).unwrap();
+            let secs = world.run_system_once(every_secs(2.0)).unwrap();
+            (day, secs)
+        };
+        assert_eq!(fires(0), (false, false));
+        assert_eq!(fires(4), (false, true));
+        assert_eq!(fires(6), (false, false));
+        assert_eq!(fires(10), (true, false));
+        assert_eq!(fires(20), (true, true));
+    }
+
+    #[test]
+    fn the_day_turns_on_the_tick_the_daily_systems_run() {
+        // 600 s não é múltiplo de 0,7 s: o dia tem 857 ticks
+        let mut world = World::new();
+        let mut clock = SimClock::new(0.7, 600.0);
+        for (tick, day, fires) in [(856, 0, false), (857, 1, true), (1714, 2, true)] {
+            clock.tick = tick;
+            world.insert_resource(clock.clone());
+            assert_eq!(clock.day(), day);
+            assert_eq!(world.run_system_once(every_day).unwrap(), fires);
+        }
+
+        // Sem perder precisão depois de 2^24 ticks
+        clock.tick = 857 * 100_000 - 1;
+        assert_eq!(clock.day(), 99_999);
+        clock.tick += 1;
+        assert_eq!(clock.day(), 100_000);
+    }
+}
@@ -1,9 +1,16 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
 use bevy::{prelude::*, utils::HashMap};
 use serde::{Deserialize, Serialize};
 
-#[derive(Component, Debug)]
+use crate::calendar::Date;
+use crate::exchange::Currency;
+use crate::items::ItemId;
+use crate::taxes::TaxRates;
+use crate::welfare::WelfarePolicy;
+
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct Person {
     pub name: String,
     pub health: f32,
@@ -12,9 +19,28 @@ pub struct Person {
     pub state: PersonState,
     pub action: PersonActions,
     pub gold: usize,
-    pub inventory: HashMap<Item, i32>,
+    #[serde(with = "crate::snapshot::map_as_vec")]
+    pub inventory: HashMap<ItemId, i32>,
     pub position: Position,
-    pub planting_time: f32, // tempo acumulado em Planting (em segundos)
+    pub planting_time: f32, // tempo acumulado na lavoura atual: semeando, regando ou colhendo (em segundos)
+    /// Item alvo da ação atual: o que comprar, comer ou plantar.
+    pub target_item: Option<ItemId>,
+    /// Habilidade de trabalho, de 0 a 1. Os empregadores contratam primeiro
+    /// os mais hábeis, que também produzem mais por turno.
+    #[serde(default)]
+    pub skill: f32,
+    /// Idade em anos simulados; um ano tem as quatro estações do calendário.
+    #[serde(default = "default_age")]
+    pub age: f32,
+    /// Vale-alimentação recebido e ainda não gasto. Só paga comida comprada
+    /// de lojas; no `Ledger`, conta junto com o ouro.
+    #[serde(default)]
+    pub vouchers: usize,
+}
+
+/// Idade de quem foi gravado antes de as Persons envelhecerem: um adulto.
+fn default_age() -> f32 {
+    30.0
 }
 
 impl Default for Person {
@@ -30,6 +56,10 @@ impl Default for Person {
             inventory: HashMap::new(),
             position: Position { x: 0.0, y: 0.0 },
             planting_time: 0.0,
+            target_item: None,
+            skill: 0.5,
+            age: default_age(),
+            vouchers: 0,
         }
     }
 }
@@ -37,7 +67,7 @@ impl Default for Person {
 #[derive(Component, Debug)]
 pub struct Alive(pub bool);
 
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Position {
     pub x: f32,
     pub y: f32,
@@ -55,132 +85,196 @@ pub enum PersonActions {
     Walking,
     Eating,
     Hungry,
-    Planting,
+    /// Semeando um lote. Snapshots antigos chamam a ação de `Planting`.
+    #[serde(alias = "Planting")]
+    Seeding,
+    /// Regando a própria planta.
+    Tending,
+    /// Colhendo a própria planta madura.
+    Harvesting,
     Buying,
     Selling,
+    /// Cumprindo um turno no emprego.
+    Working,
+    /// Candidatando-se a uma vaga na cidade.
+    SeekingWork,
 }
 
-#[derive(Component)]
-pub struct Inventory(pub HashMap<String, i32>);
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Item {
-    pub name: String,
-    pub price: usize,
-    pub stock: i32,
-    pub transactions: (usize, usize), // (sales, purchases)
-    pub item_type: ItemType,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum ItemType {
-    Food { nutritional_value: u32 },
-    // Futuramente, pode ser adicionado:
-    Weapon { attack_damage: u32 },
-}
-
-impl Item {
-    // Construtor para um item do tipo Food
-    pub fn food(name: &str, price: usize, stock: i32, nutritional_value: u32) -> Self {
-        Self {
-            name: name.to_string(),
-            price,
-            stock,
-            transactions: (0, 0),
-            item_type: ItemType::Food { nutritional_value },
-        }
-    }
-
-    pub fn weapon(name: &str, price: usize, stock: i32, attack_damage: u32) -> Self {
-        Self {
-            name: name.to_string(),
-            price,
-            stock,
-            transactions: (0, 0),
-            item_type: ItemType::Weapon { attack_damage },
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceRecord {
     pub timestamp: f32,
     pub price: usize,
+    /// Data do calendário em que o preço mudou.
+    #[serde(default)]
+    pub date: Date,
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct Shop {
-    pub items: HashMap<Item, ItemDetails>,
+    #[serde(with = "crate::snapshot::map_as_vec")]
+    pub items: HashMap<ItemId, ItemDetails>,
+    /// Ouro em caixa, usado para comprar das Persons e repor o estoque.
+    #[serde(default)]
+    pub cash: usize,
     pub position: Position,
-    pub price_history: HashMap<Item, Vec<PriceRecord>>,
+    #[serde(with = "crate::snapshot::map_as_vec")]
+    pub price_history: HashMap<ItemId, Vec<PriceRecord>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemDetails {
     pub price: usize,
     pub stock: usize,
     pub transactions: (usize, usize), // (sales, purchases)
 }
 
+impl ItemDetails {
+    /// Preço pelo qual a loja compra: o de venda menos o spread.
+    pub fn bid_price(&self, spread: f32) -> usize {
+        (self.price as f32 * (1.0 - spread)).floor() as usize
+    }
+
+    pub fn ask_price(&self) -> usize {
+        self.price
+    }
+}
+
 impl fmt::Display for Shop {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Shop {{ items: {:?}, position: {:?}, price_history: {:?} }}",
-            self.items, self.position, self.price_history
+            "Shop {{ items: {:?}, cash: {}, position: {:?}, price_history: {:?} }}",
+            self.items, self.cash, self.position, self.price_history
         )
     }
 }
 
 impl Default for Shop {
     fn default() -> Self {
-        let mut items = HashMap::new();
-        // Exemplo: Item "Apple" do tipo Food com preço 10, estoque 10 e transações zeradas.
-        let apple = default_apple();
-        let apple_details = ItemDetails {
-            price: 10,
-            stock: 10,
-            transactions: (0, 0),
-        };
-        items.insert(apple.clone(), apple_details);
-
-        let mut price_history = HashMap::new();
-        price_history.insert(
-            apple,
+        Self {
+            items: HashMap::new(),
+            cash: 0,
+            position: Position { x: 100.0, y: 100.0 },
+            price_history: HashMap::new(),
+        }
+    }
+}
+
+impl Shop {
+    /// Passa a vender um item com o preço e estoque iniciais, registrando o
+    /// primeiro ponto do histórico de preços.
+    pub fn stock_item(&mut self, item: ItemId, price: usize, stock: usize) {
+        self.items.insert(
+            item,
+            ItemDetails {
+                price,
+                stock,
+                transactions: (0, 0),
+            },
+        );
+        self.price_history.insert(
+            item,
             vec![PriceRecord {
                 timestamp: 0.0,
-                price: 10,
+                price,
+                date: Date::default(),
             }],
         );
-
-        Self {
-            items,
-            position: Position { x: 100.0, y: 100.0 },
-            price_history,
-        }
     }
 }
 
-// Definição da struct Apple com valor alimentar
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub struct Apple {
-    pub nutritional_value: u32,
+/// Produtor atacadista de uma cidade: vende qualquer item do catálogo às
+/// lojas para repor estoque e compra as colheitas no mercado.
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Wholesaler {
+    pub cash: usize,
+    /// Onde fica a fazenda, para a distância até os empregados.
+    #[serde(default)]
+    pub position: Position,
+}
+
+/// Vagas de trabalho de uma loja ou atacadista.
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Employer {
+    /// Salário oferecido, em ouro por segundo trabalhado.
+    pub wage: f32,
+    /// Vagas abertas agora.
+    pub vacancies: usize,
+    /// Vagas abertas no último ajuste de salário.
+    pub posted: usize,
+    /// Candidaturas recebidas desde o último ajuste de salário.
+    pub applicants: usize,
 }
 
-// Função auxiliar para obter o item "Apple" padrão
-pub fn default_apple() -> Item {
-    // Cria um item do tipo Food com valores iniciais.
-    // Esses valores de price, stock e transações são os iniciais na loja.
-    Item::food("Apple", 10, 10, 50)
+/// Emprego de uma Person, `None` para quem não tem. Fica em toda Person,
+/// como `Alive`, para que contratar e demitir não a mude de arquétipo nem
+/// altere a ordem em que os sistemas a percorrem.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Employment(pub Option<Job>);
+
+/// Vínculo de uma Person com o empregador.
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub employer: Entity,
+    /// Tempo trabalhado no turno atual.
+    pub worked_secs: f32,
+    /// Salário ganho e ainda não pago (a fração de ouro que sobra).
+    pub accrued: f32,
+    /// Imposto de renda retido e ainda não repassado ao tesouro da cidade,
+    /// do estado e do país.
+    pub withheld: [f32; 3],
+}
+
+/// Se a Person procurou emprego e não foi contratada; conta como
+/// desempregada até ser contratada ou voltar a plantar.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct JobSeeker(pub bool);
+
+/// Parentes e casa de uma Person. Sempre presente, como `Employment`, para
+/// que nascimentos e mortes não a mudem de arquétipo. Pode apontar para
+/// parentes já mortos; quem lê confere se ainda existem.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Family {
+    pub parents: Vec<Entity>,
+    /// Filhos em ordem de nascimento.
+    pub children: Vec<Entity>,
+    pub spouse: Option<Entity>,
+    /// Casa em que a Person mora, filha da mesma cidade.
+    pub household: Option<Entity>,
+}
+
+/// Casa de uma ou mais Persons da mesma cidade, que juntam o ouro e a
+/// comida e decidem juntas quem compra, quem vende e quem planta.
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Household {
+    pub name: String,
+    /// Membros vivos, na ordem em que entraram.
+    #[serde(skip)]
+    pub members: Vec<Entity>,
+    /// Comida guardada em comum, de onde cada membro tira o que come.
+    pub pantry: BTreeMap<ItemId, i32>,
+    /// Ouro em comum, de onde sai o que cada membro gasta.
+    pub purse: usize,
 }
 
 // New City structure
-#[derive(Component, Debug, Clone)]
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
 pub struct City {
     pub name: String,
-    pub shops: Vec<Entity>,   // Store Bevy entities for shops
+    #[serde(skip)]
+    pub shops: Vec<Entity>, // Store Bevy entities for shops
+    #[serde(skip)]
     pub persons: Vec<Entity>, // Store Bevy entities for persons
     pub position: Position,
+    /// Ouro do tesouro da cidade, que recebe as heranças sem herdeiros e
+    /// os impostos da cidade.
+    #[serde(default)]
+    pub treasury: usize,
+    /// Impostos que a cidade cobra e benefícios que paga.
+    #[serde(default)]
+    pub taxes: TaxRates,
+    #[serde(default)]
+    pub welfare: WelfarePolicy,
 }
 
 impl Default for City {
@@ -190,19 +284,95 @@ impl Default for City {
             shops: Vec::new(),
             persons: Vec::new(),
             position: Position { x: 0.0, y: 0.0 },
+            treasury: 0,
+            taxes: TaxRates::default(),
+            welfare: WelfarePolicy::default(),
+        }
+    }
+}
+
+/// Lote de cultivo de uma cidade. Só se planta em um lote próprio ou
+/// alugado.
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct Plot {
+    /// Terreno do estado da cidade.
+    pub terrain: TerrainType,
+    /// Multiplica a colheita de qualquer cultura plantada no lote.
+    pub fertility: f32,
+    /// Dono do lote. Um lote sem dono fica com o primeiro que plantar nele.
+    #[serde(skip)]
+    pub owner: Option<Entity>,
+    /// Quem está plantando no lote agora.
+    #[serde(skip)]
+    pub farmer: Option<Entity>,
+    /// Cultura plantada agora.
+    pub crop: Option<ItemId>,
+    /// Se quem planta agora paga o aluguel com parte da colheita.
+    #[serde(default)]
+    pub sharecropped: bool,
+}
+
+/// Estágio de crescimento de uma planta.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrowthStage {
+    /// Primeira quarta parte do crescimento.
+    Seedling,
+    Growing,
+    /// Pronta para a colheita.
+    Ripe,
+}
+
+/// Planta semeada em um lote, filha dele na hierarquia. Cresce enquanto tem
+/// água; com sede, para de crescer e perde vigor.
+#[derive(Component, Debug, Clone, Serialize, Deserialize)]
+pub struct Plant {
+    pub crop: ItemId,
+    pub stage: GrowthStage,
+    /// Segundos de crescimento; a planta amadurece no `grow_secs` da cultura.
+    pub growth: f32,
+    /// Umidade do solo, de 0 a 1. Cai com o tempo e volta a 1 quando regada.
+    pub water: f32,
+    /// De 0 a 1. Cai enquanto a planta passa sede e multiplica a colheita;
+    /// em 0 a planta morre.
+    pub vigor: f32,
+    /// Segundos desde a semeadura.
+    pub age: f32,
+    /// Segundos desde que amadureceu; passado `rot_secs`, a colheita se perde.
+    pub ripe_secs: f32,
+}
+
+impl Plant {
+    pub fn new(crop: ItemId) -> Self {
+        Self {
+            crop,
+            stage: GrowthStage::Seedling,
+            growth: 0.0,
+            water: 1.0,
+            vigor: 1.0,
+            age: 0.0,
+            ripe_secs: 0.0,
         }
     }
 }
 
 // Estate structure
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct State {
     pub name: String,
+    #[serde(skip)]
     pub cities: Vec<Entity>, // Store Bevy entities for cities
     pub terrain_type: TerrainType,
+    /// Ouro do tesouro do estado, os impostos que ele cobra e os benefícios
+    /// que paga.
+    #[serde(default)]
+    pub treasury: usize,
+    #[serde(default)]
+    pub taxes: TaxRates,
+    #[serde(default)]
+    pub welfare: WelfarePolicy,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TerrainType {
     Grassland,
     Forest,
@@ -216,17 +386,32 @@ impl Default for State {
             name: "Default Estate".to_string(),
             cities: Vec::new(),
             terrain_type: TerrainType::Grassland,
+            treasury: 0,
+            taxes: TaxRates::default(),
+            welfare: WelfarePolicy::default(),
         }
     }
 }
 
 // Country structure
-#[derive(Component)]
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct Country {
     pub name: String,
+    #[serde(skip)]
     pub estates: Vec<Entity>, // Store Bevy entities for estates
     pub population: usize,
     pub total_gold: usize,
+    /// Moeda do país, em que ficam os saldos de quem mora nele.
+    #[serde(default)]
+    pub currency: Currency,
+    /// Ouro do tesouro do país, os impostos que ele cobra e os benefícios
+    /// que paga.
+    #[serde(default)]
+    pub treasury: usize,
+    #[serde(default)]
+    pub taxes: TaxRates,
+    #[serde(default)]
+    pub welfare: WelfarePolicy,
 }
 
 impl Default for Country {
@@ -236,6 +421,10 @@ impl Default for Country {
             estates: Vec::new(),
             population: 0,
             total_gold: 0,
+            currency: Currency::default(),
+            treasury: 0,
+            taxes: TaxRates::default(),
+            welfare: WelfarePolicy::default(),
         }
     }
 }
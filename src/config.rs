@@ -0,0 +1,640 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::audit::AuditConfig;
+use crate::banks::BanksConfig;
+use crate::calendar::CalendarConfig;
+use crate::crops::CropsConfig;
+use crate::demography::DemographyConfig;
+use crate::exchange::ExchangeConfig;
+use crate::household::HouseholdConfig;
+use crate::labor::LaborConfig;
+use crate::land::LandConfig;
+use crate::ledger::LedgerConfig;
+use crate::metrics::MetricsConfig;
+use crate::monetary::MonetaryConfig;
+use crate::shocks::{Shock, ShockEvent};
+use crate::shops::ShopConfig;
+use crate::taxes::{Tax, TaxConfig, TaxLevel};
+use crate::weather::WeatherConfig;
+use crate::welfare::WelfareConfig;
+
+/// Parâmetros que definem uma execução da simulação.
+///
+/// Carregado de um arquivo TOML, RON ou JSON (escolhido pela extensão) para que
+/// cenários diferentes possam rodar no mesmo binário. Campos ausentes usam os
+/// valores de `ScenarioConfig::default()`.
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioConfig {
+    /// Semente do `SimRng`. Sem semente, uma é sorteada e impressa no início.
+    pub seed: Option<u64>,
+    pub num_countries: usize,
+    pub num_states: usize,
+    pub num_cities: usize,
+    pub num_persons: usize,
+    pub num_shops: usize,
+    pub start_gold: usize,
+    pub transaction_threshold: usize,
+    pub person_hungry_threshold: f32,
+    /// Segundos simulados por tick.
+    pub tick_secs: f32,
+    /// Segundos simulados em um dia.
+    pub day_secs: f32,
+    /// Se verdadeiro, cada tick espera `tick_secs` de tempo real; caso
+    /// contrário a simulação roda o mais rápido possível.
+    pub realtime: bool,
+    /// Arquivo com o catálogo de itens. Sem ele, usa `data/items.toml`
+    /// embutido no binário.
+    pub catalog: Option<PathBuf>,
+    /// Intervalo, em segundos simulados, entre recálculos dos indicadores
+    /// macroeconômicos.
+    pub indicator_secs: f32,
+    /// Caixa, spread e reposição das lojas.
+    pub shops: ShopConfig,
+    /// Vagas, turnos e salários.
+    pub labor: LaborConfig,
+    /// Lotes de cultivo e fertilidade de cada terreno.
+    pub land: LandConfig,
+    /// Estações do ano e seus efeitos.
+    pub calendar: CalendarConfig,
+    /// Chuva, seca, geada e calor em cada estado.
+    pub weather: WeatherConfig,
+    /// Linha do tempo de choques aplicados durante a execução.
+    pub shocks: Vec<Shock>,
+    /// Semeadura, rega, crescimento e colheita das plantas.
+    pub crops: CropsConfig,
+    /// Idades, nascimentos e mortes por velhice.
+    pub demography: DemographyConfig,
+    /// Casais e despensa das casas.
+    pub households: HouseholdConfig,
+    /// Depósitos, empréstimos e juros dos bancos.
+    pub banks: BanksConfig,
+    /// Regra do banco central de cada país.
+    pub monetary: MonetaryConfig,
+    /// Câmbio entre as moedas dos países e importações das lojas.
+    pub exchange: ExchangeConfig,
+    /// Impostos das cidades, estados e países.
+    pub taxes: TaxConfig,
+    /// Renda básica, vale-alimentação e seguro-desemprego pagos pelos
+    /// tesouros.
+    pub welfare: WelfareConfig,
+    /// Séries temporais gravadas em CSV a cada dia e ao encerrar.
+    pub metrics: MetricsConfig,
+    /// Retenção do diário de transferências.
+    pub ledger: LedgerConfig,
+    /// Conferência de ouro e invariantes a cada tick.
+    pub audit: AuditConfig,
+}
+
+impl Default for ScenarioConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            num_countries: 2,
+            num_states: 5,
+            num_cities: 10,
+            num_persons: 2000,
+            num_shops: 50,
+            start_gold: 20,
+            transaction_threshold: 100,
+            person_hungry_threshold: 30.0,
+            tick_secs: 0.25,
+            day_secs: 600.0,
+            realtime: false,
+            catalog: None,
+            indicator_secs: 10.0,
+            shops: ShopConfig::default(),
+            labor: LaborConfig::default(),
+            land: LandConfig::default(),
+            calendar: CalendarConfig::default(),
+            weather: WeatherConfig::default(),
+            shocks: Vec::new(),
+            crops: CropsConfig::default(),
+            demography: DemographyConfig::default(),
+            households: HouseholdConfig::default(),
+            banks: BanksConfig::default(),
+            monetary: MonetaryConfig::default(),
+            exchange: ExchangeConfig::default(),
+            taxes: TaxConfig::default(),
+            welfare: WelfareConfig::default(),
+            metrics: MetricsConfig::default(),
+            ledger: LedgerConfig::default(),
+            audit: AuditConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        message: String,
+    },
+    UnsupportedFormat {
+        path: PathBuf,
+    },
+    Invalid {
+        field: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "could not read {}: {}", path.display(), source)
+            }
+            ConfigError::Parse { path, message } => {
+                write!(f, "could not parse {}: {}", path.display(), message)
+            }
+            ConfigError::UnsupportedFormat { path } => write!(
+                f,
+                "unsupported format for {} (expected .toml, .ron or .json)",
+                path.display()
+            ),
+            ConfigError::Invalid { field, reason } => {
+                write!(f, "invalid value for `{}`: {}", field, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Lê um arquivo de dados em TOML, RON ou JSON, escolhendo o formato pela
+/// extensão.
+pub fn read_data_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |message: String| ConfigError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&text).map_err(|e| parse_error(e.to_string())),
+        Some("ron") => ron::from_str(&text).map_err(|e| parse_error(e.to_string())),
+        Some("json") => serde_json::from_str(&text).map_err(|e| parse_error(e.to_string())),
+        _ => Err(ConfigError::UnsupportedFormat {
+            path: path.to_path_buf(),
+        }),
+    }
+}
+
+impl ScenarioConfig {
+    /// Lê e valida um cenário a partir de um arquivo.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let config: ScenarioConfig = read_data_file(path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Verifica se os valores fazem sentido antes de iniciar a simulação.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let at_least_one = |field: &str, value: usize| {
+            if value == 0 {
+                Err(ConfigError::Invalid {
+                    field: field.to_string(),
+                    reason: "must be at least 1".to_string(),
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        at_least_one("num_countries", self.num_countries)?;
+        at_least_one("num_states", self.num_states)?;
+        at_least_one("num_cities", self.num_cities)?;
+        at_least_one("transaction_threshold", self.transaction_threshold)?;
+        at_least_one("shops.restock_batch", self.shops.restock_batch)?;
+        at_least_one("metrics.every_ticks", self.metrics.every_ticks as usize)?;
+        at_least_one(
+            "calendar.days_per_season",
+            self.calendar.days_per_season as usize,
+        )?;
+
+        let in_range = |field: &str, value: f32, max: f32| {
+            if value.is_nan() || value < 0.0 || value >= max {
+                Err(ConfigError::Invalid {
+                    field: field.to_string(),
+                    reason: format!("must be in [0, {}), got {}", max, value),
+                })
+            } else {
+                Ok(())
+            }
+        };
+        in_range("shops.spread", self.shops.spread, 1.0)?;
+        in_range(
+            "shops.wholesale_price",
+            self.shops.wholesale_price,
+            f32::INFINITY,
+        )?;
+        in_range("shops.crop_price", self.shops.crop_price, f32::INFINITY)?;
+        in_range("labor.min_wage", self.labor.min_wage, f32::INFINITY)?;
+        in_range("labor.base_wage", self.labor.base_wage, f32::INFINITY)?;
+        in_range("labor.wage_step", self.labor.wage_step, 1.0)?;
+        in_range("labor.commute_cost", self.labor.commute_cost, f32::INFINITY)?;
+        in_range("land.fertility_spread", self.land.fertility_spread, 1.0)?;
+        in_range("land.crop_share", self.land.crop_share, 1.0)?;
+        for (terrain, fertility) in self.land.fertility.values() {
+            in_range(
+                &format!("land.fertility.{}", terrain),
+                fertility,
+                f32::INFINITY,
+            )?;
+        }
+        for (season, factor) in self.calendar.hunger.values() {
+            in_range(
+                &format!("calendar.hunger.{}", season),
+                factor,
+                f32::INFINITY,
+            )?;
+        }
+        in_range("crops.seed_secs", self.crops.seed_secs, f32::INFINITY)?;
+        in_range("crops.tend_secs", self.crops.tend_secs, f32::INFINITY)?;
+        in_range("crops.harvest_secs", self.crops.harvest_secs, f32::INFINITY)?;
+        in_range("crops.tend_below", self.crops.tend_below, 1.0)?;
+        in_range("crops.rot_secs", self.crops.rot_secs, f32::INFINITY)?;
+        for (field, value) in [
+            ("demography.adult_age", self.demography.adult_age),
+            ("demography.start_age_min", self.demography.start_age_min),
+            ("demography.fertile_from", self.demography.fertile_from),
+            ("demography.birth_rate", self.demography.birth_rate),
+            ("demography.mortality_base", self.demography.mortality_base),
+            (
+                "demography.mortality_growth",
+                self.demography.mortality_growth,
+            ),
+        ] {
+            in_range(field, value, f32::INFINITY)?;
+        }
+        if !(0.0..=1.0).contains(&self.households.couple_share) {
+            return Err(ConfigError::Invalid {
+                field: "households.couple_share".to_string(),
+                reason: format!("must be in [0, 1], got {}", self.households.couple_share),
+            });
+        }
+        if self.households.pantry_reserve < 0 {
+            return Err(ConfigError::Invalid {
+                field: "households.pantry_reserve".to_string(),
+                reason: format!(
+                    "must not be negative, got {}",
+                    self.households.pantry_reserve
+                ),
+            });
+        }
+        for (field, value) in [
+            ("banks.deposit_rate", self.banks.deposit_rate),
+            ("banks.loan_rate", self.banks.loan_rate),
+            ("banks.repaid_score", self.banks.repaid_score),
+            ("banks.missed_score", self.banks.missed_score),
+            ("banks.default_score", self.banks.default_score),
+        ] {
+            in_range(field, value, f32::INFINITY)?;
+        }
+        for (field, value) in [
+            ("banks.reserve_ratio", self.banks.reserve_ratio),
+            ("banks.min_score", self.banks.min_score),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(ConfigError::Invalid {
+                    field: field.to_string(),
+                    reason: format!("must be in [0, 1], got {}", value),
+                });
+            }
+        }
+        at_least_one("banks.loan_days", self.banks.loan_days as usize)?;
+        at_least_one("banks.default_after", self.banks.default_after as usize)?;
+        if self.banks.household_keep < self.banks.household_borrow_below
+            || self.banks.shop_keep < self.banks.shop_borrow_below
+        {
+            return Err(ConfigError::Invalid {
+                field: "banks".to_string(),
+                reason: "household_keep and shop_keep must not be below the borrow thresholds"
+                    .to_string(),
+            });
+        }
+        let policies = std::iter::once(("default".to_string(), &self.monetary.default)).chain(
+            self.monetary
+                .countries
+                .iter()
+                .map(|(name, policy)| (format!("countries.{}", name), policy)),
+        );
+        for (name, policy) in policies {
+            let field = |key: &str| format!("monetary.{}.{}", name, key);
+            for (key, value) in [
+                ("neutral_rate", policy.neutral_rate),
+                ("target_inflation", policy.target_inflation),
+                ("inflation_weight", policy.inflation_weight),
+                ("min_rate", policy.min_rate),
+            ] {
+                in_range(&field(key), value, f32::INFINITY)?;
+            }
+            if !(policy.max_rate >= policy.min_rate && policy.max_rate.is_finite()) {
+                return Err(ConfigError::Invalid {
+                    field: field("max_rate"),
+                    reason: format!(
+                        "must be at least {}, got {}",
+                        policy.min_rate, policy.max_rate
+                    ),
+                });
+            }
+            for (key, value) in [
+                ("money_growth", policy.money_growth),
+                ("min_growth", policy.min_growth),
+            ] {
+                if !(value > -1.0 && value < 1.0) {
+                    return Err(ConfigError::Invalid {
+                        field: field(key),
+                        reason: format!("must be in (-1, 1), got {}", value),
+                    });
+                }
+            }
+            if !(policy.max_growth >= policy.min_growth && policy.max_growth < 1.0) {
+                return Err(ConfigError::Invalid {
+                    field: field("max_growth"),
+                    reason: format!(
+                        "must be in [{}, 1), got {}",
+                        policy.min_growth, policy.max_growth
+                    ),
+                });
+            }
+        }
+        in_range("exchange.spread", self.exchange.spread, f32::INFINITY)?;
+        in_range("exchange.sensitivity", self.exchange.sensitivity, 1.0)?;
+        at_least_one("exchange.import_batch", self.exchange.import_batch)?;
+        for tax in [Tax::Sales, Tax::Income, Tax::Wealth, Tax::Land] {
+            let mut total = 0.0;
+            for level in [TaxLevel::City, TaxLevel::State, TaxLevel::Country] {
+                let rate = self.taxes.rates(level).rate(tax);
+                in_range(
+                    &format!("taxes.{}.{}", level.key(), tax.key()),
+                    rate,
+                    f32::INFINITY,
+                )?;
+                total += rate;
+            }
+            if tax != Tax::Land && total > 1.0 {
+                return Err(ConfigError::Invalid {
+                    field: format!("taxes.*.{}", tax.key()),
+                    reason: format!("rates must add up to at most 1, got {}", total),
+                });
+            }
+        }
+        // Os países se chamam "Country 0", "Country 1", ...
+        let countries: Vec<String> = (0..self.num_countries)
+            .map(|i| format!("Country {}", i))
+            .collect();
+        if let Some(name) = self
+            .monetary
+            .countries
+            .keys()
+            .find(|name| !countries.contains(name))
+        {
+            return Err(ConfigError::Invalid {
+                field: format!("monetary.countries.{}", name),
+                reason: format!("no such country among {}", countries.join(", ")),
+            });
+        }
+        for (field, min, max) in [
+            (
+                "demography.start_age_max",
+                self.demography.start_age_min,
+                self.demography.start_age_max,
+            ),
+            (
+                "demography.fertile_until",
+                self.demography.fertile_from,
+                self.demography.fertile_until,
+            ),
+        ] {
+            if !(max >= min && max.is_finite()) {
+                return Err(ConfigError::Invalid {
+                    field: field.to_string(),
+                    reason: format!("must be at least {}, got {}", min, max),
+                });
+            }
+        }
+        for (terrain, odds) in self.weather.terrains() {
+            for (kind, chance) in odds.values() {
+                in_range(
+                    &format!("weather.{}.{}", terrain, kind.key()),
+                    chance,
+                    f32::INFINITY,
+                )?;
+            }
+            let total: f32 = odds.values().iter().map(|(_, chance)| chance).sum();
+            if total > 1.0 {
+                return Err(ConfigError::Invalid {
+                    field: format!("weather.{}", terrain),
+                    reason: format!("chances must add up to at most 1, got {}", total),
+                });
+            }
+        }
+        for (kind, effects) in self.weather.kinds() {
+            for (field, value) in [
+                ("growth", effects.growth),
+                ("damage", effects.damage),
+                ("hunger", effects.hunger),
+                ("energy", effects.energy),
+            ] {
+                in_range(
+                    &format!("weather.{}.{}", kind.key(), field),
+                    value,
+                    f32::INFINITY,
+                )?;
+            }
+        }
+        for (i, shock) in self.shocks.iter().enumerate() {
+            in_range(&format!("shocks[{}].day", i), shock.day, f32::INFINITY)?;
+            match shock.event {
+                ShockEvent::DestroyStock { fraction, .. } if !(0.0..=1.0).contains(&fraction) => {
+                    return Err(ConfigError::Invalid {
+                        field: format!("shocks[{}].event.destroy_stock.fraction", i),
+                        reason: format!("must be in [0, 1], got {}", fraction),
+                    });
+                }
+                ShockEvent::SetTax { tax, rate, .. } => {
+                    let max = if tax == Tax::Land { f32::INFINITY } else { 1.0 };
+                    if rate.is_nan() || rate < 0.0 || rate > max {
+                        return Err(ConfigError::Invalid {
+                            field: format!("shocks[{}].event.set_tax.rate", i),
+                            reason: format!("must be in [0, {}], got {}", max, rate),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (field, value) in [
+            ("weather.spell_secs", self.weather.spell_secs),
+            ("crops.dry_secs", self.crops.dry_secs),
+            ("crops.wilt_secs", self.crops.wilt_secs),
+        ] {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(ConfigError::Invalid {
+                    field: field.to_string(),
+                    reason: format!("must be positive, got {}", value),
+                });
+            }
+        }
+
+        if !(self.person_hungry_threshold > 0.0 && self.person_hungry_threshold < 100.0) {
+            return Err(ConfigError::Invalid {
+                field: "person_hungry_threshold".to_string(),
+                reason: format!(
+                    "must be between 0 and 100 (exclusive), got {}",
+                    self.person_hungry_threshold
+                ),
+            });
+        }
+
+        if self.tick_secs.is_nan() || self.tick_secs <= 0.0 {
+            return Err(ConfigError::Invalid {
+                field: "tick_secs".to_string(),
+                reason: format!("must be greater than 0, got {}", self.tick_secs),
+            });
+        }
+
+        if self.day_secs.is_nan() || self.day_secs < self.tick_secs {
+            return Err(ConfigError::Invalid {
+                field: "day_secs".to_string(),
+                reason: format!(
+                    "must be at least one tick ({} s), got {}",
+                    self.tick_secs, self.day_secs
+                ),
+            });
+        }
+
+        if self.indicator_secs.is_nan() || self.indicator_secs < self.tick_secs {
+            return Err(ConfigError::Invalid {
+                field: "indicator_secs".to_string(),
+                reason: format!(
+                    "must be at least one tick ({} s), got {}",
+                    self.tick_secs, self.indicator_secs
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grava `text` em um arquivo temporário com a extensão dada e tenta
+    /// carregar o cenário dele.
+    fn load_text(name: &str, text: &str) -> Result<ScenarioConfig, ConfigError> {
+        let path =
+            std::env::temp_dir().join(format!("economy-config-{}-{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        let result = ScenarioConfig::load(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    fn invalid_field(result: Result<ScenarioConfig, ConfigError>) -> String {
+        match result {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            other => panic!("expected an invalid field, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn bundled_scenarios_load_and_validate() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if let Err(err) = ScenarioConfig::load(&path) {
+                panic!("{}", err);
+            }
+        }
+        assert_eq!(ScenarioConfig::default().validate().ok(), Some(()));
+    }
+
+    #[test]
+    fn each_format_fills_missing_fields_with_defaults() {
+        let toml = load_text("a.toml", "num_persons = 10\n[shops]\nspread = 0.5\n").unwrap();
+        let ron = load_text("a.ron", "(num_persons: 10, shops: (spread: 0.5))").unwrap();
+        let json = load_text("a.json", r#"{"num_persons": 10, "shops": {"spread": 0.5}}"#).unwrap();
+        assert_eq!(toml, ron);
+        assert_eq!(toml, json);
+        assert_eq!(toml.num_persons, 10);
+        assert_eq!(toml.num_cities, ScenarioConfig::default().num_cities);
+    }
+
+    #[test]
+    fn unknown_fields_and_formats_are_rejected() {
+        // Um erro de digitação não pode virar o valor padrão em silêncio
+        let err = load_text("typo.toml", "num_person = 10\n").unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+        assert!(err.to_string().contains("num_person"), "{}", err);
+
+        let err = load_text("nested.toml", "[shops]\nsprea = 0.1\n").unwrap_err();
+        assert!(err.to_string().contains("sprea"), "{}", err);
+
+        let err = load_text("a.yaml", "num_persons: 10\n").unwrap_err();
+        assert!(matches!(err, ConfigError::UnsupportedFormat { .. }));
+
+        let err = ScenarioConfig::load(Path::new("no/such/scenario.toml")).unwrap_err();
+        assert!(matches!(err, ConfigError::Io { .. }));
+    }
+
+    #[test]
+    fn validation_names_the_bad_field() {
+        assert_eq!(
+            invalid_field(load_text("b.toml", "num_cities = 0\n")),
+            "num_cities"
+        );
+        assert_eq!(
+            invalid_field(load_text("c.toml", "[shops]\nspread = 1.5\n")),
+            "shops.spread"
+        );
+        assert_eq!(
+            invalid_field(load_text("d.toml", "tick_secs = 1.0\nday_secs = 0.5\n")),
+            "day_secs"
+        );
+        assert_eq!(
+            invalid_field(load_text(
+                "e.toml",
+                "[taxes.city]\nsales = 0.6\n[taxes.country]\nsales = 0.6\n"
+            )),
+            "taxes.*.sales"
+        );
+        assert_eq!(
+            invalid_field(load_text(
+                "f.toml",
+                "[monetary.countries.\"Country 9\"]\nneutral_rate = 0.0\n"
+            )),
+            "monetary.countries.Country 9"
+        );
+        assert_eq!(
+            invalid_field(load_text(
+                "g.toml",
+                "[monetary.default]\nmin_growth = -0.1\nmax_growth = -0.2\n"
+            )),
+            "monetary.default.max_growth"
+        );
+
+        let err = load_text("h.toml", "person_hungry_threshold = 120.0\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value for `person_hungry_threshold`: must be between 0 and 100 \
+             (exclusive), got 120"
+        );
+    }
+}
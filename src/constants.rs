@@ -1,10 +0,0 @@
-pub const NUM_COUNTRIES: usize = 2;
-pub const NUM_STATES: usize = 5;
-pub const NUM_CITIES: usize = 10;
-
-pub const NUM_PERSONS: usize = 2000;
-pub const NUM_SHOPS: usize = 50;
-// pub const NUM_CITIES: usize = 10;
-pub const START_GOLD: usize = 20;
-pub const TRANSACTION_THRESHOLD: usize = 100;
-pub const PERSON_HUNGRY_THRESHOLD: f32 = 30.0;
@@ -0,0 +1,288 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::Calendar;
+use crate::clock::SimClock;
+use crate::components::{City, GrowthStage, Person, PersonActions, Plant, Plot};
+use crate::config::ScenarioConfig;
+use crate::events::{HarvestEvent, ProductionEvent};
+use crate::items::{ItemCatalog, ItemId};
+use crate::land;
+use crate::weather::{city_effects, Weather, WeatherEffects};
+
+/// Parâmetros do ciclo das plantações.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CropsConfig {
+    /// Tempo para semear um lote.
+    pub seed_secs: f32,
+    /// Tempo para regar uma planta.
+    pub tend_secs: f32,
+    /// Tempo para colher uma planta madura.
+    pub harvest_secs: f32,
+    /// Tempo que a água de uma rega dura no solo.
+    pub dry_secs: f32,
+    /// Umidade abaixo da qual o lavrador volta para regar.
+    pub tend_below: f32,
+    /// Tempo de sede que mata uma planta com vigor 1.
+    pub wilt_secs: f32,
+    /// Tempo que uma planta madura espera a colheita antes de apodrecer.
+    pub rot_secs: f32,
+}
+
+impl Default for CropsConfig {
+    fn default() -> Self {
+        Self {
+            seed_secs: 2.0,
+            tend_secs: 1.0,
+            harvest_secs: 2.0,
+            dry_secs: 8.0,
+            tend_below: 0.5,
+            wilt_secs: 10.0,
+            rot_secs: 30.0,
+        }
+    }
+}
+
+impl CropsConfig {
+    /// Tempo de cada trabalho na lavoura.
+    pub fn work_secs(&self, action: PersonActions) -> Option<f32> {
+        match action {
+            PersonActions::Seeding => Some(self.seed_secs),
+            PersonActions::Tending => Some(self.tend_secs),
+            PersonActions::Harvesting => Some(self.harvest_secs),
+            _ => None,
+        }
+    }
+}
+
+impl Plant {
+    /// Avança `dt` segundos: o solo seca, a planta cresce enquanto tem água e,
+    /// com sede ou fora da sua estação, perde vigor. O tempo acelera ou freia
+    /// o crescimento e a secagem e pode ferir a planta. Madura, só espera a
+    /// colheita.
+    pub fn grow(
+        &mut self,
+        dt: f32,
+        grow_secs: f32,
+        in_season: bool,
+        weather: &WeatherEffects,
+        config: &CropsConfig,
+    ) {
+        self.age += dt;
+        self.water = (self.water - dt * weather.drying / config.dry_secs).clamp(0.0, 1.0);
+        if self.stage == GrowthStage::Ripe {
+            self.ripe_secs += dt;
+            return;
+        }
+        let mut wilt = weather.damage;
+        if self.water > 0.0 && in_season {
+            self.growth += dt * weather.growth;
+        } else {
+            wilt += 1.0;
+        }
+        self.vigor = (self.vigor - dt * wilt / config.wilt_secs).max(0.0);
+        self.stage = if self.growth >= grow_secs {
+            GrowthStage::Ripe
+        } else if self.growth >= grow_secs / 4.0 {
+            GrowthStage::Growing
+        } else {
+            GrowthStage::Seedling
+        };
+    }
+
+    /// A planta secou de vez ou apodreceu sem ser colhida.
+    pub fn is_dead(&self, config: &CropsConfig) -> bool {
+        self.vigor <= 0.0 || self.ripe_secs > config.rot_secs
+    }
+}
+
+/// Faz crescer as plantas de todos os lotes, sob o tempo do estado. As que
+/// morrem de sede, de frio ou apodrecem somem e liberam o lote.
+#[allow(clippy::too_many_arguments)]
+pub fn growth_system(
+    mut commands: Commands,
+    mut plants: Query<(Entity, &mut Plant, &Parent)>,
+    mut plots: Query<(&mut Plot, &Parent)>,
+    cities: Query<(Entity, &Parent), With<City>>,
+    states: Query<&Weather>,
+    catalog: Res<ItemCatalog>,
+    calendar: Res<Calendar>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+) {
+    let dt = clock.delta_secs();
+    let weather = city_effects(&cities, &states, &config.weather);
+    for (entity, mut plant, plot_entity) in plants.iter_mut() {
+        let crop = catalog.get(plant.crop);
+        let in_season = crop.grows_in(calendar.season());
+        let effects = plots
+            .get(plot_entity.get())
+            .ok()
+            .and_then(|(_, city)| weather.get(&city.get()).copied())
+            .unwrap_or_default();
+        plant.grow(dt, crop.grow_secs, in_season, &effects, &config.crops);
+        if !plant.is_dead(&config.crops) {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        if let Ok((mut plot, _)) = plots.get_mut(plot_entity.get()) {
+            plot.farmer = None;
+            plot.crop = None;
+            plot.sharecropped = false;
+        }
+    }
+}
+
+/// Trabalho na lavoura, como os passos de plantar e colher das árvores de
+/// `beings.py`. Passado o tempo de cada ação, quem está `Seeding` semeia a
+/// cultura escolhida no seu lote, quem está `Tending` rega a planta e quem
+/// está `Harvesting` colhe a planta madura e libera o lote; o meeiro entrega
+/// parte da colheita ao dono. Em seguida a Person volta a `Idle`.
+#[allow(clippy::too_many_arguments)]
+pub fn fieldwork_system(
+    mut commands: Commands,
+    mut persons: Query<(Entity, &mut Person, &Parent)>,
+    mut plots: Query<(Entity, &mut Plot, Option<&Children>)>,
+    mut plants: Query<&mut Plant>,
+    catalog: Res<ItemCatalog>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    mut production: EventWriter<ProductionEvent>,
+    mut harvests: EventWriter<HarvestEvent>,
+) {
+    let farmed: HashMap<Entity, Entity> = plots
+        .iter()
+        .filter_map(|(entity, plot, _)| plot.farmer.map(|farmer| (farmer, entity)))
+        .collect();
+
+    let mut shares: Vec<(Entity, ItemId, i32)> = Vec::new();
+    for (entity, mut person, city) in persons.iter_mut() {
+        let Some(secs) = config.crops.work_secs(person.action) else {
+            person.planting_time = 0.0;
+            continue;
+        };
+        person.planting_time += clock.delta_secs();
+        if person.planting_time < secs {
+            continue;
+        }
+
+        let plot = farmed
+            .get(&entity)
+            .and_then(|&plot| plots.get_mut(plot).ok());
+        if let Some((plot_entity, mut plot, children)) = plot {
+            let plant = children
+                .and_then(|children| children.iter().copied().find(|&c| plants.contains(c)));
+            match (person.action, plant) {
+                (PersonActions::Seeding, None) => {
+                    if let Some(crop) = plot.crop {
+                        commands.entity(plot_entity).with_children(|parent| {
+                            parent.spawn(Plant::new(crop));
+                        });
+                    }
+                }
+                (PersonActions::Tending, Some(plant)) => {
+                    if let Ok(mut plant) = plants.get_mut(plant) {
+                        plant.water = 1.0;
+                    }
+                }
+                (PersonActions::Harvesting, Some(plant_entity)) => {
+                    let plant = plants
+                        .get(plant_entity)
+                        .ok()
+                        .filter(|plant| plant.stage == GrowthStage::Ripe);
+                    if let Some(plant) = plant {
+                        let crop = plant.crop;
+                        let units = land::harvest_units(&plot, &catalog, crop, plant.vigor);
+                        let share = land::owner_share(units, &plot, config.land.crop_share);
+                        if share > 0 {
+                            shares.extend(plot.owner.map(|owner| (owner, crop, share)));
+                        }
+                        if units > 0 {
+                            *person.inventory.entry(crop).or_insert(0) += units - share;
+                            production.send(ProductionEvent {
+                                person: entity,
+                                item: crop,
+                                quantity: units as usize,
+                            });
+                            harvests.send(HarvestEvent {
+                                city: city.get(),
+                                item: crop,
+                                quantity: units as usize,
+                                age_secs: plant.age,
+                            });
+                        }
+                        commands.entity(plant_entity).despawn_recursive();
+                        plot.farmer = None;
+                        plot.crop = None;
+                        plot.sharecropped = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        person.planting_time = 0.0;
+        person.action = PersonActions::Idle;
+        person.target_item = None;
+    }
+
+    for (owner, crop, share) in shares {
+        if let Ok((_, mut owner, _)) = persons.get_mut(owner) {
+            *owner.inventory.entry(crop).or_insert(0) += share;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::WeatherConfig;
+
+    #[test]
+    fn plants_ripen_with_water_and_wilt_without_it() {
+        let config = CropsConfig::default();
+        let catalog = ItemCatalog::default();
+        let corn = catalog.find("Corn").unwrap();
+        let clear = WeatherEffects::default();
+
+        // Regada, amadurece no tempo da cultura e depois apodrece
+        let mut plant = Plant::new(corn);
+        plant.grow(2.0, 6.0, true, &clear, &config);
+        assert_eq!(plant.stage, GrowthStage::Growing);
+        plant.grow(4.0, 6.0, true, &clear, &config);
+        assert_eq!(plant.stage, GrowthStage::Ripe);
+        assert_eq!(plant.vigor, 1.0);
+        plant.grow(config.rot_secs + 1.0, 6.0, true, &clear, &config);
+        assert!(plant.is_dead(&config));
+
+        // Sem rega, para de crescer e seca
+        let mut plant = Plant::new(corn);
+        plant.grow(7.0, 20.0, true, &clear, &config);
+        assert_eq!(plant.growth, 7.0);
+        plant.grow(5.0, 20.0, true, &clear, &config);
+        assert_eq!(plant.water, 0.0);
+        assert_eq!(plant.growth, 7.0);
+        assert!((plant.vigor - 0.5).abs() < 1e-6);
+        assert!(!plant.is_dead(&config));
+        plant.grow(5.0, 20.0, true, &clear, &config);
+        assert!(plant.is_dead(&config));
+
+        // Fora da estação, nem regada ela cresce
+        let mut plant = Plant::new(corn);
+        plant.grow(5.0, 20.0, false, &clear, &config);
+        assert_eq!(plant.growth, 0.0);
+        assert!((plant.vigor - 0.5).abs() < 1e-6);
+
+        // A geada freia o crescimento e fere a planta; a chuva molha o solo
+        let frost = WeatherConfig::default().frost;
+        let mut plant = Plant::new(corn);
+        plant.grow(5.0, 20.0, true, &frost, &config);
+        assert!(plant.growth < 5.0);
+        assert!(plant.vigor < 1.0);
+        let rain = WeatherConfig::default().rain;
+        plant.water = 0.0;
+        plant.grow(1.0, 20.0, true, &rain, &config);
+        assert!(plant.water > 0.0);
+    }
+}
@@ -0,0 +1,290 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SimClock;
+use crate::components::{Alive, City, Employment, Family, Household, JobSeeker, Person};
+use crate::config::ScenarioConfig;
+use crate::events::{BirthEvent, DeathEvent};
+use crate::items::ItemCatalog;
+use crate::rng::SimRng;
+use crate::systems::food_units;
+
+/// Parâmetros de nascimentos, envelhecimento e mortes. Idades em anos
+/// simulados, de quatro estações do calendário.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DemographyConfig {
+    /// Idade a partir da qual a Person trabalha, vende e planta; antes disso
+    /// só come, da despensa da casa.
+    pub adult_age: f32,
+    /// Faixa de idade das Persons criadas no início.
+    pub start_age_min: f32,
+    pub start_age_max: f32,
+    /// Faixa de idade em que se pode ter filhos.
+    pub fertile_from: f32,
+    pub fertile_until: f32,
+    /// Chance por ano de ter um filho, para quem está bem alimentado.
+    pub birth_rate: f32,
+    /// Saciedade mínima e comida guardada na casa, por membro, para ter um
+    /// filho.
+    pub well_fed_hunger: f32,
+    pub well_fed_food: i32,
+    /// Filhos vivos a partir dos quais não se tem mais.
+    pub max_children: usize,
+    /// Mortalidade de Gompertz: a chance de morrer em um ano é
+    /// `mortality_base × e^(mortality_growth × idade)`.
+    pub mortality_base: f32,
+    pub mortality_growth: f32,
+}
+
+impl Default for DemographyConfig {
+    fn default() -> Self {
+        Self {
+            adult_age: 16.0,
+            start_age_min: 18.0,
+            start_age_max: 60.0,
+            fertile_from: 18.0,
+            fertile_until: 45.0,
+            birth_rate: 0.2,
+            well_fed_hunger: 60.0,
+            well_fed_food: 3,
+            max_children: 3,
+            mortality_base: 0.0002,
+            mortality_growth: 0.085,
+        }
+    }
+}
+
+impl DemographyConfig {
+    pub fn is_adult(&self, age: f32) -> bool {
+        age >= self.adult_age
+    }
+
+    pub fn is_fertile(&self, age: f32) -> bool {
+        (self.fertile_from..self.fertile_until).contains(&age)
+    }
+
+    /// Chance de morrer de velhice em um ano, nesta idade.
+    pub fn mortality(&self, age: f32) -> f32 {
+        self.mortality_base * (self.mortality_growth * age).exp()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+    Starvation,
+    OldAge,
+}
+
+/// Parte de `total` de cada um de `heirs` herdeiros, em partes iguais; o
+/// que não divide fica com o primeiro.
+pub fn shares(total: usize, heirs: usize) -> impl Iterator<Item = usize> {
+    let share = total / heirs.max(1);
+    let rest = total - share * heirs;
+    (0..heirs).map(move |i| if i == 0 { share + rest } else { share })
+}
+
+/// Envelhece as Persons, sorteia as mortes por idade e os nascimentos nas
+/// casas bem alimentadas. Um casal sorteia uma vez só, pelo membro que entrou
+/// primeiro na casa. O recém-nascido mora na casa dos pais, sem ouro e sem
+/// inventário; a comida vem da despensa.
+#[allow(clippy::too_many_arguments)]
+pub fn demography_system(
+    mut commands: Commands,
+    mut persons: Query<(Entity, &mut Person, &mut Alive, &mut Family, &Parent)>,
+    mut households: Query<&mut Household>,
+    mut cities: Query<&mut City>,
+    catalog: Res<ItemCatalog>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    mut rng: ResMut<SimRng>,
+    mut births: EventWriter<BirthEvent>,
+    mut deaths: EventWriter<DeathEvent>,
+) {
+    let demography = &config.demography;
+    let years = clock.delta_secs() / config.calendar.year_secs(config.day_secs);
+
+    let mut newborns = Vec::new();
+    for (entity, mut person, mut alive, family, city) in persons.iter_mut() {
+        if !alive.0 {
+            continue;
+        }
+        person.age += years;
+        if rng.0.random::<f32>() < demography.mortality(person.age) * years {
+            alive.0 = false;
+            deaths.send(DeathEvent {
+                city: city.get(),
+                cause: DeathCause::OldAge,
+            });
+            continue;
+        }
+
+        if !demography.is_fertile(person.age) || family.children.len() >= demography.max_children {
+            continue;
+        }
+        let Some(household) = family
+            .household
+            .and_then(|household| households.get(household).ok())
+        else {
+            continue;
+        };
+        let first_of_couple = household
+            .members
+            .iter()
+            .find(|&&member| member == entity || Some(member) == family.spouse)
+            == Some(&entity);
+        let food = food_units(&person, &catalog) + household.pantry_food(&catalog);
+        let well_fed = person.hunger >= demography.well_fed_hunger
+            && food >= demography.well_fed_food * household.members.len() as i32;
+        if first_of_couple && well_fed && rng.0.random::<f32>() < demography.birth_rate * years {
+            newborns.push((entity, family.spouse, family.household, city.get()));
+        }
+    }
+
+    for (parent, spouse, household, city) in newborns {
+        // O cônjuge é pai ou mãe também, se ainda está vivo
+        let spouse =
+            spouse.filter(|&spouse| persons.get(spouse).is_ok_and(|(_, _, alive, _, _)| alive.0));
+        let Ok((_, person, _, family, _)) = persons.get(parent) else {
+            continue;
+        };
+        let parents: Vec<Entity> = std::iter::once(parent).chain(spouse).collect();
+        let child = commands
+            .spawn((
+                Person {
+                    name: format!("{}.{}", person.name, family.children.len() + 1),
+                    position: person.position.clone(),
+                    gold: 0,
+                    skill: rng.0.random_range(0.0..1.0),
+                    age: 0.0,
+                    ..default()
+                },
+                Alive(true),
+                Employment::default(),
+                JobSeeker::default(),
+                Family {
+                    parents: parents.clone(),
+                    household,
+                    ..default()
+                },
+            ))
+            .id();
+        for &parent in &parents {
+            if let Ok((_, _, _, mut family, _)) = persons.get_mut(parent) {
+                family.children.push(child);
+            }
+        }
+        if let Some(mut household) =
+            household.and_then(|household| households.get_mut(household).ok())
+        {
+            household.members.push(child);
+        }
+        commands.entity(city).add_child(child);
+        if let Ok(mut city_component) = cities.get_mut(city) {
+            city_component.persons.push(child);
+        }
+        births.send(BirthEvent { city });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mortality_grows_with_age_and_estates_split_evenly() {
+        let config = DemographyConfig::default();
+        assert!(config.mortality(20.0) < 0.01);
+        assert!(config.mortality(80.0) > 10.0 * config.mortality(20.0));
+        assert!(!config.is_adult(10.0));
+        assert!(config.is_fertile(30.0) && !config.is_fertile(50.0));
+
+        assert_eq!(shares(10, 3).collect::<Vec<_>>(), vec![4, 3, 3]);
+        assert_eq!(shares(2, 4).collect::<Vec<_>>(), vec![2, 0, 0, 0]);
+        assert_eq!(shares(7, 1).collect::<Vec<_>>(), vec![7]);
+        assert_eq!(shares(7, 0).count(), 0);
+    }
+
+    #[test]
+    fn estates_go_to_living_children_or_else_to_the_city() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::components::{Plot, TerrainType, Wholesaler};
+        use crate::events::{LoanEvent, TransferEvent};
+        use crate::items::ItemId;
+        use crate::systems::despawn_dead_person_system;
+
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let potato = catalog.find("Potato").unwrap();
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(catalog);
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<LoanEvent>>();
+        let city = world.spawn(City::default()).id();
+        let wholesaler = world
+            .spawn(Wholesaler {
+                cash: 20,
+                ..default()
+            })
+            .id();
+        world.entity_mut(city).add_child(wholesaler);
+        let mut spawn = |alive: bool, inventory: &[(ItemId, i32)], family: Family| {
+            let mut person = Person {
+                gold: 10,
+                ..default()
+            };
+            person.inventory.extend(inventory.iter().copied());
+            let person = world.spawn((person, Alive(alive), family)).id();
+            world.entity_mut(city).add_child(person);
+            person
+        };
+        let parent = spawn(false, &[(apple, 3)], Family::default());
+        let older = spawn(true, &[], Family::default());
+        let younger = spawn(true, &[], Family::default());
+        let loner = spawn(false, &[(apple, 3), (potato, 4)], Family::default());
+        world.get_mut::<Family>(parent).unwrap().children = vec![older, younger];
+        for child in [older, younger] {
+            world.get_mut::<Family>(child).unwrap().parents = vec![parent];
+        }
+        let mut plot = |owner: Entity| {
+            let plot = world
+                .spawn(Plot {
+                    terrain: TerrainType::Grassland,
+                    fertility: 1.0,
+                    owner: Some(owner),
+                    farmer: Some(owner),
+                    crop: None,
+                    sharecropped: false,
+                })
+                .id();
+            world.entity_mut(city).add_child(plot);
+            plot
+        };
+        let inherited = plot(parent);
+        let escheated = plot(loner);
+
+        world.run_system_once(despawn_dead_person_system).unwrap();
+        assert!(world.get_entity(parent).is_err() && world.get_entity(loner).is_err());
+
+        // Os filhos dividem o ouro e as maçãs; o lote fica com o mais velho
+        let estate = |child: Entity| {
+            let person = world.get::<Person>(child).unwrap();
+            (person.gold, person.inventory.get(&apple).copied())
+        };
+        assert_eq!(estate(older), (15, Some(2)));
+        assert_eq!(estate(younger), (15, Some(1)));
+        assert!(world.get::<Family>(older).unwrap().parents.is_empty());
+        let plot = world.get::<Plot>(inherited).unwrap();
+        assert_eq!((plot.owner, plot.farmer), (Some(older), None));
+
+        // Sem herdeiros, o atacadista compra duas maçãs a 7 e, com o que
+        // sobra, duas batatas a 3; o tesouro fica com isso e com o ouro
+        assert_eq!(world.get::<Wholesaler>(wholesaler).unwrap().cash, 0);
+        assert_eq!(world.get::<City>(city).unwrap().treasury, 30);
+        let plot = world.get::<Plot>(escheated).unwrap();
+        assert_eq!((plot.owner, plot.farmer), (Some(city), None));
+    }
+}
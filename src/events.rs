@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+use crate::banks::LoanOutcome;
+use crate::demography::DeathCause;
+use crate::items::ItemId;
+use crate::ledger::{Account, TransferKind};
+use crate::taxes::Tax;
+use crate::welfare::Benefit;
+
+/// Uma troca liquidada no mercado de uma cidade por `market_system`.
+#[derive(Event, Debug, Clone)]
+pub struct TradeEvent {
+    pub city: Entity,
+    pub seller: Entity,
+    pub item: ItemId,
+    pub quantity: usize,
+    pub unit_price: usize,
+}
+
+/// Unidades produzidas por uma Person, na colheita de `fieldwork_system` ou
+/// no turno de `work_system`.
+#[derive(Event, Debug, Clone)]
+pub struct ProductionEvent {
+    pub person: Entity,
+    pub item: ItemId,
+    pub quantity: usize,
+}
+
+/// Uma planta colhida em `fieldwork_system`, com o tempo desde a semeadura.
+#[derive(Event, Debug, Clone)]
+pub struct HarvestEvent {
+    pub city: Entity,
+    pub item: ItemId,
+    pub quantity: usize,
+    pub age_secs: f32,
+}
+
+/// Unidades comidas por uma Person em `feeding_system`.
+#[derive(Event, Debug, Clone)]
+pub struct ConsumptionEvent {
+    pub item: ItemId,
+    pub quantity: usize,
+}
+
+/// Uma Person nascida em `demography_system` em `city`.
+#[derive(Event, Debug, Clone)]
+pub struct BirthEvent {
+    pub city: Entity,
+}
+
+/// Uma Person que morreu neste tick, de fome ou de velhice.
+#[derive(Event, Debug, Clone)]
+pub struct DeathEvent {
+    pub city: Entity,
+    pub cause: DeathCause,
+}
+
+/// Um empréstimo concedido, quitado ou executado pelo banco de `city`.
+#[derive(Event, Debug, Clone)]
+pub struct LoanEvent {
+    pub city: Entity,
+    pub amount: usize,
+    pub outcome: LoanOutcome,
+}
+
+/// Imposto pago por alguém de `city` ao tesouro de uma cidade, estado ou
+/// país.
+#[derive(Event, Debug, Clone)]
+pub struct TaxEvent {
+    pub city: Entity,
+    pub treasury: Entity,
+    pub tax: Tax,
+    pub amount: usize,
+}
+
+/// Uma loja tentou tirar do estoque mais do que tinha. O estoque fica em
+/// zero e a auditoria reporta a falta.
+#[derive(Event, Debug, Clone)]
+pub struct StockShortfallEvent {
+    pub shop: Entity,
+    pub item: ItemId,
+    pub wanted: usize,
+    pub stock: usize,
+}
+
+/// Benefício pago a alguém de `city` pelo tesouro de uma cidade, estado ou
+/// país.
+#[derive(Event, Debug, Clone)]
+pub struct BenefitEvent {
+    pub city: Entity,
+    pub benefit: Benefit,
+    pub amount: usize,
+}
+
+/// Ouro movido de uma conta para outra, lançado no `Ledger`. Todo sistema
+/// que altera o ouro de alguém envia um.
+#[derive(Event, Debug, Clone)]
+pub struct TransferEvent {
+    pub from: Account,
+    pub to: Account,
+    pub amount: usize,
+    pub kind: TransferKind,
+}
@@ -0,0 +1,610 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::banks::{self, Bank};
+use crate::components::{City, Country, Employer, Shop, State};
+use crate::config::ScenarioConfig;
+use crate::events::{StockShortfallEvent, TransferEvent};
+use crate::items::ItemId;
+use crate::labor::distance;
+use crate::ledger::{Account, TransferKind};
+use crate::shops;
+
+/// Parâmetros do mercado de câmbio.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExchangeConfig {
+    /// Margem cobrada pelos bancos sobre cada conversão.
+    pub spread: f32,
+    /// Quanto a cotação de uma moeda muda em um dia em que ela só foi
+    /// comprada (ou só vendida).
+    pub sensitivity: f32,
+    /// Se as lojas podem comprar de lojas de outros países.
+    pub imports: bool,
+    /// Estoque que a loja de fora mantém; só vende o que passa disso.
+    pub export_above: usize,
+    /// Unidades de cada importação.
+    pub import_batch: usize,
+}
+
+impl Default for ExchangeConfig {
+    fn default() -> Self {
+        Self {
+            spread: 0.02,
+            sensitivity: 0.05,
+            imports: true,
+            export_above: 20,
+            import_batch: 10,
+        }
+    }
+}
+
+/// Moeda de um país. Os saldos de ouro (`Person.gold`, `Shop.cash`, o caixa
+/// dos bancos, ...) ficam na moeda do país da cidade do dono. A moeda de
+/// fora fica com os bancos, em `Bank.foreign`: é com ela que pagam as
+/// importações das lojas em `import_system`, e é ela que devolvem uns aos
+/// outros em `settlement_system`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Currency {
+    pub name: String,
+    /// Valor de uma unidade numa unidade de conta comum a todas as moedas. A
+    /// cotação entre duas moedas é a razão entre os seus valores.
+    pub rate: f64,
+    /// Compras e vendas da moeda no câmbio desde a última cotação, na
+    /// unidade de conta.
+    pub bought: f64,
+    pub sold: f64,
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            rate: 1.0,
+            bought: 0.0,
+            sold: 0.0,
+        }
+    }
+}
+
+impl Currency {
+    /// Quanto desta moeda compra `amount` da outra, com a margem do banco.
+    pub fn price_of(&self, other: &Currency, amount: f64, spread: f32) -> f64 {
+        amount * other.rate / self.rate * (1.0 + spread as f64)
+    }
+
+    /// O mesmo em ouro, arredondado para cima.
+    pub fn cost_of(&self, other: &Currency, amount: usize, spread: f32) -> usize {
+        self.price_of(other, amount as f64, spread).ceil() as usize
+    }
+
+    /// Nova cotação a partir das compras e vendas do dia: a moeda mais
+    /// procurada do que oferecida se valoriza.
+    pub fn quote(&mut self, sensitivity: f32) {
+        let turnover = self.bought + self.sold;
+        if turnover > 0.0 {
+            self.rate *= 1.0 + sensitivity as f64 * (self.bought - self.sold) / turnover;
+        }
+        self.bought = 0.0;
+        self.sold = 0.0;
+    }
+}
+
+/// País de cada cidade.
+pub fn country_by_city<'a>(
+    countries: impl Iterator<Item = (Entity, &'a Children)>,
+    states: &Query<&Children, With<State>>,
+) -> HashMap<Entity, Entity> {
+    let mut by_city = HashMap::new();
+    for (country, children) in countries {
+        for state in children.iter().filter_map(|&state| states.get(state).ok()) {
+            by_city.extend(state.iter().map(|&city| (city, country)));
+        }
+    }
+    by_city
+}
+
+/// Importações entre lojas de países diferentes, a cada tick. Uma loja abaixo
+/// do estoque máximo de um item compra um lote da loja mais barata da cidade
+/// mais próxima de cada outro país, se o preço dela, convertido pela cotação e
+/// com a margem do câmbio, fica abaixo do que a loja pagaria na própria
+/// cidade. O lote não passa do estoque máximo, e a loja não gasta a reserva
+/// para os salários. Ela paga na sua moeda ao banco da cidade, que paga a
+/// vendedora com a moeda de lá que tiver guardado. O que faltar, o banco de
+/// lá adianta do próprio caixa, até onde ele alcança, e recebe do banco
+/// daqui o mesmo valor pela cotação sem a margem, na moeda daqui, que passa
+/// a guardar. As lojas são atendidas na ordem das cidades pelo nome e os
+/// itens pelo id.
+#[allow(clippy::too_many_arguments)]
+pub fn import_system(
+    mut shops: Query<&mut Shop>,
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    mut countries: Query<(Entity, &mut Country, &Children)>,
+    states: Query<&Children, With<State>>,
+    cities: Query<(Entity, &City)>,
+    employers: Query<&Employer>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut shortfalls: EventWriter<StockShortfallEvent>,
+) {
+    let labor = &config.labor;
+    let shops_config = &config.shops;
+    let config = &config.exchange;
+    if !config.imports {
+        return;
+    }
+    let home = country_by_city(
+        countries
+            .iter()
+            .map(|(entity, _, children)| (entity, children)),
+        &states,
+    );
+    let bank_by_city = banks::banks_by_city(&banks);
+    let mut currencies: HashMap<Entity, Currency> = countries
+        .iter()
+        .map(|(entity, country, _)| (entity, country.currency.clone()))
+        .collect();
+    let mut cities: Vec<(Entity, &City)> = cities.iter().collect();
+    cities.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+
+    for &(city, city_component) in &cities {
+        let (Some(&country), Some(&bank)) = (home.get(&city), bank_by_city.get(&city)) else {
+            continue;
+        };
+        // Cidade mais próxima de cada outro país, com o seu banco, na ordem
+        // das cidades
+        let mut partners: Vec<(Entity, f32, &City, Entity)> = Vec::new();
+        for &(other_city, other) in &cities {
+            let (Some(&other_country), Some(&other_bank)) =
+                (home.get(&other_city), bank_by_city.get(&other_city))
+            else {
+                continue;
+            };
+            if other_country == country {
+                continue;
+            }
+            let far = distance(&city_component.position, &other.position);
+            match partners
+                .iter_mut()
+                .find(|partner| partner.0 == other_country)
+            {
+                Some(partner) if far < partner.1 => {
+                    *partner = (other_country, far, other, other_bank)
+                }
+                Some(_) => {}
+                None => partners.push((other_country, far, other, other_bank)),
+            }
+        }
+
+        for &importer in &city_component.shops {
+            let Ok(shop) = shops.get(importer) else {
+                continue;
+            };
+            let mut wanted: Vec<ItemId> = shop
+                .items
+                .iter()
+                .filter(|(_, details)| details.stock < shops_config.max_stock)
+                .map(|(item, _)| *item)
+                .collect();
+            wanted.sort();
+            for item in wanted {
+                let Ok(shop) = shops.get(importer) else {
+                    continue;
+                };
+                let details = &shop.items[&item];
+                let bid = details.bid_price(shops_config.spread) as f64;
+                let room = shops_config.max_stock.saturating_sub(details.stock);
+                let reserve = labor.payroll_reserve(employers.get(importer).ok(), labor.shop_jobs);
+                let cash = shop.cash.saturating_sub(reserve);
+                // A oferta de fora mais barata, pelo custo de uma unidade na
+                // moeda da loja
+                let mut best: Option<(f64, Entity, Entity, Entity)> = None;
+                for &(other_country, _, other_city, other_bank) in &partners {
+                    for &seller in &other_city.shops {
+                        let Some(details) = shops
+                            .get(seller)
+                            .ok()
+                            .and_then(|shop| shop.items.get(&item))
+                        else {
+                            continue;
+                        };
+                        if details.stock <= config.export_above {
+                            continue;
+                        }
+                        let cost = currencies[&country].price_of(
+                            &currencies[&other_country],
+                            details.ask_price() as f64,
+                            config.spread,
+                        );
+                        if cost < bid && best.is_none_or(|(best, ..)| cost < best) {
+                            best = Some((cost, seller, other_country, other_bank));
+                        }
+                    }
+                }
+                let Some((cost, seller, other_country, other_bank)) = best else {
+                    continue;
+                };
+
+                let Some(details) = shops
+                    .get(seller)
+                    .ok()
+                    .and_then(|shop| shop.items.get(&item))
+                else {
+                    continue;
+                };
+                let (ask, available) = (details.ask_price(), details.stock - config.export_above);
+                let (home_currency, foreign_currency) = (
+                    currencies[&country].name.clone(),
+                    currencies[&other_country].name.clone(),
+                );
+                let held = banks
+                    .get(bank)
+                    .map_or(0, |(_, bank, _)| bank.holding(&foreign_currency));
+                let reserves = held + banks.get(other_bank).map_or(0, |(_, bank, _)| bank.cash);
+                let mut quantity = config
+                    .import_batch
+                    .min(room)
+                    .min(available)
+                    .min((cash as f64 / cost) as usize)
+                    .min(reserves / ask.max(1));
+                // A conversão arredonda para cima: a loja leva só o que o
+                // caixa paga
+                let charge = |quantity: usize| {
+                    currencies[&country].cost_of(
+                        &currencies[&other_country],
+                        ask * quantity,
+                        config.spread,
+                    )
+                };
+                while quantity > 0 && charge(quantity) > cash {
+                    quantity -= 1;
+                }
+                if quantity == 0 {
+                    continue;
+                }
+                let (paid, charged) = (ask * quantity, charge(quantity));
+
+                let Ok([(_, mut home_bank, _), (_, mut foreign_bank, _)]) =
+                    banks.get_many_mut([bank, other_bank])
+                else {
+                    continue;
+                };
+                let held = home_bank.holding(&foreign_currency).min(paid);
+                let advanced = paid - held;
+                let owed = currencies[&country].cost_of(&currencies[&other_country], advanced, 0.0);
+                let Some(reserves) = foreign_bank.cash.checked_sub(advanced) else {
+                    continue;
+                };
+                let Some(home_reserves) = (home_bank.cash + charged).checked_sub(owed) else {
+                    continue;
+                };
+                let Ok([mut seller_shop, mut shop]) = shops.get_many_mut([seller, importer]) else {
+                    continue;
+                };
+                let Some(cash) = shop.cash.checked_sub(charged) else {
+                    continue;
+                };
+                let (Some(sold), Some(bought)) =
+                    (seller_shop.items.get_mut(&item), shop.items.get_mut(&item))
+                else {
+                    continue;
+                };
+                shops::take_stock(sold, seller, item, quantity, &mut shortfalls);
+                sold.transactions.0 += quantity;
+                bought.stock += quantity;
+                bought.transactions.1 += quantity;
+                seller_shop.cash += paid;
+                shop.cash = cash;
+                foreign_bank.cash = reserves;
+                home_bank.cash = home_reserves;
+                home_bank.take_foreign(&foreign_currency, held);
+                *foreign_bank.foreign.entry(home_currency).or_insert(0) += owed;
+                for (from, to, amount) in [
+                    (Account::Agent(importer), Account::Agent(bank), charged),
+                    (
+                        Account::Foreign(bank, other_country),
+                        Account::Agent(seller),
+                        held,
+                    ),
+                    (Account::Agent(other_bank), Account::Agent(seller), advanced),
+                    (
+                        Account::Agent(bank),
+                        Account::Foreign(other_bank, country),
+                        owed,
+                    ),
+                ] {
+                    if amount > 0 {
+                        transfers.send(TransferEvent {
+                            from,
+                            to,
+                            amount,
+                            kind: TransferKind::Exchange,
+                        });
+                    }
+                }
+                if let Some(currency) = currencies.get_mut(&country) {
+                    currency.sold += charged as f64 * currency.rate;
+                }
+                if let Some(currency) = currencies.get_mut(&other_country) {
+                    currency.bought += paid as f64 * currency.rate;
+                }
+            }
+        }
+    }
+
+    for (entity, mut country, _) in countries.iter_mut() {
+        if let Some(currency) = currencies.remove(&entity) {
+            country.currency = currency;
+        }
+    }
+}
+
+/// Acerto diário entre os bancos de países diferentes, na ordem dos nomes.
+/// Quando cada banco de um par guarda moeda do país do outro, os dois a
+/// devolvem pela cotação sem a margem, até esgotar a menor das duas em
+/// valor; o arredondamento favorece o banco que devolve tudo o que guardava.
+pub fn settlement_system(
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    countries: Query<(Entity, &Country, &Children)>,
+    states: Query<&Children, With<State>>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    let home = country_by_city(
+        countries
+            .iter()
+            .map(|(entity, _, children)| (entity, children)),
+        &states,
+    );
+    let mut order: Vec<(String, Entity, Entity)> = banks
+        .iter()
+        .filter_map(|(entity, bank, city)| {
+            let country = *home.get(&city.get())?;
+            Some((bank.name.clone(), entity, country))
+        })
+        .collect();
+    order.sort();
+
+    for (i, (_, a, a_country)) in order.iter().enumerate() {
+        for (_, b, b_country) in &order[i + 1..] {
+            if a_country == b_country {
+                continue;
+            }
+            let currency = |country: Entity| {
+                countries
+                    .get(country)
+                    .map(|(_, country, _)| &country.currency)
+            };
+            let (Ok(a_currency), Ok(b_currency)) = (currency(*a_country), currency(*b_country))
+            else {
+                continue;
+            };
+            let Ok([(_, mut a_bank, _), (_, mut b_bank, _)]) = banks.get_many_mut([*a, *b]) else {
+                continue;
+            };
+            let (a_holds, b_holds) = (
+                a_bank.holding(&b_currency.name),
+                b_bank.holding(&a_currency.name),
+            );
+            // Quanto cada um devolve ao outro, na moeda do outro
+            let (to_b, to_a) =
+                if a_holds as f64 * b_currency.rate <= b_holds as f64 * a_currency.rate {
+                    (a_holds, a_currency.cost_of(b_currency, a_holds, 0.0))
+                } else {
+                    (b_currency.cost_of(a_currency, b_holds, 0.0), b_holds)
+                };
+            let (to_b, to_a) = (to_b.min(a_holds), to_a.min(b_holds));
+            if to_b == 0 || to_a == 0 {
+                continue;
+            }
+            a_bank.take_foreign(&b_currency.name, to_b);
+            b_bank.take_foreign(&a_currency.name, to_a);
+            b_bank.cash += to_b;
+            a_bank.cash += to_a;
+            for (from, to, amount) in [
+                (Account::Foreign(*a, *b_country), Account::Agent(*b), to_b),
+                (Account::Foreign(*b, *a_country), Account::Agent(*a), to_a),
+            ] {
+                transfers.send(TransferEvent {
+                    from,
+                    to,
+                    amount,
+                    kind: TransferKind::Settlement,
+                });
+            }
+        }
+    }
+}
+
+/// Cotação diária de cada moeda pelo fluxo de câmbio do dia.
+pub fn exchange_rate_system(mut countries: Query<&mut Country>, config: Res<ScenarioConfig>) {
+    for mut country in countries.iter_mut() {
+        country.currency.quote(config.exchange.sensitivity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion_charges_the_spread_and_demand_appreciates_a_currency() {
+        let mut home = Currency::default();
+        let foreign = Currency {
+            rate: 2.0,
+            ..default()
+        };
+        assert_eq!(home.cost_of(&foreign, 10, 0.0), 20);
+        assert_eq!(home.cost_of(&foreign, 10, 0.25), 25);
+        assert_eq!(foreign.cost_of(&home, 10, 0.0), 5);
+
+        // Só vendida, cai o máximo do dia; sem fluxo, não muda
+        home.sold = 30.0;
+        home.quote(0.1);
+        assert!((home.rate - 0.9).abs() < 1e-6);
+        assert_eq!(home.sold, 0.0);
+        home.quote(0.1);
+        assert!((home.rate - 0.9).abs() < 1e-6);
+        home.bought = 30.0;
+        home.sold = 10.0;
+        home.quote(0.1);
+        assert!((home.rate - 0.945).abs() < 1e-6);
+    }
+
+    /// País com um estado, uma cidade e o banco dela.
+    fn spawn_country(world: &mut World, name: &str, rate: f64) -> (Entity, Entity) {
+        let country = world
+            .spawn(Country {
+                currency: Currency {
+                    name: name.to_string(),
+                    rate,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        let state = world.spawn(State::default()).id();
+        let city = world
+            .spawn(City {
+                name: name.to_string(),
+                ..default()
+            })
+            .id();
+        let bank = world
+            .spawn(Bank {
+                cash: 100,
+                ..default()
+            })
+            .id();
+        world.entity_mut(country).add_child(state);
+        world.entity_mut(state).add_child(city);
+        world.entity_mut(city).add_child(bank);
+        (city, bank)
+    }
+
+    fn spawn_shop(
+        world: &mut World,
+        city: Entity,
+        price: usize,
+        stock: usize,
+        cash: usize,
+    ) -> Entity {
+        let apple = crate::items::ItemCatalog::default().find("Apple").unwrap();
+        let mut shop = Shop { cash, ..default() };
+        shop.stock_item(apple, price, stock);
+        let shop = world.spawn(shop).id();
+        world.entity_mut(city).add_child(shop);
+        world.get_mut::<City>(city).unwrap().shops.push(shop);
+        shop
+    }
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<StockShortfallEvent>>();
+        world
+    }
+
+    #[test]
+    fn imports_only_what_the_shop_can_pay_after_conversion() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::items::ItemCatalog;
+
+        let apple = ItemCatalog::default().find("Apple").unwrap();
+        let mut world = world();
+
+        // Um país por cidade, cada um com o seu banco e uma loja
+        let (home, home_bank) = spawn_country(&mut world, "A", 1.0);
+        let (foreign, foreign_bank) = spawn_country(&mut world, "B", 1.5);
+        let importer = spawn_shop(&mut world, home, 20, 0, 35);
+        let seller = spawn_shop(&mut world, foreign, 10, 30, 0);
+
+        // Cada maçã de fora custa 10 * 1,5 * 1,02 = 15,3 na moeda da loja,
+        // abaixo dos 16 que ela pagaria em casa; o caixa paga duas, 30,6,
+        // que o câmbio arredonda para 31
+        world.run_system_once(import_system).unwrap();
+        let shop = world.get::<Shop>(importer).unwrap();
+        assert_eq!((shop.items[&apple].stock, shop.cash), (2, 4));
+        let shop = world.get::<Shop>(seller).unwrap();
+        assert_eq!((shop.items[&apple].stock, shop.cash), (28, 20));
+        // O banco de lá adianta os 20 e guarda os 30 que valem na moeda
+        // daqui; o daqui fica com a margem
+        assert_eq!(world.get::<Bank>(home_bank).unwrap().cash, 101);
+        let bank = world.get::<Bank>(foreign_bank).unwrap();
+        assert_eq!((bank.cash, bank.holding("A")), (80, 30));
+
+        // Sem caixa para mais uma, não compra nada
+        world.run_system_once(import_system).unwrap();
+        assert_eq!(world.get::<Shop>(importer).unwrap().cash, 4);
+        assert!(world.resource::<Events<StockShortfallEvent>>().is_empty());
+    }
+
+    #[test]
+    fn imports_stop_at_the_max_stock_and_spare_the_payroll_reserve() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::items::ItemCatalog;
+
+        let apple = ItemCatalog::default().find("Apple").unwrap();
+        let mut world = world();
+        let (home, _) = spawn_country(&mut world, "A", 1.0);
+        let (foreign, _) = spawn_country(&mut world, "B", 1.5);
+        // Uma unidade abaixo do máximo de 60, com caixa para muito mais
+        let nearly_full = spawn_shop(&mut world, home, 20, 59, 1000);
+        // Duas vagas guardam 60 dos 100 do caixa, que paga só duas maçãs
+        let employing = spawn_shop(&mut world, home, 20, 0, 100);
+        let labor = ScenarioConfig::default().labor;
+        world
+            .entity_mut(employing)
+            .insert(labor.employer(100, labor.shop_jobs));
+        spawn_shop(&mut world, foreign, 10, 50, 0);
+
+        world.run_system_once(import_system).unwrap();
+        let shop = world.get::<Shop>(nearly_full).unwrap();
+        assert_eq!((shop.items[&apple].stock, shop.cash), (60, 984));
+        let shop = world.get::<Shop>(employing).unwrap();
+        assert_eq!((shop.items[&apple].stock, shop.cash), (2, 69));
+    }
+
+    #[test]
+    fn held_currency_pays_imports_first_and_banks_settle_what_they_hold() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = world();
+        let (home, home_bank) = spawn_country(&mut world, "A", 1.0);
+        let (foreign, foreign_bank) = spawn_country(&mut world, "B", 2.0);
+        let importer = spawn_shop(&mut world, foreign, 20, 0, 100);
+        let seller = spawn_shop(&mut world, home, 10, 30, 0);
+        let mut bank = world.get_mut::<Bank>(foreign_bank).unwrap();
+        bank.foreign.insert("A".to_string(), 50);
+
+        // Dez maçãs custam 100 em A: o banco de B paga 50 com o que guarda
+        // e o de A adianta os outros 50, que valem 25 em B
+        world.run_system_once(import_system).unwrap();
+        assert_eq!(world.get::<Shop>(seller).unwrap().cash, 100);
+        assert_eq!(world.get::<Shop>(importer).unwrap().cash, 49);
+        let bank = world.get::<Bank>(foreign_bank).unwrap();
+        assert_eq!((bank.cash, bank.holding("A")), (126, 0));
+        assert!(bank.foreign.is_empty());
+        let bank = world.get::<Bank>(home_bank).unwrap();
+        assert_eq!((bank.cash, bank.holding("B")), (50, 25));
+
+        // Os 30 de A que B volta a guardar valem 15 em B: cada banco devolve
+        // ao outro a própria moeda, e sobram 10 de B com o de A
+        let mut bank = world.get_mut::<Bank>(foreign_bank).unwrap();
+        bank.foreign.insert("A".to_string(), 30);
+        world.run_system_once(settlement_system).unwrap();
+        let bank = world.get::<Bank>(foreign_bank).unwrap();
+        assert_eq!((bank.cash, bank.holding("A")), (141, 0));
+        let bank = world.get::<Bank>(home_bank).unwrap();
+        assert_eq!((bank.cash, bank.holding("B")), (80, 10));
+        let transfers = world.resource::<Events<TransferEvent>>();
+        let settled: Vec<usize> = transfers
+            .iter_current_update_events()
+            .filter(|transfer| transfer.kind == TransferKind::Settlement)
+            .map(|transfer| transfer.amount)
+            .collect();
+        assert_eq!(settled, vec![15, 30]);
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Alive, Family, Household, Person, PersonActions};
+use crate::events::TransferEvent;
+use crate::items::{ItemCatalog, ItemId};
+use crate::ledger::{Account, TransferKind};
+
+/// Parâmetros das casas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HouseholdConfig {
+    /// Chance de cada Person do início morar com um cônjuge; as demais moram
+    /// sozinhas.
+    pub couple_share: f32,
+    /// Comida por membro que a casa guarda antes de mandar alguém vender o
+    /// que sobra.
+    pub pantry_reserve: i32,
+}
+
+impl Default for HouseholdConfig {
+    fn default() -> Self {
+        Self {
+            couple_share: 0.6,
+            pantry_reserve: 2,
+        }
+    }
+}
+
+impl Household {
+    /// Unidades de comida na despensa.
+    pub fn pantry_food(&self, catalog: &ItemCatalog) -> i32 {
+        self.pantry
+            .iter()
+            .filter(|(item, _)| catalog.nutritional_value(**item).is_some())
+            .map(|(_, count)| *count)
+            .sum()
+    }
+
+    /// Parte de cada membro no caixa da casa.
+    pub fn budget(&self) -> usize {
+        self.purse / self.members.len().max(1)
+    }
+
+    /// Tira da despensa uma unidade da comida mais nutritiva.
+    pub fn take_food(&mut self, catalog: &ItemCatalog) -> Option<ItemId> {
+        let (item, count) = self
+            .pantry
+            .iter_mut()
+            .filter(|(_, count)| **count > 0)
+            .filter_map(|(item, count)| Some((catalog.nutritional_value(*item)?, *item, count)))
+            .max_by_key(|(value, item, _)| (*value, *item))
+            .map(|(_, item, count)| (item, count))?;
+        *count -= 1;
+        Some(item)
+    }
+
+    /// Tira da despensa a comida acima da reserva da casa, para vender.
+    pub fn take_surplus(&mut self, catalog: &ItemCatalog, reserve: i32) -> BTreeMap<ItemId, i32> {
+        let mut keep = reserve * self.members.len() as i32;
+        let mut surplus = BTreeMap::new();
+        // Guarda primeiro a comida mais nutritiva
+        let mut foods: Vec<(u32, ItemId)> = self
+            .pantry
+            .keys()
+            .filter_map(|item| Some((catalog.nutritional_value(*item)?, *item)))
+            .collect();
+        foods.sort_by(|a, b| b.cmp(a));
+        for (_, item) in foods {
+            let Some(count) = self.pantry.get_mut(&item) else {
+                continue;
+            };
+            let kept = (*count).min(keep).max(0);
+            keep -= kept;
+            if *count > kept {
+                surplus.insert(item, *count - kept);
+                *count = kept;
+            }
+        }
+        surplus
+    }
+}
+
+/// Dá a um membro `amount` de ouro do caixa da casa, até onde ele alcança.
+pub fn allowance(
+    household: &mut Household,
+    household_entity: Entity,
+    person: &mut Person,
+    person_entity: Entity,
+    amount: usize,
+    transfers: &mut EventWriter<TransferEvent>,
+) {
+    let amount = amount.min(household.purse);
+    if amount == 0 {
+        return;
+    }
+    household.purse -= amount;
+    person.gold += amount;
+    transfers.send(TransferEvent {
+        from: Account::Agent(household_entity),
+        to: Account::Agent(person_entity),
+        amount,
+        kind: TransferKind::Household,
+    });
+}
+
+/// Cada membro vivo e parado entrega à casa o ouro e a comida que trouxe: o
+/// salário, a venda, a colheita, a compra ou o troco do que recebeu para
+/// gastar. Quem está no meio de uma ação fica com o que leva até terminar.
+pub fn pooling_system(
+    mut households: Query<(Entity, &mut Household)>,
+    mut persons: Query<(&mut Person, &Alive, &Family)>,
+    catalog: Res<ItemCatalog>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    for (household_entity, mut household) in households.iter_mut() {
+        for i in 0..household.members.len() {
+            let member = household.members[i];
+            let Ok((mut person, alive, family)) = persons.get_mut(member) else {
+                continue;
+            };
+            if !alive.0
+                || person.action != PersonActions::Idle
+                || family.household != Some(household_entity)
+            {
+                continue;
+            }
+            let gold = std::mem::take(&mut person.gold);
+            if gold > 0 {
+                household.purse += gold;
+                transfers.send(TransferEvent {
+                    from: Account::Agent(member),
+                    to: Account::Agent(household_entity),
+                    amount: gold,
+                    kind: TransferKind::Household,
+                });
+            }
+            for (item, count) in person.inventory.iter_mut() {
+                if *count > 0 && catalog.nutritional_value(*item).is_some() {
+                    *household.pantry.entry(*item).or_insert(0) += *count;
+                    *count = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pantry_keeps_the_most_nutritious_food_and_sells_the_rest() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let corn = catalog.find("Corn").unwrap();
+        let (best, other) = if catalog.nutritional_value(apple) > catalog.nutritional_value(corn) {
+            (apple, corn)
+        } else {
+            (corn, apple)
+        };
+
+        let mut household = Household {
+            members: vec![Entity::from_raw(1), Entity::from_raw(2)],
+            purse: 9,
+            ..default()
+        };
+        household.pantry.insert(best, 3);
+        household.pantry.insert(other, 4);
+        assert_eq!(household.pantry_food(&catalog), 7);
+        assert_eq!(household.budget(), 4);
+
+        // Reserva de 2 por membro: fica com as 3 melhores e 1 da outra
+        let surplus = household.take_surplus(&catalog, 2);
+        assert_eq!(surplus.get(&other), Some(&3));
+        assert_eq!(surplus.get(&best), None);
+        assert_eq!(household.pantry_food(&catalog), 4);
+
+        assert_eq!(household.take_food(&catalog), Some(best));
+        assert_eq!(household.pantry[&best], 2);
+    }
+}
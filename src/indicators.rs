@@ -0,0 +1,532 @@
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::banks::Credit;
+use crate::clock::SimClock;
+use crate::components::{
+    Alive, City, Country, Employer, Employment, Family, Household, JobSeeker, Person, Shop, State,
+    Wholesaler,
+};
+use crate::config::ScenarioConfig;
+use crate::events::{ConsumptionEvent, ProductionEvent, TradeEvent, TransferEvent};
+use crate::items::{ItemCatalog, ItemId, MarketPrices, PriceTable};
+use crate::ledger::{Account, TransferKind};
+
+/// Indicadores de um escopo (cidade, estado, país ou o mundo todo), medidos
+/// no último período de `indicator_secs`. Os valores em ouro estão na moeda
+/// do país do escopo; os do mundo, na unidade de conta comum das cotações
+/// (`Currency::rate`), pela cotação do fim do período.
+#[derive(Debug, Clone, Default)]
+pub struct ScopeIndicators {
+    /// Índice de preços ao consumidor. 100 equivale à cesta de consumo
+    /// comprada pelos preços base do catálogo.
+    pub cpi: f64,
+    /// Variação percentual do CPI em relação ao período anterior.
+    pub inflation: f64,
+    /// Valor das colheitas do período, a preços de mercado.
+    pub production_value: f64,
+    /// Valor das trocas com as lojas no período.
+    pub trade_value: f64,
+    /// Produto nominal do período: colheitas mais trocas.
+    pub nominal_output: f64,
+    /// Ouro em poder das Persons vivas, das casas, das lojas e dos
+    /// atacadistas, mais os depósitos das casas e das lojas nos bancos.
+    pub money_supply: f64,
+    /// Quantas vezes o estoque de moeda circulou nas trocas do período.
+    pub velocity: f64,
+    /// Gini da riqueza (ouro mais inventário a preços de mercado, com a
+    /// parte de cada um no caixa, na despensa e no saldo da casa no banco).
+    pub wealth_gini: f64,
+    /// Gini da renda recebida no período.
+    pub income_gini: f64,
+    /// Gini da renda das casas no período, somada entre os membros.
+    pub household_income_gini: f64,
+    /// Renda média das casas no período.
+    pub average_household_income: f64,
+    /// Fração dos que procuram emprego entre empregados e desempregados.
+    pub unemployment: f64,
+    /// Salário médio oferecido pelos empregadores, em ouro por segundo.
+    pub average_wage: f64,
+}
+
+/// Indicadores macroeconômicos por escopo, recalculados a cada
+/// `indicator_secs` segundos simulados.
+#[derive(Resource, Default)]
+pub struct Indicators {
+    pub world: ScopeIndicators,
+    pub countries: HashMap<Entity, ScopeIndicators>,
+    pub states: HashMap<Entity, ScopeIndicators>,
+    pub cities: HashMap<Entity, ScopeIndicators>,
+    /// Quantidade consumida de cada comida desde o início; pesos do CPI.
+    /// Vai no snapshot. Os fluxos e o CPI anterior, não: o primeiro período
+    /// depois de retomar começa sem eles.
+    pub basket: BTreeMap<ItemId, f64>,
+    /// Fluxos do período corrente por Person ou cidade.
+    flows: HashMap<Entity, Flows>,
+}
+
+impl Indicators {
+    pub fn country(&self, entity: Entity) -> Option<&ScopeIndicators> {
+        self.countries.get(&entity)
+    }
+
+    pub fn state(&self, entity: Entity) -> Option<&ScopeIndicators> {
+        self.states.get(&entity)
+    }
+
+    pub fn city(&self, entity: Entity) -> Option<&ScopeIndicators> {
+        self.cities.get(&entity)
+    }
+
+    /// Pesos da cesta do CPI. Antes de qualquer refeição, todas as comidas
+    /// pesam igual.
+    fn weights(&self, catalog: &ItemCatalog) -> Vec<(ItemId, f64)> {
+        if self.basket.is_empty() {
+            catalog.foods().map(|item| (item.id, 1.0)).collect()
+        } else {
+            self.basket
+                .iter()
+                .map(|(item, qty)| (*item, *qty))
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Flows {
+    production: f64,
+    trade: f64,
+    income: f64,
+}
+
+/// Somatórios de um escopo, combinados de cidade para estado, país e mundo.
+#[derive(Default)]
+struct Totals {
+    prices: BTreeMap<ItemId, (f64, usize)>,
+    production: f64,
+    trade: f64,
+    money: f64,
+    wealth: Vec<f64>,
+    income: Vec<f64>,
+    household_income: Vec<f64>,
+    employed: usize,
+    seekers: usize,
+    wages: f64,
+    employers: usize,
+}
+
+impl Totals {
+    fn merge(&mut self, other: &Totals) {
+        self.merge_at(other, 1.0);
+    }
+
+    /// Soma os totais de `other`, cujos valores em ouro estão numa moeda que
+    /// vale `rate` na moeda deste escopo.
+    fn merge_at(&mut self, other: &Totals, rate: f64) {
+        for (item, (sum, count)) in &other.prices {
+            let entry = self.prices.entry(*item).or_insert((0.0, 0));
+            entry.0 += sum * rate;
+            entry.1 += count;
+        }
+        let convert = |values: &[f64]| values.iter().map(|value| value * rate).collect::<Vec<_>>();
+        self.production += other.production * rate;
+        self.trade += other.trade * rate;
+        self.money += other.money * rate;
+        self.wealth.extend(convert(&other.wealth));
+        self.income.extend(convert(&other.income));
+        self.household_income
+            .extend(convert(&other.household_income));
+        self.employed += other.employed;
+        self.seekers += other.seekers;
+        self.wages += other.wages * rate;
+        self.employers += other.employers;
+    }
+
+    fn finish(
+        &self,
+        weights: &[(ItemId, f64)],
+        catalog: &ItemCatalog,
+        prices: &PriceTable,
+        previous: Option<&ScopeIndicators>,
+    ) -> ScopeIndicators {
+        // Itens sem loja no escopo usam o preço médio do país
+        let price = |item: ItemId| match self.prices.get(&item) {
+            Some((sum, count)) if *count > 0 => sum / *count as f64,
+            _ => prices.price(catalog, item) as f64,
+        };
+        let current: f64 = weights.iter().map(|(item, w)| w * price(*item)).sum();
+        let base: f64 = weights
+            .iter()
+            .map(|(item, w)| w * catalog.get(*item).base_price as f64)
+            .sum();
+        let cpi = if base > 0.0 {
+            current / base * 100.0
+        } else {
+            100.0
+        };
+        let inflation = match previous {
+            Some(previous) if previous.cpi > 0.0 => (cpi / previous.cpi - 1.0) * 100.0,
+            _ => 0.0,
+        };
+
+        ScopeIndicators {
+            cpi,
+            inflation,
+            production_value: self.production,
+            trade_value: self.trade,
+            nominal_output: self.production + self.trade,
+            money_supply: self.money,
+            velocity: if self.money > 0.0 {
+                self.trade / self.money
+            } else {
+                0.0
+            },
+            wealth_gini: gini(&self.wealth),
+            income_gini: gini(&self.income),
+            household_income_gini: gini(&self.household_income),
+            average_household_income: if self.household_income.is_empty() {
+                0.0
+            } else {
+                self.household_income.iter().sum::<f64>() / self.household_income.len() as f64
+            },
+            unemployment: if self.employed + self.seekers > 0 {
+                self.seekers as f64 / (self.employed + self.seekers) as f64
+            } else {
+                0.0
+            },
+            average_wage: if self.employers > 0 {
+                self.wages / self.employers as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Coeficiente de Gini de valores não negativos: 0 é igualdade total e
+/// valores perto de 1, concentração total.
+pub fn gini(values: &[f64]) -> f64 {
+    // Soma já ordenada, para não depender da ordem das Persons
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let total: f64 = sorted.iter().sum();
+    if sorted.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+    let n = sorted.len() as f64;
+    let weighted: f64 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (i + 1) as f64 * value)
+        .sum();
+    2.0 * weighted / (n * total) - (n + 1.0) / n
+}
+
+/// Acumula colheitas, trocas e refeições de cada tick e, a cada
+/// `indicator_secs`, recalcula os indicadores de todos os escopos.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_indicators_system(
+    mut indicators: ResMut<Indicators>,
+    mut trades: EventReader<TradeEvent>,
+    mut production: EventReader<ProductionEvent>,
+    mut consumption: EventReader<ConsumptionEvent>,
+    mut transfers: EventReader<TransferEvent>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    catalog: Res<ItemCatalog>,
+    prices: Res<MarketPrices>,
+    parents: Query<&Parent>,
+    countries: Query<(Entity, &Country, &Children)>,
+    states: Query<(Entity, &Children), With<State>>,
+    cities: Query<(Entity, &Children), With<City>>,
+    persons: Query<(&Person, &Alive, &Employment, &JobSeeker, &Family)>,
+    households: Query<(&Household, Option<&Credit>)>,
+    businesses: Query<(
+        Option<&Shop>,
+        Option<&Wholesaler>,
+        Option<&Employer>,
+        Option<&Credit>,
+    )>,
+) {
+    for event in production.read() {
+        let prices = parents
+            .get(event.person)
+            .map_or(prices.base(), |city| prices.city(city.get()));
+        let value = prices.price(&catalog, event.item) as f64 * event.quantity as f64;
+        indicators.flows.entry(event.person).or_default().production += value;
+    }
+    for event in trades.read() {
+        let value = (event.unit_price * event.quantity) as f64;
+        // A troca conta uma vez, na cidade; para quem vende, é renda
+        indicators.flows.entry(event.city).or_default().trade += value;
+        indicators.flows.entry(event.seller).or_default().income += value;
+    }
+    for transfer in transfers.read() {
+        if let (TransferKind::Wage, Account::Agent(worker)) = (transfer.kind, transfer.to) {
+            indicators.flows.entry(worker).or_default().income += transfer.amount as f64;
+        }
+    }
+    for event in consumption.read() {
+        *indicators.basket.entry(event.item).or_insert(0.0) += event.quantity as f64;
+    }
+
+    if !clock
+        .tick
+        .is_multiple_of(clock.ticks_for(config.indicator_secs))
+    {
+        return;
+    }
+
+    let flows = std::mem::take(&mut indicators.flows);
+    let weights = indicators.weights(&catalog);
+    let goods_value = |prices: &PriceTable, items: &mut dyn Iterator<Item = (&ItemId, &i32)>| {
+        items
+            .filter(|(_, count)| **count > 0)
+            .map(|(item, count)| prices.price(&catalog, *item) as f64 * *count as f64)
+            .sum::<f64>()
+    };
+    // Parte de cada membro no caixa, na despensa e no saldo da casa no
+    // banco, que não desce de zero
+    let household_share = |prices: &PriceTable, family: &Family| {
+        family
+            .household
+            .and_then(|household| households.get(household).ok())
+            .map_or(0.0, |(household, credit)| {
+                let balance = credit.map_or(0.0, |credit| {
+                    credit.deposit as f64 - credit.loan.map_or(0.0, |loan| loan.balance as f64)
+                });
+                let wealth = household.purse as f64
+                    + goods_value(prices, &mut household.pantry.iter())
+                    + balance;
+                wealth.max(0.0) / household.members.len().max(1) as f64
+            })
+    };
+    let city_totals = |city: Entity, children: &Children| {
+        let prices = prices.city(city);
+        let mut totals = Totals {
+            trade: flows.get(&city).map_or(0.0, |flow| flow.trade),
+            ..default()
+        };
+        for &child in children.iter() {
+            let flow = flows.get(&child).cloned().unwrap_or_default();
+            totals.production += flow.production;
+            if let Ok((person, alive, employment, seeker, family)) = persons.get(child) {
+                if alive.0 {
+                    totals.employed += employment.0.is_some() as usize;
+                    totals.seekers += seeker.0 as usize;
+                    let goods = goods_value(prices, &mut person.inventory.iter());
+                    totals.money += person.gold as f64;
+                    totals
+                        .wealth
+                        .push(person.gold as f64 + goods + household_share(prices, family));
+                    totals.income.push(flow.income);
+                }
+            }
+            if let Ok((household, credit)) = households.get(child) {
+                totals.money += household.purse as f64;
+                totals.money += credit.map_or(0, |credit| credit.deposit) as f64;
+                let income = household
+                    .members
+                    .iter()
+                    .filter_map(|member| flows.get(member))
+                    .map(|flow| flow.income)
+                    .sum();
+                totals.household_income.push(income);
+            }
+            let Ok((shop, wholesaler, employer, credit)) = businesses.get(child) else {
+                continue;
+            };
+            if let Some(employer) = employer {
+                totals.wages += employer.wage as f64;
+                totals.employers += 1;
+            }
+            if let Some(wholesaler) = wholesaler {
+                totals.money += wholesaler.cash as f64;
+            }
+            if let Some(shop) = shop {
+                totals.money += shop.cash as f64;
+                totals.money += credit.map_or(0, |credit| credit.deposit) as f64;
+                for (item, details) in shop.items.iter() {
+                    let entry = totals.prices.entry(*item).or_insert((0.0, 0));
+                    entry.0 += details.price as f64;
+                    entry.1 += 1;
+                }
+            }
+        }
+        totals
+    };
+
+    let indicators = &mut *indicators;
+    let mut world = Totals::default();
+    for (country_entity, country_component, country_children) in countries.iter() {
+        let country_prices = prices.country(country_entity);
+        let mut country_totals = Totals::default();
+        for &state_entity in country_children.iter() {
+            let Ok((_, state_children)) = states.get(state_entity) else {
+                continue;
+            };
+            let mut state_totals = Totals::default();
+            for &city_entity in state_children.iter() {
+                let Ok((_, city_children)) = cities.get(city_entity) else {
+                    continue;
+                };
+                let totals = city_totals(city_entity, city_children);
+                let previous = indicators.cities.get(&city_entity);
+                let city = totals.finish(&weights, &catalog, country_prices, previous);
+                indicators.cities.insert(city_entity, city);
+                state_totals.merge(&totals);
+            }
+            let previous = indicators.states.get(&state_entity);
+            let state = state_totals.finish(&weights, &catalog, country_prices, previous);
+            indicators.states.insert(state_entity, state);
+            country_totals.merge(&state_totals);
+        }
+        let previous = indicators.countries.get(&country_entity);
+        let country = country_totals.finish(&weights, &catalog, country_prices, previous);
+        indicators.countries.insert(country_entity, country);
+        // Cada país na sua moeda; o mundo, na unidade de conta
+        world.merge_at(&country_totals, country_component.currency.rate);
+    }
+    // Só um item que nenhuma loja vende fica com o preço do catálogo
+    indicators.world = world.finish(&weights, &catalog, prices.base(), Some(&indicators.world));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gini_is_zero_for_equal_shares_and_near_one_for_a_single_holder() {
+        assert_eq!(gini(&[]), 0.0);
+        assert_eq!(gini(&[5.0]), 0.0);
+        assert_eq!(gini(&[0.0, 0.0, 0.0]), 0.0);
+        assert!(gini(&[7.0; 10]).abs() < 1e-12);
+
+        // Com um só dono, o Gini é 1 - 1/n
+        let mut values = vec![0.0; 99];
+        values.push(50.0);
+        assert!((gini(&values) - 0.99).abs() < 1e-12);
+        // A ordem não importa
+        values.reverse();
+        assert!((gini(&values) - 0.99).abs() < 1e-12);
+        assert!((gini(&[1.0, 3.0]) - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cpi_is_the_base_at_catalog_prices_and_follows_the_basket() {
+        let catalog = ItemCatalog::default();
+        let prices = PriceTable::default();
+        let apple = catalog.find("Apple").unwrap();
+        let potato = catalog.find("Potato").unwrap();
+        let weights = [(apple, 1.0), (potato, 3.0)];
+
+        // Sem lojas no escopo, os preços são os base do catálogo
+        let base = Totals::default().finish(&weights, &catalog, &prices, None);
+        assert_eq!(base.cpi, 100.0);
+        assert_eq!(base.inflation, 0.0);
+
+        // A maçã dobra de preço: pesa 10 de 25 na cesta a preços base
+        let mut totals = Totals::default();
+        let apple_price = 2.0 * catalog.get(apple).base_price as f64;
+        totals.prices.insert(apple, (2.0 * apple_price, 2));
+        let doubled = totals.finish(&weights, &catalog, &prices, Some(&base));
+        assert!((doubled.cpi - 140.0).abs() < 1e-9);
+        assert!((doubled.inflation - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn money_supply_counts_cash_and_deposits_and_velocity_divides_trade() {
+        let totals = Totals {
+            money: 200.0,
+            trade: 50.0,
+            production: 30.0,
+            ..default()
+        };
+        let indicators = totals.finish(&[], &ItemCatalog::default(), &PriceTable::default(), None);
+        assert_eq!(indicators.money_supply, 200.0);
+        assert_eq!(indicators.nominal_output, 80.0);
+        assert_eq!(
+            (indicators.production_value, indicators.trade_value),
+            (30.0, 50.0)
+        );
+        assert_eq!(indicators.velocity, 0.25);
+
+        // No mundo simulado, a oferta de moeda de cada país é o ouro dos
+        // seus agentes, e a do mundo soma as dos países pela cotação
+        let mut app = crate::build_app(ScenarioConfig {
+            seed: Some(7),
+            num_persons: 100,
+            num_shops: 10,
+            ..default()
+        });
+        let ticks = app
+            .world()
+            .resource::<SimClock>()
+            .ticks_for(app.world().resource::<ScenarioConfig>().indicator_secs);
+        for _ in 0..ticks {
+            app.update();
+        }
+        let world = app.world_mut();
+        let persons: usize = world
+            .query::<(&Person, &Alive)>()
+            .iter(world)
+            .filter(|(_, alive)| alive.0)
+            .map(|(person, _)| person.gold)
+            .sum();
+        let households: usize = world
+            .query::<(&Household, &Credit)>()
+            .iter(world)
+            .map(|(household, credit)| household.purse + credit.deposit)
+            .sum();
+        let shops: usize = world
+            .query::<(&Shop, &Credit)>()
+            .iter(world)
+            .map(|(shop, credit)| shop.cash + credit.deposit)
+            .sum();
+        let wholesalers: usize = world
+            .query::<&Wholesaler>()
+            .iter(world)
+            .map(|wholesaler| wholesaler.cash)
+            .sum();
+        let countries: Vec<(Entity, f64)> = world
+            .query::<(Entity, &Country)>()
+            .iter(world)
+            .map(|(entity, country)| (entity, country.currency.rate))
+            .collect();
+        let indicators = world.resource::<Indicators>();
+        let supply = |country: Entity| indicators.country(country).unwrap().money_supply;
+        let nominal: f64 = countries.iter().map(|&(country, _)| supply(country)).sum();
+        assert_eq!(nominal, (persons + households + shops + wholesalers) as f64);
+        let converted: f64 = countries
+            .iter()
+            .map(|&(country, rate)| supply(country) * rate)
+            .sum();
+        assert!((indicators.world.money_supply - converted).abs() < 1e-6);
+    }
+
+    #[test]
+    fn world_totals_convert_each_currency_at_its_rate() {
+        let home = Totals {
+            money: 100.0,
+            trade: 10.0,
+            wealth: vec![50.0, 50.0],
+            ..default()
+        };
+        let foreign = Totals {
+            money: 100.0,
+            trade: 10.0,
+            wealth: vec![50.0, 50.0],
+            ..default()
+        };
+        // A moeda de fora vale o triplo: quem tem 50 dela é mais rico
+        let mut world = Totals::default();
+        world.merge_at(&home, 1.0);
+        world.merge_at(&foreign, 3.0);
+        let indicators = world.finish(&[], &ItemCatalog::default(), &PriceTable::default(), None);
+        assert_eq!(indicators.money_supply, 400.0);
+        assert_eq!(indicators.nominal_output, 40.0);
+        assert!((indicators.wealth_gini - gini(&[50.0, 50.0, 150.0, 150.0])).abs() < 1e-12);
+        assert!(indicators.wealth_gini > 0.0);
+    }
+}
@@ -0,0 +1,524 @@
+use std::path::Path;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::Season;
+use crate::components::Shop;
+use crate::config::{read_data_file, ConfigError};
+use crate::land::TerrainFactors;
+
+/// Catálogo padrão, embutido no binário.
+const DEFAULT_CATALOG: &str = include_str!("../data/items.toml");
+
+/// Identificador estável de um item do catálogo.
+///
+/// Inventários, lojas e históricos de preço usam o id como chave, então
+/// preço, estoque e demais dados variáveis ficam fora da identidade do item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ItemId(pub u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemType {
+    Food { nutritional_value: u32 },
+    Tool { durability: u32 },
+    Weapon { attack_damage: u32 },
+    Material,
+}
+
+/// Definição estática de um item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemDef {
+    pub id: ItemId,
+    pub name: String,
+    pub item_type: ItemType,
+    pub base_price: usize,
+    /// Estoque inicial de cada loja que vende o item.
+    pub initial_stock: usize,
+    /// Se o item pode ser obtido plantando.
+    pub growable: bool,
+    /// Rendimento da cultura em cada terreno, multiplicando a colheita.
+    #[serde(default)]
+    pub yields: TerrainFactors,
+    /// Segundos de crescimento, com água, até a cultura amadurecer.
+    #[serde(default = "default_grow_secs")]
+    pub grow_secs: f32,
+    /// Unidades colhidas de uma planta em um lote de fertilidade 1.
+    #[serde(default = "default_harvest")]
+    pub harvest: usize,
+    /// Estações em que a cultura pode ser semeada e cresce.
+    #[serde(default = "default_seasons")]
+    pub seasons: Vec<Season>,
+}
+
+impl ItemDef {
+    pub fn grows_in(&self, season: Season) -> bool {
+        self.growable && self.seasons.contains(&season)
+    }
+}
+
+/// Entrada do arquivo de catálogo; o id é atribuído pela ordem no arquivo.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemEntry {
+    name: String,
+    kind: ItemType,
+    base_price: usize,
+    #[serde(default = "default_initial_stock")]
+    initial_stock: usize,
+    #[serde(default)]
+    growable: bool,
+    #[serde(default)]
+    yields: Option<TerrainFactors>,
+    grow_secs: Option<f32>,
+    harvest: Option<usize>,
+    seasons: Option<Vec<Season>>,
+}
+
+fn default_initial_stock() -> usize {
+    10
+}
+
+fn default_grow_secs() -> f32 {
+    10.0
+}
+
+fn default_harvest() -> usize {
+    10
+}
+
+fn default_seasons() -> Vec<Season> {
+    Season::ALL.to_vec()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CatalogFile {
+    items: Vec<ItemEntry>,
+}
+
+/// Registro de todos os itens existentes na simulação.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct ItemCatalog {
+    items: Vec<ItemDef>,
+}
+
+impl ItemCatalog {
+    pub fn empty() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    /// Lê e valida um catálogo a partir de um arquivo TOML, RON ou JSON.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let file: CatalogFile = read_data_file(path)?;
+        Self::from_entries(file.items)
+    }
+
+    fn from_entries(entries: Vec<ItemEntry>) -> Result<Self, ConfigError> {
+        let mut catalog = Self::empty();
+        for (i, entry) in entries.into_iter().enumerate() {
+            let invalid = |field: &str, reason: &str| ConfigError::Invalid {
+                field: format!("items[{}].{}", i, field),
+                reason: reason.to_string(),
+            };
+
+            if catalog.find(&entry.name).is_some() {
+                return Err(invalid("name", &format!("duplicate item `{}`", entry.name)));
+            }
+            if entry.base_price == 0 {
+                return Err(invalid("base_price", "must be at least 1"));
+            }
+            match entry.kind {
+                ItemType::Food {
+                    nutritional_value: 0,
+                } => return Err(invalid("kind", "food must have a nutritional_value")),
+                ItemType::Food { .. } => {}
+                _ if entry.growable => return Err(invalid("growable", "only food can be grown")),
+                _ => {}
+            }
+            if !entry.growable {
+                for (field, present) in [
+                    ("yields", entry.yields.is_some()),
+                    ("grow_secs", entry.grow_secs.is_some()),
+                    ("harvest", entry.harvest.is_some()),
+                    ("seasons", entry.seasons.is_some()),
+                ] {
+                    if present {
+                        return Err(invalid(field, "only growable items have it"));
+                    }
+                }
+            }
+            if entry
+                .grow_secs
+                .is_some_and(|secs| secs.is_nan() || secs <= 0.0)
+            {
+                return Err(invalid("grow_secs", "must be positive"));
+            }
+            if entry.harvest == Some(0) {
+                return Err(invalid("harvest", "must be at least 1"));
+            }
+            if entry
+                .seasons
+                .as_ref()
+                .is_some_and(|seasons| seasons.is_empty())
+            {
+                return Err(invalid("seasons", "must list at least one season"));
+            }
+            if let Some(yields) = &entry.yields {
+                if let Some((terrain, _)) = yields
+                    .values()
+                    .into_iter()
+                    .find(|(_, value)| value.is_nan() || *value < 0.0)
+                {
+                    return Err(invalid(
+                        &format!("yields.{}", terrain),
+                        "must not be negative",
+                    ));
+                }
+            }
+
+            let id = catalog.register(&entry.name, entry.kind, entry.base_price);
+            let item = &mut catalog.items[id.0 as usize];
+            item.initial_stock = entry.initial_stock;
+            item.growable = entry.growable;
+            item.yields = entry.yields.unwrap_or_default();
+            item.grow_secs = entry.grow_secs.unwrap_or_else(default_grow_secs);
+            item.harvest = entry.harvest.unwrap_or_else(default_harvest);
+            item.seasons = entry.seasons.unwrap_or_else(default_seasons);
+        }
+
+        if catalog.foods().next().is_none() {
+            return Err(ConfigError::Invalid {
+                field: "items".to_string(),
+                reason: "the catalog needs at least one food".to_string(),
+            });
+        }
+        Ok(catalog)
+    }
+
+    /// Adiciona um item e devolve o id atribuído a ele.
+    pub fn register(&mut self, name: &str, item_type: ItemType, base_price: usize) -> ItemId {
+        let id = ItemId(self.items.len() as u32);
+        self.items.push(ItemDef {
+            id,
+            name: name.to_string(),
+            item_type,
+            base_price,
+            initial_stock: default_initial_stock(),
+            growable: false,
+            yields: TerrainFactors::default(),
+            grow_secs: default_grow_secs(),
+            harvest: default_harvest(),
+            seasons: default_seasons(),
+        });
+        id
+    }
+
+    pub fn get(&self, id: ItemId) -> &ItemDef {
+        &self.items[id.0 as usize]
+    }
+
+    pub fn name(&self, id: ItemId) -> &str {
+        &self.get(id).name
+    }
+
+    pub fn find(&self, name: &str) -> Option<ItemId> {
+        self.items
+            .iter()
+            .find(|item| item.name == name)
+            .map(|item| item.id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ItemDef> {
+        self.items.iter()
+    }
+
+    pub fn foods(&self) -> impl Iterator<Item = &ItemDef> {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.item_type, ItemType::Food { .. }))
+    }
+
+    /// Valor nutricional do item, se for comida.
+    pub fn nutritional_value(&self, id: ItemId) -> Option<u32> {
+        match self.get(id).item_type {
+            ItemType::Food { nutritional_value } => Some(nutritional_value),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ItemCatalog {
+    fn default() -> Self {
+        let file: CatalogFile =
+            toml::from_str(DEFAULT_CATALOG).expect("data/items.toml is invalid");
+        Self::from_entries(file.items).expect("data/items.toml is invalid")
+    }
+}
+
+/// Preço médio praticado pelas lojas de um país para cada item, na moeda
+/// dele. Itens sem loja usam o preço base do catálogo.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceTable(#[serde(with = "crate::snapshot::map_as_vec")] pub HashMap<ItemId, f32>);
+
+/// Preços de mercado de cada país, atualizados junto com os preços das
+/// lojas. Cada país tem a sua moeda, então a média de um item só junta lojas
+/// do mesmo país.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MarketPrices {
+    pub countries: HashMap<Entity, PriceTable>,
+    /// País de cada cidade.
+    pub cities: HashMap<Entity, Entity>,
+    base: PriceTable,
+}
+
+impl MarketPrices {
+    pub fn new(countries: HashMap<Entity, PriceTable>, cities: HashMap<Entity, Entity>) -> Self {
+        MarketPrices {
+            countries,
+            cities,
+            base: PriceTable::default(),
+        }
+    }
+
+    /// Médias das lojas, cada uma com a cidade em que fica.
+    pub fn from_shops<'a>(
+        shops: impl Iterator<Item = (Entity, &'a Shop)>,
+        cities: HashMap<Entity, Entity>,
+    ) -> Self {
+        let mut by_country: HashMap<Entity, Vec<&Shop>> = HashMap::new();
+        for (city, shop) in shops {
+            if let Some(&country) = cities.get(&city) {
+                by_country.entry(country).or_default().push(shop);
+            }
+        }
+        let countries = by_country
+            .into_iter()
+            .map(|(country, shops)| (country, PriceTable::from_shops(shops.into_iter())))
+            .collect();
+        MarketPrices::new(countries, cities)
+    }
+
+    /// Preços do país; sem loja nele, os do catálogo.
+    pub fn country(&self, country: Entity) -> &PriceTable {
+        self.countries.get(&country).unwrap_or(&self.base)
+    }
+
+    /// Preços do país da cidade.
+    pub fn city(&self, city: Entity) -> &PriceTable {
+        self.cities
+            .get(&city)
+            .map_or(&self.base, |&country| self.country(country))
+    }
+
+    /// Preços base do catálogo, para quem não está em país nenhum.
+    pub fn base(&self) -> &PriceTable {
+        &self.base
+    }
+}
+
+impl PriceTable {
+    pub fn from_shops<'a>(shops: impl Iterator<Item = &'a Shop>) -> Self {
+        let mut totals: HashMap<ItemId, (f32, usize)> = HashMap::new();
+        for shop in shops {
+            for (item, details) in shop.items.iter() {
+                let entry = totals.entry(*item).or_insert((0.0, 0));
+                entry.0 += details.price as f32;
+                entry.1 += 1;
+            }
+        }
+        PriceTable(
+            totals
+                .into_iter()
+                .map(|(item, (total, count))| (item, total / count as f32))
+                .collect(),
+        )
+    }
+
+    pub fn price(&self, catalog: &ItemCatalog, item: ItemId) -> f32 {
+        self.0
+            .get(&item)
+            .copied()
+            .unwrap_or(catalog.get(item).base_price as f32)
+    }
+
+    /// Comida com mais valor nutricional por ouro que cabe no orçamento.
+    pub fn best_food_for(&self, catalog: &ItemCatalog, budget: usize) -> Option<ItemId> {
+        catalog
+            .foods()
+            .filter(|item| self.price(catalog, item.id) <= budget as f32)
+            .max_by(|a, b| {
+                let value = |item: &ItemDef| {
+                    catalog.nutritional_value(item.id).unwrap_or(0) as f32
+                        / self.price(catalog, item.id).max(1.0)
+                };
+                value(a).total_cmp(&value(b))
+            })
+            .map(|item| item.id)
+    }
+
+    /// Cultura que pode ser semeada na estação e hoje vale mais no mercado.
+    pub fn most_valuable_crop(&self, catalog: &ItemCatalog, season: Season) -> Option<ItemId> {
+        catalog
+            .iter()
+            .filter(|item| item.grows_in(season))
+            .max_by(|a, b| {
+                self.price(catalog, a.id)
+                    .total_cmp(&self.price(catalog, b.id))
+            })
+            .map(|item| item.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPLE: &str = r#"
+[[items]]
+name = "Apple"
+kind = { food = { nutritional_value = 50 } }
+base_price = 10
+growable = true
+"#;
+
+    fn parse(text: &str) -> Result<ItemCatalog, ConfigError> {
+        let file: CatalogFile = toml::from_str(text).map_err(|e| ConfigError::Parse {
+            path: "items.toml".into(),
+            message: e.to_string(),
+        })?;
+        ItemCatalog::from_entries(file.items)
+    }
+
+    /// Campo apontado pelo erro de validação de um catálogo com a maçã e
+    /// mais `extra`.
+    fn invalid_field(extra: &str) -> String {
+        match parse(&format!("{}{}", APPLE, extra)) {
+            Err(ConfigError::Invalid { field, .. }) => field,
+            Err(err) => panic!("expected an invalid field, got {}", err),
+            Ok(_) => panic!("catalog with {} was accepted", extra),
+        }
+    }
+
+    #[test]
+    fn ids_follow_file_order_and_omitted_fields_use_defaults() {
+        let catalog = parse(&format!(
+            "{}\n[[items]]\nname = \"Stone\"\nkind = \"material\"\nbase_price = 1\n",
+            APPLE
+        ))
+        .unwrap();
+        assert_eq!(catalog.find("Apple"), Some(ItemId(0)));
+        assert_eq!(catalog.find("Stone"), Some(ItemId(1)));
+        let apple = catalog.get(ItemId(0));
+        assert_eq!(apple.initial_stock, 10);
+        assert_eq!(apple.seasons, Season::ALL.to_vec());
+        assert_eq!(catalog.nutritional_value(ItemId(1)), None);
+
+        // O catálogo embutido também passa pela validação
+        assert!(ItemCatalog::default().find("Potato").is_some());
+    }
+
+    #[test]
+    fn invalid_items_name_the_bad_field() {
+        let item = |fields: &str| format!("\n[[items]]\nname = \"Other\"\n{}\n", fields);
+        let food = "kind = { food = { nutritional_value = 5 } }\nbase_price = 3";
+
+        assert_eq!(
+            invalid_field("\n[[items]]\nname = \"Apple\"\nkind = \"material\"\nbase_price = 1\n"),
+            "items[1].name"
+        );
+        assert_eq!(
+            invalid_field(&item("kind = \"material\"\nbase_price = 0")),
+            "items[1].base_price"
+        );
+        assert_eq!(
+            invalid_field(&item(
+                "kind = { food = { nutritional_value = 0 } }\nbase_price = 1"
+            )),
+            "items[1].kind"
+        );
+        assert_eq!(
+            invalid_field(&item(
+                "kind = \"material\"\nbase_price = 1\ngrowable = true"
+            )),
+            "items[1].growable"
+        );
+        assert_eq!(
+            invalid_field(&item(&format!("{}\nharvest = 5", food))),
+            "items[1].harvest"
+        );
+        for (fields, field) in [
+            ("grow_secs = 0.0", "items[1].grow_secs"),
+            ("harvest = 0", "items[1].harvest"),
+            ("seasons = []", "items[1].seasons"),
+            ("yields = { desert = -1.0 }", "items[1].yields.desert"),
+        ] {
+            let fields = format!("{}\ngrowable = true\n{}", food, fields);
+            assert_eq!(invalid_field(&item(&fields)), field);
+        }
+
+        // Sem nenhuma comida ninguém come
+        let tools =
+            "[[items]]\nname = \"Hoe\"\nkind = { tool = { durability = 5 } }\nbase_price = 1\n";
+        assert!(matches!(
+            parse(tools),
+            Err(ConfigError::Invalid { field, .. }) if field == "items"
+        ));
+    }
+
+    #[test]
+    fn unknown_fields_seasons_and_terrains_are_rejected() {
+        for extra in [
+            "price = 3",
+            "seasons = [\"monsoon\"]",
+            "yields = { swamp = 1.0 }",
+        ] {
+            let text = format!("{}{}\n", APPLE, extra);
+            let err = parse(&text).map(|_| ()).unwrap_err();
+            assert!(matches!(err, ConfigError::Parse { .. }), "{}", err);
+        }
+
+        let path = Path::new("no/such/items.toml");
+        assert!(matches!(
+            ItemCatalog::load(path),
+            Err(ConfigError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn market_prices_average_each_country_apart() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let shop = |price: usize| {
+            let mut shop = Shop::default();
+            shop.stock_item(apple, price, 1);
+            shop
+        };
+        let (home, away) = (Entity::from_raw(1), Entity::from_raw(2));
+        let (city, other_city, far_city) = (
+            Entity::from_raw(3),
+            Entity::from_raw(4),
+            Entity::from_raw(5),
+        );
+        let shops = [shop(10), shop(20), shop(400)];
+        let cities: HashMap<Entity, Entity> = [(city, home), (other_city, away), (far_city, away)]
+            .into_iter()
+            .collect();
+
+        // A loja de 400 fica em outro país, com outra moeda
+        let prices = MarketPrices::from_shops(
+            [city, city, other_city].into_iter().zip(shops.iter()),
+            cities,
+        );
+        assert_eq!(prices.city(city).price(&catalog, apple), 15.0);
+        assert_eq!(prices.city(far_city).price(&catalog, apple), 400.0);
+        assert_eq!(prices.country(away).price(&catalog, apple), 400.0);
+        // Fora de qualquer país, o preço base do catálogo
+        let base = catalog.get(apple).base_price as f32;
+        assert_eq!(
+            prices.city(Entity::from_raw(6)).price(&catalog, apple),
+            base
+        );
+    }
+}
@@ -0,0 +1,616 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SimClock;
+use crate::components::{
+    Alive, Employer, Employment, Job, JobSeeker, Person, PersonActions, Position, Shop, Wholesaler,
+};
+use crate::config::ScenarioConfig;
+use crate::events::{ProductionEvent, TaxEvent, TransferEvent};
+use crate::ledger::{Account, TransferKind};
+use crate::taxes::{self, Tax, TaxTable};
+
+/// Parâmetros do mercado de trabalho.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaborConfig {
+    /// Salário inicial das vagas, em ouro por segundo trabalhado.
+    pub base_wage: f32,
+    /// Piso do salário nos ajustes.
+    pub min_wage: f32,
+    /// Duração de um turno de trabalho.
+    pub shift_secs: f32,
+    /// Vagas no máximo em cada loja.
+    pub shop_jobs: usize,
+    /// Vagas no máximo no atacadista (a fazenda) de cada cidade.
+    pub wholesaler_jobs: usize,
+    /// Uma vaga só fica aberta se o caixa paga o salário por este tempo.
+    pub payroll_secs: f32,
+    /// Intervalo entre os ajustes de salário e de vagas.
+    pub adjust_secs: f32,
+    /// Variação do salário em cada ajuste, para cima ou para baixo.
+    pub wage_step: f32,
+    /// Quanto cada unidade de distância desconta do salário na escolha da
+    /// vaga, em ouro por segundo.
+    pub commute_cost: f32,
+    /// Distância máxima até o emprego.
+    pub max_commute: f32,
+    /// Unidades que um turno numa loja produz, com habilidade 1.
+    pub shop_output: usize,
+}
+
+impl Default for LaborConfig {
+    fn default() -> Self {
+        Self {
+            base_wage: 0.5,
+            min_wage: 0.1,
+            shift_secs: 10.0,
+            shop_jobs: 2,
+            wholesaler_jobs: 10,
+            payroll_secs: 60.0,
+            adjust_secs: 30.0,
+            wage_step: 0.05,
+            commute_cost: 0.002,
+            max_commute: 60.0,
+            shop_output: 4,
+        }
+    }
+}
+
+impl LaborConfig {
+    /// Ouro ganho em um turno completo.
+    pub fn shift_pay(&self, wage: f32) -> f32 {
+        wage * self.shift_secs
+    }
+
+    /// Quantos empregos, até `max_jobs`, um caixa consegue pagar por
+    /// `payroll_secs`.
+    pub fn affordable_jobs(&self, cash: usize, wage: f32, max_jobs: usize) -> usize {
+        let payroll = wage * self.payroll_secs;
+        if payroll > 0.0 {
+            max_jobs.min((cash as f32 / payroll) as usize)
+        } else {
+            max_jobs
+        }
+    }
+
+    /// Parte do caixa que um empregador guarda para pagar `max_jobs`
+    /// empregos por `payroll_secs` e que, portanto, não gasta no mercado.
+    pub fn payroll_reserve(&self, employer: Option<&Employer>, max_jobs: usize) -> usize {
+        employer.map_or(0, |employer| {
+            (employer.wage * self.payroll_secs * max_jobs as f32).ceil() as usize
+        })
+    }
+
+    /// Empregador recém-criado, com as vagas que o caixa inicial paga.
+    pub fn employer(&self, cash: usize, max_jobs: usize) -> Employer {
+        let vacancies = self.affordable_jobs(cash, self.base_wage, max_jobs);
+        Employer {
+            wage: self.base_wage,
+            vacancies,
+            posted: vacancies,
+            applicants: 0,
+        }
+    }
+}
+
+pub fn distance(a: &Position, b: &Position) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Onde fica o empregador, seja loja ou atacadista.
+fn workplace<'a>(
+    shop: Option<&'a Shop>,
+    wholesaler: Option<&'a Wholesaler>,
+) -> Option<&'a Position> {
+    shop.map(|shop| &shop.position)
+        .or(wholesaler.map(|wholesaler| &wholesaler.position))
+}
+
+/// A cada `adjust_secs`, ajusta o salário de cada empregador pela relação
+/// entre candidatos e vagas (sobem quando faltam candidatos, descem quando
+/// sobram) e reabre as vagas que o caixa consegue pagar. Se o caixa não
+/// cobre nem a equipe atual, os menos hábeis são demitidos.
+pub fn adjust_wages_system(
+    mut employers: Query<(Entity, &mut Employer, Option<&Shop>, Option<&Wholesaler>)>,
+    mut persons: Query<(Entity, &mut Person, &mut Employment)>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+) {
+    if !clock
+        .tick
+        .is_multiple_of(clock.ticks_for(config.labor.adjust_secs))
+    {
+        return;
+    }
+    let config = &config.labor;
+
+    let mut workers: BTreeMap<Entity, Vec<(f32, Entity)>> = BTreeMap::new();
+    for (entity, person, employment) in persons.iter() {
+        if let Some(job) = &employment.0 {
+            workers
+                .entry(job.employer)
+                .or_default()
+                .push((person.skill, entity));
+        }
+    }
+
+    for (entity, mut employer, shop, wholesaler) in employers.iter_mut() {
+        let (cash, max_jobs) = match (shop, wholesaler) {
+            (Some(shop), _) => (shop.cash, config.shop_jobs),
+            (None, Some(wholesaler)) => (wholesaler.cash, config.wholesaler_jobs),
+            (None, None) => continue,
+        };
+
+        if employer.posted > 0 && employer.applicants < employer.posted {
+            employer.wage *= 1.0 + config.wage_step;
+        } else if employer.applicants > employer.posted {
+            employer.wage = (employer.wage * (1.0 - config.wage_step)).max(config.min_wage);
+        }
+        employer.applicants = 0;
+
+        let jobs = config.affordable_jobs(cash, employer.wage, max_jobs);
+
+        let mut staff = workers.remove(&entity).unwrap_or_default();
+        if staff.len() > jobs {
+            // Os menos hábeis saem primeiro
+            staff.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            for &(_, worker) in &staff[..staff.len() - jobs] {
+                if let Ok((_, mut person, mut employment)) = persons.get_mut(worker) {
+                    employment.0 = None;
+                    if person.action == PersonActions::Working {
+                        person.action = PersonActions::Idle;
+                    }
+                }
+            }
+        }
+        employer.vacancies = jobs.saturating_sub(staff.len());
+        employer.posted = employer.vacancies;
+    }
+}
+
+/// Casa quem está em `SeekingWork` com as vagas da própria cidade. Cada
+/// candidato escolhe a vaga de maior salário descontado o deslocamento, e
+/// cada empregador contrata os candidatos mais hábeis até preencher as vagas.
+/// Quem fica de fora é marcado com `JobSeeker`.
+#[allow(clippy::type_complexity)]
+pub fn hiring_system(
+    mut persons: Query<(
+        Entity,
+        &mut Person,
+        &Parent,
+        &mut Employment,
+        &mut JobSeeker,
+    )>,
+    mut employers: Query<(
+        Entity,
+        &mut Employer,
+        Option<&Shop>,
+        Option<&Wholesaler>,
+        &Parent,
+    )>,
+    config: Res<ScenarioConfig>,
+) {
+    let config = &config.labor;
+
+    let mut applications: BTreeMap<Entity, Vec<(f32, Entity)>> = BTreeMap::new();
+    for (entity, mut person, city, _, mut seeker) in persons.iter_mut() {
+        // Quem voltou a semear sai da fila do desemprego
+        if seeker.0 && person.action == PersonActions::Seeding {
+            seeker.0 = false;
+        }
+        if person.action != PersonActions::SeekingWork {
+            continue;
+        }
+        person.action = PersonActions::Idle;
+
+        let best = employers
+            .iter()
+            .filter(|(_, employer, _, _, parent)| {
+                parent.get() == city.get() && employer.vacancies > 0
+            })
+            .filter_map(|(employer_entity, employer, shop, wholesaler, _)| {
+                let commute = distance(&person.position, workplace(shop, wholesaler)?);
+                (commute <= config.max_commute).then_some((
+                    employer_entity,
+                    employer.wage - config.commute_cost * commute,
+                ))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+
+        match best {
+            Some((employer, _)) => applications
+                .entry(employer)
+                .or_default()
+                .push((person.skill, entity)),
+            None => seeker.0 = true,
+        }
+    }
+
+    for (employer_entity, mut candidates) in applications {
+        let Ok((_, mut employer, _, _, _)) = employers.get_mut(employer_entity) else {
+            continue;
+        };
+        employer.applicants += candidates.len();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, candidate) in candidates {
+            let Ok((_, _, _, mut employment, mut seeker)) = persons.get_mut(candidate) else {
+                continue;
+            };
+            if employer.vacancies > 0 {
+                employer.vacancies -= 1;
+                employment.0 = Some(Job {
+                    employer: employer_entity,
+                    worked_secs: 0.0,
+                    accrued: 0.0,
+                    withheld: [0.0; 3],
+                });
+                seeker.0 = false;
+            } else {
+                seeker.0 = true;
+            }
+        }
+    }
+}
+
+/// Avança o turno de quem está em `Working` e paga o salário de cada tick
+/// trabalhado, retendo o imposto de renda da cidade da Person, que o
+/// empregador repassa aos tesouros em ouro inteiro. Se o empregador não tem
+/// caixa para pagar (ou deixou de existir), o empregado é dispensado. Ao fim
+/// de um turno numa loja, o trabalho vira estoque do item que ela tem em
+/// menor quantidade.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn work_system(
+    mut persons: Query<(Entity, &mut Person, &Alive, &mut Employment, &Parent)>,
+    mut employers: Query<(&Employer, Option<&mut Shop>, Option<&mut Wholesaler>)>,
+    table: Res<TaxTable>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut tax_events: EventWriter<TaxEvent>,
+    mut production: EventWriter<ProductionEvent>,
+) {
+    let config = &config.labor;
+    let dt = clock.delta_secs();
+
+    for (entity, mut person, alive, mut employment, city) in persons.iter_mut() {
+        if !alive.0 || person.action != PersonActions::Working {
+            continue;
+        }
+        let Some(job) = employment.0.as_mut() else {
+            person.action = PersonActions::Idle;
+            continue;
+        };
+        let Ok((employer, mut shop, mut wholesaler)) = employers.get_mut(job.employer) else {
+            employment.0 = None;
+            person.action = PersonActions::Idle;
+            continue;
+        };
+
+        job.worked_secs += dt;
+        let wage = employer.wage * dt;
+        let shares = table.shares(city.get(), Tax::Income, wage);
+        let mut net = wage;
+        for (withheld, (_, share)) in job.withheld.iter_mut().zip(&shares) {
+            *withheld += share;
+            net -= share;
+        }
+        job.accrued += net;
+        let pay = job.accrued.floor() as usize;
+        let due: Vec<(usize, Entity, usize)> = job
+            .withheld
+            .iter()
+            .zip(&shares)
+            .enumerate()
+            .map(|(i, (withheld, (treasury, _)))| (i, *treasury, withheld.floor() as usize))
+            .filter(|(.., amount)| *amount > 0)
+            .collect();
+        if pay > 0 || !due.is_empty() {
+            let cash = match (shop.as_mut(), wholesaler.as_mut()) {
+                (Some(shop), _) => &mut shop.cash,
+                (None, Some(wholesaler)) => &mut wholesaler.cash,
+                (None, None) => continue,
+            };
+            let withheld: usize = due.iter().map(|(.., amount)| amount).sum();
+            if *cash < pay + withheld {
+                employment.0 = None;
+                person.action = PersonActions::Idle;
+                continue;
+            }
+            *cash -= pay + withheld;
+            if pay > 0 {
+                person.gold += pay;
+                job.accrued -= pay as f32;
+                transfers.send(TransferEvent {
+                    from: Account::Agent(job.employer),
+                    to: Account::Agent(entity),
+                    amount: pay,
+                    kind: TransferKind::Wage,
+                });
+            }
+            for (i, treasury, amount) in due {
+                job.withheld[i] -= amount as f32;
+                taxes::pay(
+                    &mut transfers,
+                    &mut tax_events,
+                    job.employer,
+                    city.get(),
+                    treasury,
+                    Tax::Income,
+                    amount,
+                );
+            }
+        }
+
+        if job.worked_secs < config.shift_secs {
+            continue;
+        }
+        job.worked_secs = 0.0;
+        person.action = PersonActions::Idle;
+
+        if let Some(shop) = shop.as_mut() {
+            let quantity = ((config.shop_output as f32 * person.skill).round() as usize).max(1);
+            let Some((&item, details)) = shop
+                .items
+                .iter_mut()
+                .min_by_key(|(item, details)| (details.stock, **item))
+            else {
+                continue;
+            };
+            details.stock += quantity;
+            production.send(ProductionEvent {
+                person: entity,
+                item,
+                quantity,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_and_reserve_follow_the_payroll_window() {
+        let config = LaborConfig::default();
+
+        // 0.5/s por 60 s: cada vaga pede 30 de caixa
+        assert_eq!(config.affordable_jobs(100, 0.5, 10), 3);
+        assert_eq!(config.affordable_jobs(1000, 0.5, 10), 10);
+        assert_eq!(config.affordable_jobs(29, 0.5, 10), 0);
+
+        let employer = config.employer(100, 2);
+        assert_eq!(employer.vacancies, 2);
+        assert_eq!(employer.posted, 2);
+        assert_eq!(config.payroll_reserve(Some(&employer), 2), 60);
+        assert_eq!(config.payroll_reserve(None, 2), 0);
+    }
+
+    #[test]
+    fn seekers_take_the_best_wage_net_of_commute_and_the_most_skilled_get_hired() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        let city = world.spawn_empty().id();
+        let employer = |world: &mut World, wage: f32, x: f32| {
+            let position = Position { x, y: 0.0 };
+            let employer = Employer {
+                wage,
+                vacancies: 1,
+                ..default()
+            };
+            let entity = world
+                .spawn((
+                    employer,
+                    Wholesaler {
+                        cash: 1000,
+                        position,
+                    },
+                ))
+                .id();
+            world.entity_mut(city).add_child(entity);
+            entity
+        };
+        // Paga menos, mas fica ao lado; a de salário maior custa 0,1/s de
+        // deslocamento e a de salário alto está além de `max_commute`
+        let near = employer(&mut world, 0.5, 0.0);
+        let far = employer(&mut world, 0.55, 50.0);
+        let unreachable = employer(&mut world, 5.0, 100.0);
+        let seekers: Vec<Entity> = [0.2, 0.9, 0.5]
+            .into_iter()
+            .map(|skill| {
+                let person = Person {
+                    action: PersonActions::SeekingWork,
+                    skill,
+                    ..default()
+                };
+                let person = world
+                    .spawn((person, Employment::default(), JobSeeker::default()))
+                    .id();
+                world.entity_mut(city).add_child(person);
+                person
+            })
+            .collect();
+
+        world.run_system_once(hiring_system).unwrap();
+        let employer = world.get::<Employer>(near).unwrap();
+        assert_eq!((employer.applicants, employer.vacancies), (3, 0));
+        for entity in [far, unreachable] {
+            assert_eq!(world.get::<Employer>(entity).unwrap().applicants, 0);
+        }
+        let hired: Vec<Option<Entity>> = seekers
+            .iter()
+            .map(|&person| {
+                let employment = world.get::<Employment>(person).unwrap();
+                employment.0.as_ref().map(|job| job.employer)
+            })
+            .collect();
+        assert_eq!(hired, vec![None, Some(near), None]);
+        let waiting: Vec<bool> = seekers
+            .iter()
+            .map(|&person| world.get::<JobSeeker>(person).unwrap().0)
+            .collect();
+        assert_eq!(waiting, vec![true, false, true]);
+        let idle =
+            |person: &Entity| world.get::<Person>(*person).unwrap().action == PersonActions::Idle;
+        assert!(seekers.iter().all(idle));
+    }
+
+    #[test]
+    fn wages_follow_applicants_and_staff_beyond_the_cash_is_laid_off() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(SimClock::new(1.0, 100.0));
+        let employer = |posted: usize, applicants: usize| Employer {
+            wage: 0.5,
+            vacancies: posted,
+            posted,
+            applicants,
+        };
+        let short = world
+            .spawn((
+                employer(2, 1),
+                Shop {
+                    cash: 1000,
+                    ..default()
+                },
+            ))
+            .id();
+        let crowded = world
+            .spawn((
+                employer(1, 3),
+                Wholesaler {
+                    cash: 1000,
+                    ..default()
+                },
+            ))
+            .id();
+        // 40 de caixa pagam um empregado por `payroll_secs`, não dois
+        let broke = world
+            .spawn((
+                employer(0, 0),
+                Shop {
+                    cash: 40,
+                    ..default()
+                },
+            ))
+            .id();
+        let mut worker = |skill: f32| {
+            let job = Job {
+                employer: broke,
+                worked_secs: 0.0,
+                accrued: 0.0,
+                withheld: [0.0; 3],
+            };
+            let person = Person {
+                action: PersonActions::Working,
+                skill,
+                ..default()
+            };
+            world.spawn((person, Employment(Some(job)))).id()
+        };
+        let skilled = worker(0.9);
+        let unskilled = worker(0.3);
+
+        world.run_system_once(adjust_wages_system).unwrap();
+        let employer = world.get::<Employer>(short).unwrap();
+        assert!((employer.wage - 0.525).abs() < 1e-6);
+        assert_eq!((employer.vacancies, employer.applicants), (2, 0));
+        let employer = world.get::<Employer>(crowded).unwrap();
+        assert!((employer.wage - 0.475).abs() < 1e-6);
+        let employer = world.get::<Employer>(broke).unwrap();
+        assert_eq!((employer.wage, employer.vacancies), (0.5, 0));
+        assert!(world.get::<Employment>(skilled).unwrap().0.is_some());
+        assert!(world.get::<Employment>(unskilled).unwrap().0.is_none());
+        let person = world.get::<Person>(unskilled).unwrap();
+        assert_eq!(person.action, PersonActions::Idle);
+    }
+
+    #[test]
+    fn work_pays_each_tick_net_of_income_tax_until_the_employer_runs_dry() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::components::{City, Country, State};
+        use crate::taxes::TaxRates;
+
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(SimClock::new(1.0, 100.0));
+        world.init_resource::<TaxTable>();
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<TaxEvent>>();
+        world.init_resource::<Events<ProductionEvent>>();
+        let country = world.spawn(Country::default()).id();
+        let state = world.spawn(State::default()).id();
+        let city = world
+            .spawn(City {
+                taxes: TaxRates {
+                    income: 0.25,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        world.entity_mut(country).add_child(state);
+        world.entity_mut(state).add_child(city);
+        world.run_system_once(taxes::tax_table_system).unwrap();
+
+        let shop = world
+            .spawn((
+                Employer {
+                    wage: 2.0,
+                    ..default()
+                },
+                Shop {
+                    cash: 100,
+                    ..default()
+                },
+            ))
+            .id();
+        let job = Job {
+            employer: shop,
+            worked_secs: 0.0,
+            accrued: 0.0,
+            withheld: [0.0; 3],
+        };
+        let person = Person {
+            action: PersonActions::Working,
+            ..default()
+        };
+        let worker = world
+            .spawn((person, Alive(true), Employment(Some(job))))
+            .id();
+        world.entity_mut(city).add_child(worker);
+        let state_of = |world: &World| {
+            let gold = world.get::<Person>(worker).unwrap().gold;
+            (gold, world.get::<Shop>(shop).unwrap().cash)
+        };
+
+        // 2 por tick, 0,5 retido: paga 1 e guarda 0,5; no tick seguinte
+        // paga 2 e repassa 1 de imposto ao tesouro da cidade
+        world.run_system_once(work_system).unwrap();
+        assert_eq!(state_of(&world), (101, 99));
+        world.run_system_once(work_system).unwrap();
+        assert_eq!(state_of(&world), (103, 96));
+        let paid: Vec<(Entity, usize)> = world
+            .resource_mut::<Events<TaxEvent>>()
+            .drain()
+            .map(|event| (event.treasury, event.amount))
+            .collect();
+        assert_eq!(paid, vec![(city, 1)]);
+
+        // Sem caixa para o próximo pagamento, o empregado é dispensado
+        world.get_mut::<Shop>(shop).unwrap().cash = 0;
+        world.run_system_once(work_system).unwrap();
+        assert_eq!(state_of(&world), (103, 0));
+        assert!(world.get::<Employment>(worker).unwrap().0.is_none());
+        let person = world.get::<Person>(worker).unwrap();
+        assert_eq!(person.action, PersonActions::Idle);
+    }
+}
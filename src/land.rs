@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::banks::Bank;
+use crate::calendar::{Calendar, Season};
+use crate::components::{City, Family, Household, Person, PersonActions, Plant, Plot, TerrainType};
+use crate::config::ScenarioConfig;
+use crate::events::TransferEvent;
+use crate::items::{ItemCatalog, ItemId, MarketPrices, PriceTable};
+use crate::ledger::{Account, TransferKind};
+use crate::systems::food_units;
+
+/// Parâmetros das terras de cultivo.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LandConfig {
+    /// Lotes de cultivo em cada cidade.
+    pub plots_per_city: usize,
+    /// Fertilidade média dos lotes em cada terreno.
+    pub fertility: TerrainFactors,
+    /// Quanto a fertilidade de cada lote varia em torno da média do
+    /// terreno, como fração dela.
+    pub fertility_spread: f32,
+    /// Ouro pago ao dono por uma safra plantada em lote alheio.
+    pub rent: usize,
+    /// Parte da colheita entregue ao dono por quem não tem ouro para o
+    /// aluguel e planta como meeiro.
+    pub crop_share: f32,
+}
+
+impl Default for LandConfig {
+    fn default() -> Self {
+        Self {
+            plots_per_city: 100,
+            fertility: TerrainFactors {
+                grassland: 1.0,
+                forest: 0.8,
+                mountain: 0.5,
+                desert: 0.3,
+            },
+            fertility_spread: 0.25,
+            rent: 2,
+            crop_share: 0.5,
+        }
+    }
+}
+
+/// Um fator para cada tipo de terreno.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerrainFactors {
+    pub grassland: f32,
+    pub forest: f32,
+    pub mountain: f32,
+    pub desert: f32,
+}
+
+impl Default for TerrainFactors {
+    fn default() -> Self {
+        Self {
+            grassland: 1.0,
+            forest: 1.0,
+            mountain: 1.0,
+            desert: 1.0,
+        }
+    }
+}
+
+impl TerrainFactors {
+    pub fn get(&self, terrain: TerrainType) -> f32 {
+        match terrain {
+            TerrainType::Grassland => self.grassland,
+            TerrainType::Forest => self.forest,
+            TerrainType::Mountain => self.mountain,
+            TerrainType::Desert => self.desert,
+        }
+    }
+
+    pub fn values(&self) -> [(&'static str, f32); 4] {
+        [
+            ("grassland", self.grassland),
+            ("forest", self.forest),
+            ("mountain", self.mountain),
+            ("desert", self.desert),
+        ]
+    }
+}
+
+/// Unidades colhidas de uma planta: a colheita da cultura ajustada pela
+/// fertilidade do lote, pelo rendimento da cultura no terreno e pelo vigor
+/// com que a planta chegou à colheita.
+pub fn harvest_units(plot: &Plot, catalog: &ItemCatalog, crop: ItemId, vigor: f32) -> i32 {
+    let item = catalog.get(crop);
+    let factor = plot.fertility * item.yields.get(plot.terrain) * vigor;
+    (item.harvest as f32 * factor).round() as i32
+}
+
+/// Unidades da colheita que ficam com o dono do lote: a parte do meeiro,
+/// arredondada para baixo.
+pub fn owner_share(units: i32, plot: &Plot, crop_share: f32) -> i32 {
+    if plot.sharecropped {
+        (units as f32 * crop_share).floor() as i32
+    } else {
+        0
+    }
+}
+
+/// Cultura da estação que rende mais por segundo de crescimento em um
+/// terreno. Quem planta para comer (`subsistence`) conta a nutrição colhida;
+/// quem planta para vender, o valor da colheita pelos preços de mercado de
+/// hoje no país.
+pub fn best_crop_for(
+    catalog: &ItemCatalog,
+    prices: &PriceTable,
+    terrain: TerrainType,
+    season: Season,
+    subsistence: bool,
+) -> Option<ItemId> {
+    let value = |item: ItemId| {
+        let worth = if subsistence {
+            catalog.nutritional_value(item).unwrap_or(0) as f32
+        } else {
+            prices.price(catalog, item)
+        };
+        let def = catalog.get(item);
+        worth * def.harvest as f32 * def.yields.get(terrain) / def.grow_secs
+    };
+    catalog
+        .iter()
+        .filter(|item| item.grows_in(season))
+        .max_by(|a, b| value(a.id).total_cmp(&value(b.id)).then(b.id.cmp(&a.id)))
+        .map(|item| item.id)
+}
+
+/// Dá um lote da cidade a quem acabou de decidir semear. A Person usa um lote
+/// livre seu; se não tem, toma posse de um sem dono; se todos têm dono, aluga
+/// por uma safra o mais fértil que estiver livre, pagando `rent` ao dono, ou,
+/// sem ouro para isso, planta como meeiro e entrega parte da colheita. Um
+/// banco ou uma cidade dona de lote só aluga por ouro, que a cidade guarda no
+/// tesouro. Quem não consegue lote, ou não tem o que semear na estação, volta
+/// a `Idle`. A cultura é a que mais rende no terreno do lote, em nutrição
+/// para quem está sem comida, nem na despensa da casa, e em ouro para os
+/// demais.
+///
+/// Antes disso, libera os lotes sem planta de quem não está semeando e
+/// devolve ao domínio comum os lotes de donos que já não existem, Persons,
+/// bancos ou cidades.
+#[allow(clippy::too_many_arguments)]
+pub fn allot_plots_system(
+    mut persons: Query<(Entity, &mut Person, &Parent)>,
+    mut plots: Query<(Entity, &mut Plot, &Parent, Option<&Children>)>,
+    plants: Query<(), With<Plant>>,
+    families: Query<&Family>,
+    households: Query<&Household>,
+    mut banks: Query<&mut Bank>,
+    mut cities: Query<&mut City>,
+    catalog: Res<ItemCatalog>,
+    prices: Res<MarketPrices>,
+    calendar: Res<Calendar>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    let rent = config.land.rent;
+
+    let seeding: HashSet<Entity> = persons
+        .iter()
+        .filter(|(_, person, _)| person.action == PersonActions::Seeding)
+        .map(|(entity, _, _)| entity)
+        .collect();
+
+    let mut farming = HashSet::new();
+    let mut free: BTreeMap<Entity, Vec<Entity>> = BTreeMap::new();
+    for (entity, mut plot, city, children) in plots.iter_mut() {
+        if plot.owner.is_some_and(|owner| {
+            !persons.contains(owner) && !banks.contains(owner) && !cities.contains(owner)
+        }) {
+            plot.owner = None;
+        }
+        // Uma planta órfã fica no lote até morrer de sede
+        if plot.farmer.is_some_and(|farmer| !persons.contains(farmer)) {
+            plot.farmer = None;
+        }
+        let planted = children.is_some_and(|children| children.iter().any(|&c| plants.contains(c)));
+        match plot.farmer {
+            Some(farmer) if planted || seeding.contains(&farmer) => {
+                farming.insert(farmer);
+            }
+            _ if planted => {}
+            _ => {
+                plot.farmer = None;
+                plot.crop = None;
+                plot.sharecropped = false;
+                free.entry(city.get()).or_default().push(entity);
+            }
+        }
+    }
+
+    // Bancos e cidades não recebem parte da colheita, só aluguel
+    let landlord = |owner: Entity| banks.contains(owner) || cities.contains(owner);
+    let mut rents = Vec::new();
+    for (entity, mut person, city) in persons.iter_mut() {
+        if person.action != PersonActions::Seeding || farming.contains(&entity) {
+            continue;
+        }
+        let candidates = free.entry(city.get()).or_default();
+
+        // Lote próprio, depois sem dono, depois alheio; o mais fértil primeiro
+        let choice = candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &plot_entity)| {
+                let (_, plot, _, _) = plots.get(plot_entity).ok()?;
+                let rank = match plot.owner {
+                    Some(owner) if owner == entity => 0,
+                    None => 1,
+                    Some(owner) if landlord(owner) && person.gold < rent => return None,
+                    Some(_) => 2,
+                };
+                Some((rank, plot.fertility, plot_entity, i))
+            })
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)).then(a.2.cmp(&b.2)));
+
+        let pantry = families
+            .get(entity)
+            .ok()
+            .and_then(|family| households.get(family.household?).ok())
+            .map_or(0, |household| household.pantry_food(&catalog));
+        let subsistence = food_units(&person, &catalog) + pantry == 0;
+        let crop = choice.and_then(|(_, _, plot_entity, i)| {
+            let (_, plot, _, _) = plots.get(plot_entity).ok()?;
+            let crop = best_crop_for(
+                &catalog,
+                prices.city(city.get()),
+                plot.terrain,
+                calendar.season(),
+                subsistence,
+            )?;
+            Some((plot_entity, i, crop))
+        });
+        let Some((plot_entity, i, crop)) = crop else {
+            person.action = PersonActions::Idle;
+            person.target_item = None;
+            continue;
+        };
+        candidates.remove(i);
+        let Ok((_, mut plot, _, _)) = plots.get_mut(plot_entity) else {
+            continue;
+        };
+
+        plot.sharecropped = false;
+        match plot.owner {
+            None => plot.owner = Some(entity),
+            Some(owner) if owner != entity => {
+                if person.gold >= rent {
+                    person.gold -= rent;
+                    rents.push((entity, owner));
+                } else {
+                    plot.sharecropped = true;
+                }
+            }
+            Some(_) => {}
+        }
+        plot.farmer = Some(entity);
+        plot.crop = Some(crop);
+        person.target_item = Some(crop);
+    }
+
+    for (tenant, owner) in rents {
+        if let Ok((_, mut owner_person, _)) = persons.get_mut(owner) {
+            owner_person.gold += rent;
+        } else if let Ok(mut bank) = banks.get_mut(owner) {
+            bank.cash += rent;
+        } else if let Ok(mut city) = cities.get_mut(owner) {
+            city.treasury += rent;
+        }
+        if rent > 0 {
+            transfers.send(TransferEvent {
+                from: Account::Agent(tenant),
+                to: Account::Agent(owner),
+                amount: rent,
+                kind: TransferKind::Rent,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn harvest_follows_fertility_terrain_and_share() {
+        let catalog = ItemCatalog::default();
+        let prices = PriceTable::default();
+        let apple = catalog.find("Apple").unwrap();
+        let corn = catalog.find("Corn").unwrap();
+
+        let mut plot = Plot {
+            terrain: TerrainType::Desert,
+            fertility: 0.5,
+            owner: None,
+            farmer: None,
+            crop: None,
+            sharecropped: false,
+        };
+        // 10 × 0.5 × 0.2 e 20 × 0.5 × 1.0, e metade disso sem vigor
+        assert_eq!(harvest_units(&plot, &catalog, apple, 1.0), 1);
+        assert_eq!(harvest_units(&plot, &catalog, corn, 1.0), 10);
+        assert_eq!(harvest_units(&plot, &catalog, corn, 0.5), 5);
+
+        assert_eq!(owner_share(5, &plot, 0.5), 0);
+        plot.sharecropped = true;
+        assert_eq!(owner_share(5, &plot, 0.5), 2);
+
+        // No deserto a maçã quase não cresce, nem para comer nem para vender
+        assert_ne!(
+            best_crop_for(&catalog, &prices, TerrainType::Desert, Season::Spring, true),
+            Some(apple)
+        );
+        assert_eq!(
+            best_crop_for(
+                &catalog,
+                &prices,
+                TerrainType::Forest,
+                Season::Spring,
+                false
+            ),
+            Some(apple)
+        );
+        // Fora da estação, a maçã não é semeada
+        assert_ne!(
+            best_crop_for(
+                &catalog,
+                &prices,
+                TerrainType::Forest,
+                Season::Winter,
+                false
+            ),
+            Some(apple)
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::RangeInclusive;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SimClock;
+use crate::config::ScenarioConfig;
+use crate::events::TransferEvent;
+
+/// Configuração do livro-razão.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LedgerConfig {
+    /// Por quantos ticks os lançamentos ficam no diário. Os saldos valem
+    /// desde o início, mesmo depois que os lançamentos antigos saem.
+    pub retain_ticks: u64,
+}
+
+impl Default for LedgerConfig {
+    fn default() -> Self {
+        Self { retain_ticks: 2400 }
+    }
+}
+
+/// Conta do livro-razão. Cada Person, casa, loja, atacadista, banco, cidade
+/// ou governo tem a sua, identificada pela entidade dona.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Account {
+    Agent(Entity),
+    /// Moeda de um país no caixa de um banco de outro: a entidade do banco e
+    /// a do país da moeda.
+    Foreign(Entity, Entity),
+    /// Contrapartida do ouro que entra na economia ou sai dela.
+    Outside,
+}
+
+/// Motivo de uma transferência.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TransferKind {
+    /// Saldo com que o agente começa, no setup ou ao carregar um snapshot.
+    Opening,
+    /// Compra e venda no mercado da cidade.
+    Trade,
+    /// Encomenda de uma loja ao atacadista.
+    Restock,
+    /// Caixa de uma loja falida entregue ao atacadista.
+    Liquidation,
+    /// Ouro que some com uma Person morta sem herdeiros nem cidade, e o
+    /// vale-alimentação que ela não gastou.
+    Death,
+    /// Ouro de uma Person morta entregue aos herdeiros ou ao tesouro da
+    /// cidade.
+    Inheritance,
+    /// Salário pago por tick trabalhado.
+    Wage,
+    /// Aluguel de um lote, pago ao dono por safra.
+    Rent,
+    /// Ouro dado de fora da economia por um choque do cenário.
+    Windfall,
+    /// Ouro juntado no caixa de uma casa ou dado dele a um membro.
+    Household,
+    /// Ouro guardado no banco ou sacado dele.
+    Deposit,
+    Withdrawal,
+    /// Empréstimo do banco e as parcelas pagas de volta.
+    Loan,
+    Repayment,
+    /// Moeda emitida pelo banco central no caixa de um banco, ou recolhida
+    /// dele.
+    Issuance,
+    Retirement,
+    /// Moeda entregue ao banco para conversão, e a outra moeda paga pelo
+    /// banco do outro país.
+    Exchange,
+    /// Moedas devolvidas entre bancos de países diferentes ao acertar o que
+    /// cada um guardou da moeda do outro.
+    Settlement,
+    /// Imposto pago ao tesouro de uma cidade, estado ou país.
+    Tax,
+    /// Benefício pago por um tesouro: renda básica, seguro-desemprego ou
+    /// vale-alimentação.
+    Benefit,
+}
+
+/// Um lançamento: `amount` sai da conta `credit` e entra na `debit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub tick: u64,
+    pub debit: Account,
+    pub credit: Account,
+    pub amount: usize,
+    pub kind: TransferKind,
+}
+
+/// Entradas e saídas de uma conta em um intervalo, por motivo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IncomeStatement {
+    pub revenue: BTreeMap<TransferKind, usize>,
+    pub expenses: BTreeMap<TransferKind, usize>,
+}
+
+impl IncomeStatement {
+    pub fn total_revenue(&self) -> usize {
+        self.revenue.values().sum()
+    }
+
+    pub fn total_expenses(&self) -> usize {
+        self.expenses.values().sum()
+    }
+
+    pub fn net(&self) -> i64 {
+        self.total_revenue() as i64 - self.total_expenses() as i64
+    }
+}
+
+/// Livro-razão de partidas dobradas com todas as transferências de ouro.
+///
+/// Os componentes (`Person.gold`, `Shop.cash`, ...) continuam guardando o
+/// ouro; cada sistema que o move envia um `TransferEvent`, lançado aqui por
+/// `post_transfers_system`. O saldo de cada conta deve então coincidir com o
+/// ouro do dono, o que a auditoria confere.
+#[derive(Resource, Default)]
+pub struct Ledger {
+    journal: VecDeque<JournalEntry>,
+    balances: HashMap<Account, i64>,
+}
+
+impl Ledger {
+    pub fn post(&mut self, entry: JournalEntry) {
+        *self.balances.entry(entry.debit).or_insert(0) += entry.amount as i64;
+        *self.balances.entry(entry.credit).or_insert(0) -= entry.amount as i64;
+        self.journal.push_back(entry);
+    }
+
+    /// Saldo acumulado de uma conta. O de `Outside` é o oposto de todo o
+    /// ouro em circulação.
+    pub fn balance(&self, account: Account) -> i64 {
+        self.balances.get(&account).copied().unwrap_or(0)
+    }
+
+    /// Lançamentos ainda no diário que envolvem a conta, do mais antigo ao
+    /// mais recente.
+    pub fn entries_for(&self, account: Account) -> impl Iterator<Item = &JournalEntry> {
+        self.journal
+            .iter()
+            .filter(move |entry| entry.debit == account || entry.credit == account)
+    }
+
+    /// Demonstrativo da conta com os lançamentos de `ticks` ainda no diário.
+    pub fn income_statement(
+        &self,
+        account: Account,
+        ticks: RangeInclusive<u64>,
+    ) -> IncomeStatement {
+        let mut statement = IncomeStatement::default();
+        for entry in self.entries_for(account) {
+            if !ticks.contains(&entry.tick) {
+                continue;
+            }
+            // Uma transferência da conta para ela mesma não muda nada
+            if entry.debit == entry.credit {
+                continue;
+            }
+            let side = if entry.debit == account {
+                &mut statement.revenue
+            } else {
+                &mut statement.expenses
+            };
+            *side.entry(entry.kind).or_insert(0) += entry.amount;
+        }
+        statement
+    }
+
+    /// Tira do diário os lançamentos anteriores a `tick`.
+    fn prune_before(&mut self, tick: u64) {
+        while self.journal.front().is_some_and(|entry| entry.tick < tick) {
+            self.journal.pop_front();
+        }
+    }
+}
+
+/// Lança no diário as transferências do tick.
+pub fn post_transfers_system(
+    mut ledger: ResMut<Ledger>,
+    mut transfers: EventReader<TransferEvent>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+) {
+    for transfer in transfers.read() {
+        ledger.post(JournalEntry {
+            tick: clock.tick,
+            debit: transfer.to,
+            credit: transfer.from,
+            amount: transfer.amount,
+            kind: transfer.kind,
+        });
+    }
+    ledger.prune_before(clock.tick.saturating_sub(config.ledger.retain_ticks));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        tick: u64,
+        from: Account,
+        to: Account,
+        amount: usize,
+        kind: TransferKind,
+    ) -> JournalEntry {
+        JournalEntry {
+            tick,
+            debit: to,
+            credit: from,
+            amount,
+            kind,
+        }
+    }
+
+    #[test]
+    fn balances_and_statements_follow_both_sides_of_each_entry() {
+        let person = Account::Agent(Entity::from_raw(1));
+        let shop = Account::Agent(Entity::from_raw(2));
+
+        let mut ledger = Ledger::default();
+        ledger.post(entry(
+            0,
+            Account::Outside,
+            person,
+            20,
+            TransferKind::Opening,
+        ));
+        ledger.post(entry(3, person, shop, 8, TransferKind::Trade));
+        ledger.post(entry(5, shop, person, 3, TransferKind::Trade));
+
+        assert_eq!(ledger.balance(person), 15);
+        assert_eq!(ledger.balance(shop), 5);
+        assert_eq!(ledger.balance(Account::Outside), -20);
+
+        let statement = ledger.income_statement(person, 1..=10);
+        assert_eq!(statement.revenue.get(&TransferKind::Trade), Some(&3));
+        assert_eq!(statement.expenses.get(&TransferKind::Trade), Some(&8));
+        assert_eq!(statement.net(), -5);
+
+        ledger.prune_before(4);
+        assert_eq!(ledger.entries_for(person).count(), 1);
+        assert_eq!(ledger.balance(person), 15);
+    }
+
+    #[test]
+    fn posted_transfers_give_each_agent_an_income_statement() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(SimClock::new(1.0, 10.0));
+        world.init_resource::<Ledger>();
+        world.init_resource::<Events<TransferEvent>>();
+        let treasury = world.spawn_empty().id();
+        let person = world.spawn_empty().id();
+        let transfer = |world: &mut World, from: Entity, to: Entity, amount, kind| {
+            world.send_event(TransferEvent {
+                from: Account::Agent(from),
+                to: Account::Agent(to),
+                amount,
+                kind,
+            });
+            world.run_system_once(post_transfers_system).unwrap();
+            world.resource_mut::<Events<TransferEvent>>().clear();
+            world.resource_mut::<SimClock>().tick += 1;
+        };
+        transfer(&mut world, person, treasury, 12, TransferKind::Tax);
+        transfer(&mut world, person, treasury, 3, TransferKind::Tax);
+        transfer(&mut world, treasury, person, 10, TransferKind::Benefit);
+
+        let ledger = world.resource::<Ledger>();
+        let statement = ledger.income_statement(Account::Agent(treasury), 0..=2);
+        assert_eq!(statement.revenue.get(&TransferKind::Tax), Some(&15));
+        assert_eq!(statement.expenses.get(&TransferKind::Benefit), Some(&10));
+        assert_eq!(
+            (statement.total_revenue(), statement.total_expenses()),
+            (15, 10)
+        );
+        assert_eq!(statement.net(), 5);
+        // O benefício do tick 2 fica fora do intervalo
+        let person = ledger.income_statement(Account::Agent(person), 0..=1);
+        assert_eq!(person.net(), -15);
+        assert!(person.revenue.is_empty());
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SimClock;
+use crate::components::{Employer, Person, PersonActions, Shop, Wholesaler};
+use crate::config::ScenarioConfig;
+use crate::events::{StockShortfallEvent, TaxEvent, TradeEvent, TransferEvent};
+use crate::items::{ItemCatalog, ItemId, MarketPrices};
+use crate::ledger::{Account, TransferKind};
+use crate::shops;
+use crate::taxes::{self, Tax, TaxTable};
+
+/// Quanto acima da oferta de venda mais barata da cidade uma Person aceita
+/// pagar.
+const BUYER_MARKUP: f32 = 1.2;
+
+/// Fração do preço médio de mercado pedida por uma Person que vende. Quem
+/// vende precisa de ouro, então aceita bem menos que o preço das lojas.
+const SELLER_DISCOUNT: f32 = 0.7;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    pub owner: Entity,
+    pub quantity: usize,
+    pub limit_price: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub buyer: Entity,
+    pub seller: Entity,
+    pub quantity: usize,
+}
+
+/// Resultado do leilão de um item: todas as execuções saem pelo mesmo preço.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clearing {
+    pub price: usize,
+    pub volume: usize,
+    pub fills: Vec<Fill>,
+}
+
+/// Ofertas de compra e venda de um item em uma cidade durante um tick.
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    pub bids: Vec<Order>,
+    pub asks: Vec<Order>,
+}
+
+impl OrderBook {
+    pub fn bid(&mut self, owner: Entity, quantity: usize, limit_price: usize) {
+        if quantity > 0 && limit_price > 0 {
+            self.bids.push(Order {
+                owner,
+                quantity,
+                limit_price,
+            });
+        }
+    }
+
+    pub fn ask(&mut self, owner: Entity, quantity: usize, limit_price: usize) {
+        if quantity > 0 {
+            self.asks.push(Order {
+                owner,
+                quantity,
+                limit_price: limit_price.max(1),
+            });
+        }
+    }
+
+    /// Menor preço pedido no livro.
+    pub fn best_ask(&self) -> Option<usize> {
+        self.asks.iter().map(|order| order.limit_price).min()
+    }
+
+    /// Leilão duplo: casa as maiores ofertas de compra com as menores de
+    /// venda enquanto elas se cruzam. O preço é o ponto médio do último par
+    /// casado, que respeita o limite de todas as ordens executadas. Em caso
+    /// de empate vale a ordem de chegada.
+    pub fn clear(mut self) -> Option<Clearing> {
+        self.bids
+            .sort_by_key(|order| std::cmp::Reverse(order.limit_price));
+        self.asks.sort_by_key(|order| order.limit_price);
+
+        let mut fills = Vec::new();
+        let mut marginal = None;
+        let (mut i, mut j) = (0, 0);
+        while i < self.bids.len()
+            && j < self.asks.len()
+            && self.bids[i].limit_price >= self.asks[j].limit_price
+        {
+            let quantity = self.bids[i].quantity.min(self.asks[j].quantity);
+            fills.push(Fill {
+                buyer: self.bids[i].owner,
+                seller: self.asks[j].owner,
+                quantity,
+            });
+            marginal = Some((self.bids[i].limit_price, self.asks[j].limit_price));
+
+            self.bids[i].quantity -= quantity;
+            self.asks[j].quantity -= quantity;
+            if self.bids[i].quantity == 0 {
+                i += 1;
+            }
+            if self.asks[j].quantity == 0 {
+                j += 1;
+            }
+        }
+
+        let (bid, ask) = marginal?;
+        Some(Clearing {
+            price: (bid + ask) / 2,
+            volume: fills.iter().map(|fill| fill.quantity).sum(),
+            fills,
+        })
+    }
+}
+
+/// Último leilão com negócios de um item em uma cidade.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quote {
+    pub tick: u64,
+    pub price: usize,
+    pub volume: usize,
+}
+
+/// Preço e volume de equilíbrio de cada item em cada cidade.
+#[derive(Resource, Default)]
+pub struct CityMarkets {
+    pub quotes: HashMap<Entity, BTreeMap<ItemId, Quote>>,
+}
+
+impl CityMarkets {
+    pub fn quote(&self, city: Entity, item: ItemId) -> Option<Quote> {
+        self.quotes.get(&city)?.get(&item).copied()
+    }
+}
+
+/// Monta o livro de ofertas de cada cidade, roda o leilão de cada item e
+/// liquida as execuções.
+///
+/// As lojas atuam como formadoras de mercado: oferecem todo o estoque pelo
+/// preço de venda e compram pelo preço de compra (o de venda menos o spread,
+/// e abaixo da venda mais barata da cidade) o que cabe no caixa e no estoque
+/// máximo, sem tocar na reserva para salários. O atacadista compra colheitas
+/// por uma fração do preço base, também abaixo das lojas. Quem
+/// está em `Buying` faz uma oferta por uma unidade da comida escolhida (ou,
+/// se ninguém na cidade a vende, da que rende mais nutrição por ouro), contando
+/// o vale-alimentação, que só paga compras de lojas; quem está em `Selling`
+/// oferece todo o inventário. Do valor de cada venda, o vendedor paga o
+/// imposto sobre vendas da cidade.
+#[allow(clippy::too_many_arguments)]
+pub fn market_system(
+    mut persons: Query<(Entity, &mut Person, &Parent)>,
+    mut shops: Query<(Entity, &mut Shop, &Parent)>,
+    mut wholesalers: Query<(Entity, &mut Wholesaler, &Parent)>,
+    employers: Query<&Employer>,
+    catalog: Res<ItemCatalog>,
+    prices: Res<MarketPrices>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    mut markets: ResMut<CityMarkets>,
+    table: Res<TaxTable>,
+    mut trades: EventWriter<TradeEvent>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut tax_events: EventWriter<TaxEvent>,
+    mut shortfalls: EventWriter<StockShortfallEvent>,
+) {
+    let labor = &config.labor;
+    let config = &config.shops;
+    let mut books: BTreeMap<(Entity, ItemId), OrderBook> = BTreeMap::new();
+
+    for (entity, shop, city) in shops.iter() {
+        for (item, details) in shop.items.iter() {
+            books.entry((city.get(), *item)).or_default().ask(
+                entity,
+                details.stock,
+                details.ask_price(),
+            );
+        }
+    }
+    // As lojas compram abaixo da venda mais barata da cidade, para que não
+    // negociem entre si
+    for (entity, shop, city) in shops.iter() {
+        let budget = shop
+            .cash
+            .saturating_sub(labor.payroll_reserve(employers.get(entity).ok(), labor.shop_jobs));
+        for (item, details) in shop.items.iter() {
+            let book = books.entry((city.get(), *item)).or_default();
+            let cheapest = book.best_ask().unwrap_or(usize::MAX);
+            let limit = details
+                .bid_price(config.spread)
+                .min(cheapest.saturating_sub(1));
+            if limit == 0 {
+                continue;
+            }
+            let room = config.max_stock.saturating_sub(details.stock);
+            book.bid(entity, room.min(budget / limit), limit);
+        }
+    }
+
+    for (entity, wholesaler, city) in wholesalers.iter() {
+        let budget = wholesaler.cash.saturating_sub(
+            labor.payroll_reserve(employers.get(entity).ok(), labor.wholesaler_jobs),
+        );
+        for crop in catalog.iter().filter(|item| item.growable) {
+            let book = books.entry((city.get(), crop.id)).or_default();
+            let cheapest = book.best_ask().unwrap_or(usize::MAX);
+            let limit = config
+                .crop_price(&catalog, crop.id)
+                .min(cheapest.saturating_sub(1));
+            if let Some(quantity) = budget.checked_div(limit) {
+                book.bid(entity, quantity, limit);
+            }
+        }
+    }
+
+    for (entity, person, city) in persons.iter() {
+        if person.action != PersonActions::Selling {
+            continue;
+        }
+        for (item, count) in person.inventory.iter() {
+            if *count > 0 {
+                let limit = prices.city(city.get()).price(&catalog, *item) * SELLER_DISCOUNT;
+                books.entry((city.get(), *item)).or_default().ask(
+                    entity,
+                    *count as usize,
+                    limit as usize,
+                );
+            }
+        }
+    }
+
+    for (entity, person, city) in persons.iter() {
+        if person.action != PersonActions::Buying {
+            continue;
+        }
+        let best_ask = |item: ItemId| books.get(&(city.get(), item))?.best_ask();
+        let funds = person.gold + person.vouchers;
+        let item = person
+            .target_item
+            .filter(|item| best_ask(*item).is_some())
+            .or_else(|| {
+                catalog
+                    .foods()
+                    .filter_map(|food| {
+                        let price = best_ask(food.id).filter(|price| *price <= funds)?;
+                        let value = catalog.nutritional_value(food.id)? as f32 / price as f32;
+                        Some((food.id, value))
+                    })
+                    .max_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+                    .map(|(item, _)| item)
+            });
+        let Some(item) = item else {
+            continue;
+        };
+        let reference = best_ask(item).unwrap_or(0) as f32;
+        let limit = ((reference * BUYER_MARKUP).ceil() as usize).min(funds);
+        books
+            .entry((city.get(), item))
+            .or_default()
+            .bid(entity, 1, limit);
+    }
+
+    for ((city, item), book) in books {
+        let Some(clearing) = book.clear() else {
+            continue;
+        };
+        let mut volume = 0;
+        for fill in &clearing.fills {
+            let jobs = if shops.contains(fill.buyer) {
+                labor.shop_jobs
+            } else {
+                labor.wholesaler_jobs
+            };
+            let reserve = labor.payroll_reserve(employers.get(fill.buyer).ok(), jobs);
+            let (quantity, sales_tax) = settle(
+                &mut persons,
+                &mut shops,
+                &mut wholesalers,
+                &table,
+                city,
+                item,
+                clearing.price,
+                fill,
+                reserve,
+                &mut shortfalls,
+            );
+            if quantity == 0 {
+                continue;
+            }
+            volume += quantity;
+            transfers.send(TransferEvent {
+                from: Account::Agent(fill.buyer),
+                to: Account::Agent(fill.seller),
+                amount: quantity * clearing.price,
+                kind: TransferKind::Trade,
+            });
+            for (treasury, amount) in sales_tax {
+                taxes::pay(
+                    &mut transfers,
+                    &mut tax_events,
+                    fill.seller,
+                    city,
+                    treasury,
+                    Tax::Sales,
+                    amount,
+                );
+            }
+            trades.send(TradeEvent {
+                city,
+                seller: fill.seller,
+                item,
+                quantity,
+                unit_price: clearing.price,
+            });
+        }
+        if volume == 0 {
+            continue;
+        }
+        markets.quotes.entry(city).or_default().insert(
+            item,
+            Quote {
+                tick: clock.tick,
+                price: clearing.price,
+                volume,
+            },
+        );
+    }
+
+    // As ordens valem por um tick: quem não foi atendido volta a ficar ocioso
+    // e decide de novo no próximo, podendo comer o que não conseguiu vender
+    for (_, mut person, _) in persons.iter_mut() {
+        if matches!(
+            person.action,
+            PersonActions::Buying | PersonActions::Selling
+        ) {
+            person.action = PersonActions::Idle;
+            person.target_item = None;
+        }
+    }
+}
+
+/// Transfere mercadoria do vendedor para o comprador e o ouro no sentido
+/// contrário. Ordens de compra de lojas e atacadistas de itens diferentes
+/// disputam o mesmo caixa, então só é executado o que o comprador ainda pode
+/// pagar sem tocar em `reserve`, a reserva para salários; uma Person que
+/// compra de uma loja paga primeiro com o vale-alimentação. O vendedor recebe
+/// o valor menos o imposto sobre vendas.
+/// Devolve a quantidade executada e o imposto devido a cada tesouro.
+#[allow(clippy::too_many_arguments)]
+fn settle(
+    persons: &mut Query<(Entity, &mut Person, &Parent)>,
+    shops: &mut Query<(Entity, &mut Shop, &Parent)>,
+    wholesalers: &mut Query<(Entity, &mut Wholesaler, &Parent)>,
+    table: &TaxTable,
+    city: Entity,
+    item: ItemId,
+    price: usize,
+    fill: &Fill,
+    reserve: usize,
+    shortfalls: &mut EventWriter<StockShortfallEvent>,
+) -> (usize, Vec<(Entity, usize)>) {
+    let from_shop = shops.contains(fill.seller);
+    let funds = if let Ok((_, buyer, _)) = persons.get(fill.buyer) {
+        buyer.gold + if from_shop { buyer.vouchers } else { 0 }
+    } else if let Ok((_, shop, _)) = shops.get(fill.buyer) {
+        shop.cash.saturating_sub(reserve)
+    } else if let Ok((_, wholesaler, _)) = wholesalers.get(fill.buyer) {
+        wholesaler.cash.saturating_sub(reserve)
+    } else {
+        0
+    };
+    let quantity = fill.quantity.min(funds / price.max(1));
+    if quantity == 0 {
+        return (0, Vec::new());
+    }
+    let value = price * quantity;
+    let sales_tax = table.withhold(city, Tax::Sales, value);
+    let proceeds = value - sales_tax.iter().map(|(_, amount)| amount).sum::<usize>();
+
+    if let Ok((_, mut seller, _)) = persons.get_mut(fill.seller) {
+        *seller.inventory.entry(item).or_insert(0) -= quantity as i32;
+        seller.gold += proceeds;
+    } else if let Ok((_, mut shop, _)) = shops.get_mut(fill.seller) {
+        shop.cash += proceeds;
+        if let Some(details) = shop.items.get_mut(&item) {
+            shops::take_stock(details, fill.seller, item, quantity, shortfalls);
+            details.transactions.0 += quantity;
+        }
+    }
+
+    if let Ok((_, mut buyer, _)) = persons.get_mut(fill.buyer) {
+        *buyer.inventory.entry(item).or_insert(0) += quantity as i32;
+        let vouchers = if from_shop {
+            value.min(buyer.vouchers)
+        } else {
+            0
+        };
+        buyer.vouchers -= vouchers;
+        buyer.gold -= value - vouchers;
+    } else if let Ok((_, mut shop, _)) = shops.get_mut(fill.buyer) {
+        shop.cash -= value;
+        if let Some(details) = shop.items.get_mut(&item) {
+            details.stock += quantity;
+            details.transactions.1 += quantity;
+        }
+    } else if let Ok((_, mut wholesaler, _)) = wholesalers.get_mut(fill.buyer) {
+        // As colheitas viram insumo do atacadista e saem do mercado
+        wholesaler.cash -= value;
+    }
+
+    (quantity, sales_tax)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(owner: u32, quantity: usize, limit_price: usize) -> Order {
+        Order {
+            owner: Entity::from_raw(owner),
+            quantity,
+            limit_price,
+        }
+    }
+
+    #[test]
+    fn clears_at_the_midpoint_of_the_marginal_pair() {
+        let book = OrderBook {
+            bids: vec![order(1, 1, 12), order(2, 2, 10), order(3, 1, 6)],
+            asks: vec![order(4, 2, 8), order(5, 5, 9)],
+        };
+
+        let clearing = book.clear().unwrap();
+        assert_eq!(clearing.volume, 3);
+        assert_eq!(clearing.price, 9);
+        assert_eq!(
+            clearing.fills,
+            vec![
+                Fill {
+                    buyer: Entity::from_raw(1),
+                    seller: Entity::from_raw(4),
+                    quantity: 1
+                },
+                Fill {
+                    buyer: Entity::from_raw(2),
+                    seller: Entity::from_raw(4),
+                    quantity: 1
+                },
+                Fill {
+                    buyer: Entity::from_raw(2),
+                    seller: Entity::from_raw(5),
+                    quantity: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_trade_when_orders_do_not_cross() {
+        let book = OrderBook {
+            bids: vec![order(1, 1, 5)],
+            asks: vec![order(2, 1, 6)],
+        };
+        assert_eq!(book.clear(), None);
+    }
+
+    #[test]
+    fn shop_purchases_leave_the_payroll_reserve_untouched() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::components::City;
+
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let potato = catalog.find("Potato").unwrap();
+        let config = ScenarioConfig::default();
+        let employer = Employer {
+            wage: 0.5,
+            ..default()
+        };
+        let reserve = config
+            .labor
+            .payroll_reserve(Some(&employer), config.labor.shop_jobs);
+        assert!(reserve > 0);
+
+        let mut world = World::new();
+        world.insert_resource(config);
+        world.insert_resource(SimClock::new(0.25, 600.0));
+        world.init_resource::<MarketPrices>();
+        world.init_resource::<CityMarkets>();
+        world.init_resource::<TaxTable>();
+        world.init_resource::<Events<TradeEvent>>();
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<TaxEvent>>();
+        world.init_resource::<Events<StockShortfallEvent>>();
+
+        // As ordens de maçã e de batata cabem, cada uma, no que sobra da
+        // reserva, mas não as duas juntas
+        let city = world.spawn(City::default()).id();
+        let mut shop = Shop {
+            cash: reserve + 20,
+            ..default()
+        };
+        shop.stock_item(apple, catalog.get(apple).base_price, 0);
+        shop.stock_item(potato, catalog.get(potato).base_price, 0);
+        let shop = world.spawn((shop, employer)).id();
+        world.entity_mut(city).add_child(shop);
+        for item in [apple, potato] {
+            let mut person = Person {
+                action: PersonActions::Selling,
+                ..default()
+            };
+            person.inventory.insert(item, 10);
+            let person = world.spawn(person).id();
+            world.entity_mut(city).add_child(person);
+        }
+        world.insert_resource(catalog);
+        world.run_system_once(market_system).unwrap();
+
+        let cash = world.get::<Shop>(shop).unwrap().cash;
+        assert!(cash < reserve + 20, "the shop bought nothing");
+        assert!(
+            cash >= reserve,
+            "cash {cash} dipped into the reserve {reserve}"
+        );
+        let trades = world.resource::<Events<TradeEvent>>().len();
+        assert_eq!(trades, 2);
+    }
+}
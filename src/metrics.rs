@@ -0,0 +1,941 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use bevy::{app::AppExit, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::banks::{Bank, Credit, LoanOutcome};
+use crate::clock::{every_day, SimClock};
+use crate::components::{Alive, City, Country, Household, Person, Shop, State};
+use crate::config::ScenarioConfig;
+use crate::demography::DeathCause;
+use crate::events::{
+    BenefitEvent, BirthEvent, DeathEvent, HarvestEvent, LoanEvent, TaxEvent, TradeEvent,
+};
+use crate::indicators::{Indicators, ScopeIndicators};
+use crate::items::{ItemCatalog, ItemId};
+use crate::monetary::CentralBank;
+use crate::taxes::Tax;
+use crate::welfare::Benefit;
+
+/// Configuração do registro de séries temporais.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// CSV gravado a cada dia simulado e ao encerrar. Sem ele, nada é
+    /// registrado.
+    pub output: Option<PathBuf>,
+    /// Intervalo entre amostras, em ticks.
+    pub every_ticks: u64,
+    /// Séries registradas; vazio registra todas.
+    pub series: Vec<Series>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            output: None,
+            every_ticks: 40,
+            series: Vec::new(),
+        }
+    }
+}
+
+impl MetricsConfig {
+    pub fn records(&self, series: Series) -> bool {
+        self.series.is_empty() || self.series.contains(&series)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Series {
+    PopulationAlive,
+    /// Nascimentos desde a amostra anterior.
+    Births,
+    /// Mortes desde a amostra anterior, por qualquer causa.
+    Deaths,
+    /// Das mortes desde a amostra anterior, as de fome.
+    Starvations,
+    /// Idade média das Persons vivas, em anos simulados.
+    AverageAge,
+    /// Persons vivas abaixo da idade adulta.
+    Children,
+    AverageHunger,
+    AverageHealth,
+    /// Ouro por Person viva, contando o caixa das casas.
+    AverageGold,
+    /// Casas com algum membro.
+    Households,
+    /// Depósitos das casas e das lojas nos bancos.
+    Deposits,
+    /// Saldo devedor dos empréstimos em aberto.
+    Loans,
+    /// Ouro em caixa nos bancos, com a moeda de fora que guardam convertida
+    /// pela cotação.
+    BankReserves,
+    /// Caixa mais empréstimos menos depósitos dos bancos.
+    BankEquity,
+    /// Ouro emprestado desde a amostra anterior.
+    NewLoans,
+    /// Empréstimos executados desde a amostra anterior.
+    Defaults,
+    /// Taxa básica do banco central, só por país.
+    PolicyRate,
+    /// Ouro em circulação (`Country.total_gold`, recontado a cada dia), por
+    /// país e no total.
+    Currency,
+    /// Valor da moeda do país na unidade de conta do câmbio.
+    ExchangeRate,
+    /// Impostos pagos desde a amostra anterior por quem é do escopo, a
+    /// qualquer nível de governo.
+    TaxRevenue,
+    /// Da arrecadação desde a amostra anterior, a de cada imposto.
+    SalesTax,
+    IncomeTax,
+    WealthTax,
+    LandTax,
+    /// Benefícios pagos desde a amostra anterior a quem é do escopo, por
+    /// qualquer nível de governo, com o vale-alimentação.
+    Benefits,
+    /// Dos benefícios desde a amostra anterior, os de cada programa.
+    BasicIncome,
+    FoodStamps,
+    UnemploymentBenefits,
+    /// Vale-alimentação recebido e ainda não gasto pelas Persons vivas.
+    Vouchers,
+    /// Ouro nos tesouros públicos do escopo: o de cada cidade, mais o do
+    /// estado e o do país quando o escopo é um deles.
+    Treasury,
+    /// Preço médio de cada item entre as lojas que o vendem.
+    AveragePrice,
+    /// Estoque total de cada item nas lojas.
+    Stock,
+    /// Unidades negociadas no mercado desde a amostra anterior.
+    Volume,
+    /// Preço médio das trocas desde a amostra anterior.
+    TradePrice,
+    /// Unidades colhidas desde a amostra anterior.
+    Harvested,
+    /// Tempo médio entre semeadura e colheita das plantas colhidas desde a
+    /// amostra anterior, em segundos.
+    TimeToHarvest,
+    /// Os indicadores abaixo repetem o último valor de `Indicators`.
+    Cpi,
+    Inflation,
+    NominalOutput,
+    ProductionValue,
+    TradeValue,
+    MoneySupply,
+    Velocity,
+    WealthGini,
+    IncomeGini,
+    HouseholdIncomeGini,
+    AverageHouseholdIncome,
+    Unemployment,
+    AverageWage,
+}
+
+impl Series {
+    /// Série da arrecadação de um imposto.
+    pub fn of_tax(tax: Tax) -> Series {
+        match tax {
+            Tax::Sales => Series::SalesTax,
+            Tax::Income => Series::IncomeTax,
+            Tax::Wealth => Series::WealthTax,
+            Tax::Land => Series::LandTax,
+        }
+    }
+
+    /// Série dos pagamentos de um benefício.
+    pub fn of_benefit(benefit: Benefit) -> Series {
+        match benefit {
+            Benefit::BasicIncome => Series::BasicIncome,
+            Benefit::FoodStamps => Series::FoodStamps,
+            Benefit::Unemployment => Series::UnemploymentBenefits,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Series::PopulationAlive => "population_alive",
+            Series::Births => "births",
+            Series::Deaths => "deaths",
+            Series::Starvations => "starvations",
+            Series::AverageAge => "average_age",
+            Series::Children => "children",
+            Series::AverageHunger => "average_hunger",
+            Series::AverageHealth => "average_health",
+            Series::AverageGold => "average_gold",
+            Series::Households => "households",
+            Series::Deposits => "deposits",
+            Series::Loans => "loans",
+            Series::BankReserves => "bank_reserves",
+            Series::BankEquity => "bank_equity",
+            Series::NewLoans => "new_loans",
+            Series::Defaults => "defaults",
+            Series::PolicyRate => "policy_rate",
+            Series::Currency => "currency",
+            Series::ExchangeRate => "exchange_rate",
+            Series::TaxRevenue => "tax_revenue",
+            Series::SalesTax => "sales_tax",
+            Series::IncomeTax => "income_tax",
+            Series::WealthTax => "wealth_tax",
+            Series::LandTax => "land_tax",
+            Series::Benefits => "benefits",
+            Series::BasicIncome => "basic_income",
+            Series::FoodStamps => "food_stamps",
+            Series::UnemploymentBenefits => "unemployment_benefits",
+            Series::Vouchers => "vouchers",
+            Series::Treasury => "treasury",
+            Series::AveragePrice => "average_price",
+            Series::Stock => "stock",
+            Series::Volume => "volume",
+            Series::TradePrice => "trade_price",
+            Series::Harvested => "harvested",
+            Series::TimeToHarvest => "time_to_harvest",
+            Series::Cpi => "cpi",
+            Series::Inflation => "inflation",
+            Series::NominalOutput => "nominal_output",
+            Series::ProductionValue => "production_value",
+            Series::TradeValue => "trade_value",
+            Series::MoneySupply => "money_supply",
+            Series::Velocity => "velocity",
+            Series::WealthGini => "wealth_gini",
+            Series::IncomeGini => "income_gini",
+            Series::HouseholdIncomeGini => "household_income_gini",
+            Series::AverageHouseholdIncome => "average_household_income",
+            Series::Unemployment => "unemployment",
+            Series::AverageWage => "average_wage",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeLevel {
+    /// O mundo todo. Os valores em ouro de cada país entram convertidos para
+    /// a unidade de conta comum das cotações (`Currency::rate`).
+    Global,
+    Country,
+    State,
+    City,
+}
+
+impl ScopeLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            ScopeLevel::Global => "global",
+            ScopeLevel::Country => "country",
+            ScopeLevel::State => "state",
+            ScopeLevel::City => "city",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricRow {
+    pub tick: u64,
+    pub level: ScopeLevel,
+    pub scope: String,
+    pub series: Series,
+    pub item: Option<ItemId>,
+    pub value: f64,
+}
+
+/// Séries registradas durante a execução.
+#[derive(Resource, Default)]
+pub struct Metrics {
+    /// Linhas registradas desde a última gravação no CSV, que as tira daqui.
+    pub rows: Vec<MetricRow>,
+    /// Unidades e valor negociados em cada cidade desde a última amostra.
+    pending_trades: HashMap<Entity, HashMap<ItemId, (usize, usize)>>,
+    /// Unidades, plantas e soma das idades colhidas em cada cidade desde a
+    /// última amostra.
+    pending_harvests: HashMap<Entity, HashMap<ItemId, (usize, usize, f64)>>,
+    /// Nascimentos, mortes e mortes de fome em cada cidade desde a última
+    /// amostra.
+    pending_vitals: HashMap<Entity, (usize, usize, usize)>,
+    /// Ouro emprestado e empréstimos executados em cada cidade desde a
+    /// última amostra.
+    pending_credit: HashMap<Entity, (usize, usize)>,
+    /// Impostos pagos e benefícios recebidos em cada cidade desde a última
+    /// amostra, por imposto e por benefício.
+    pending_taxes: HashMap<Entity, BTreeMap<Tax, usize>>,
+    pending_benefits: HashMap<Entity, BTreeMap<Benefit, usize>>,
+    /// Se o CSV já foi criado, com o cabeçalho.
+    started: bool,
+}
+
+impl Metrics {
+    /// Grava no CSV as linhas registradas desde a última gravação e as tira
+    /// da memória. A primeira cria o arquivo, com o cabeçalho; as seguintes
+    /// acrescentam. Se a gravação falha, as linhas ficam para a próxima.
+    pub fn write_csv(
+        &mut self,
+        path: &Path,
+        catalog: &ItemCatalog,
+        tick_secs: f32,
+    ) -> io::Result<()> {
+        let mut out = if !self.started {
+            let mut out = BufWriter::new(File::create(path)?);
+            writeln!(out, "tick,time_secs,level,scope,series,item,value")?;
+            out
+        } else {
+            BufWriter::new(OpenOptions::new().append(true).open(path)?)
+        };
+        for row in &self.rows {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                row.tick,
+                row.tick as f64 * tick_secs as f64,
+                row.level.name(),
+                csv_field(&row.scope),
+                row.series.name(),
+                row.item
+                    .map(|item| csv_field(catalog.name(item)))
+                    .unwrap_or_default(),
+                row.value
+            )?;
+        }
+        out.flush()?;
+        self.started = true;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Default, Clone)]
+struct ItemAggregate {
+    price_sum: f64,
+    offers: usize,
+    stock: usize,
+    volume: usize,
+    value: usize,
+    harvested: usize,
+    harvests: usize,
+    harvest_age: f64,
+}
+
+/// Somatórios de um escopo, combinados de cidade para estado, país e total.
+#[derive(Default, Clone)]
+struct Aggregate {
+    alive: usize,
+    births: usize,
+    deaths: usize,
+    starvations: usize,
+    age: f64,
+    children: usize,
+    households: usize,
+    hunger: f64,
+    health: f64,
+    gold: f64,
+    deposits: usize,
+    loans: usize,
+    reserves: usize,
+    new_loans: usize,
+    defaults: usize,
+    taxes: BTreeMap<Tax, usize>,
+    benefits: BTreeMap<Benefit, usize>,
+    vouchers: usize,
+    treasury: usize,
+    /// Só nos países, vindos do banco central e do câmbio; a moeda soma no
+    /// total, convertida pela cotação.
+    policy_rate: Option<f64>,
+    currency: Option<usize>,
+    exchange_rate: Option<f64>,
+    items: BTreeMap<ItemId, ItemAggregate>,
+}
+
+impl Aggregate {
+    fn merge(&mut self, other: &Aggregate) {
+        self.merge_at(other, 1.0);
+    }
+
+    /// Soma `other`, cujos valores em ouro estão numa moeda que vale `rate`
+    /// na moeda deste escopo. Os valores inteiros são arredondados.
+    fn merge_at(&mut self, other: &Aggregate, rate: f64) {
+        let convert = |amount: usize| (amount as f64 * rate).round() as usize;
+        self.alive += other.alive;
+        self.births += other.births;
+        self.deaths += other.deaths;
+        self.starvations += other.starvations;
+        self.age += other.age;
+        self.children += other.children;
+        self.households += other.households;
+        self.hunger += other.hunger;
+        self.health += other.health;
+        self.gold += other.gold * rate;
+        self.deposits += convert(other.deposits);
+        self.loans += convert(other.loans);
+        self.reserves += convert(other.reserves);
+        self.new_loans += convert(other.new_loans);
+        self.defaults += other.defaults;
+        for (tax, amount) in &other.taxes {
+            *self.taxes.entry(*tax).or_insert(0) += convert(*amount);
+        }
+        for (benefit, amount) in &other.benefits {
+            *self.benefits.entry(*benefit).or_insert(0) += convert(*amount);
+        }
+        self.vouchers += convert(other.vouchers);
+        self.treasury += convert(other.treasury);
+        if let Some(currency) = other.currency {
+            *self.currency.get_or_insert(0) += convert(currency);
+        }
+        for (item, data) in &other.items {
+            let entry = self.items.entry(*item).or_default();
+            entry.price_sum += data.price_sum * rate;
+            entry.offers += data.offers;
+            entry.stock += data.stock;
+            entry.volume += data.volume;
+            entry.value += convert(data.value);
+            entry.harvested += data.harvested;
+            entry.harvests += data.harvests;
+            entry.harvest_age += data.harvest_age;
+        }
+    }
+
+    fn emit(
+        &self,
+        rows: &mut Vec<MetricRow>,
+        config: &MetricsConfig,
+        tick: u64,
+        level: ScopeLevel,
+        scope: &str,
+        indicators: Option<&ScopeIndicators>,
+    ) {
+        let mut push = |series: Series, item: Option<ItemId>, value: f64| {
+            if config.records(series) {
+                rows.push(MetricRow {
+                    tick,
+                    level,
+                    scope: scope.to_string(),
+                    series,
+                    item,
+                    value,
+                });
+            }
+        };
+
+        let alive = self.alive.max(1) as f64;
+        push(Series::PopulationAlive, None, self.alive as f64);
+        push(Series::Births, None, self.births as f64);
+        push(Series::Deaths, None, self.deaths as f64);
+        push(Series::Starvations, None, self.starvations as f64);
+        push(Series::AverageAge, None, self.age / alive);
+        push(Series::Children, None, self.children as f64);
+        push(Series::AverageHunger, None, self.hunger / alive);
+        push(Series::AverageHealth, None, self.health / alive);
+        push(Series::AverageGold, None, self.gold / alive);
+        push(Series::Households, None, self.households as f64);
+        push(Series::Deposits, None, self.deposits as f64);
+        push(Series::Loans, None, self.loans as f64);
+        push(Series::BankReserves, None, self.reserves as f64);
+        push(
+            Series::BankEquity,
+            None,
+            (self.reserves + self.loans) as f64 - self.deposits as f64,
+        );
+        push(Series::NewLoans, None, self.new_loans as f64);
+        push(Series::Defaults, None, self.defaults as f64);
+        push(
+            Series::TaxRevenue,
+            None,
+            self.taxes.values().sum::<usize>() as f64,
+        );
+        for (tax, amount) in &self.taxes {
+            push(Series::of_tax(*tax), None, *amount as f64);
+        }
+        push(
+            Series::Benefits,
+            None,
+            self.benefits.values().sum::<usize>() as f64,
+        );
+        for (benefit, amount) in &self.benefits {
+            push(Series::of_benefit(*benefit), None, *amount as f64);
+        }
+        push(Series::Vouchers, None, self.vouchers as f64);
+        push(Series::Treasury, None, self.treasury as f64);
+        if let Some(rate) = self.policy_rate {
+            push(Series::PolicyRate, None, rate);
+        }
+        if let Some(currency) = self.currency {
+            push(Series::Currency, None, currency as f64);
+        }
+        if let Some(rate) = self.exchange_rate {
+            push(Series::ExchangeRate, None, rate);
+        }
+        for (item, data) in &self.items {
+            if data.offers > 0 {
+                push(
+                    Series::AveragePrice,
+                    Some(*item),
+                    data.price_sum / data.offers as f64,
+                );
+            }
+            push(Series::Stock, Some(*item), data.stock as f64);
+            push(Series::Volume, Some(*item), data.volume as f64);
+            if data.volume > 0 {
+                push(
+                    Series::TradePrice,
+                    Some(*item),
+                    data.value as f64 / data.volume as f64,
+                );
+            }
+            if data.harvests > 0 {
+                push(Series::Harvested, Some(*item), data.harvested as f64);
+                push(
+                    Series::TimeToHarvest,
+                    Some(*item),
+                    data.harvest_age / data.harvests as f64,
+                );
+            }
+        }
+        if let Some(indicators) = indicators {
+            push(Series::Cpi, None, indicators.cpi);
+            push(Series::Inflation, None, indicators.inflation);
+            push(Series::NominalOutput, None, indicators.nominal_output);
+            push(Series::ProductionValue, None, indicators.production_value);
+            push(Series::TradeValue, None, indicators.trade_value);
+            push(Series::MoneySupply, None, indicators.money_supply);
+            push(Series::Velocity, None, indicators.velocity);
+            push(Series::WealthGini, None, indicators.wealth_gini);
+            push(Series::IncomeGini, None, indicators.income_gini);
+            push(
+                Series::HouseholdIncomeGini,
+                None,
+                indicators.household_income_gini,
+            );
+            push(
+                Series::AverageHouseholdIncome,
+                None,
+                indicators.average_household_income,
+            );
+            push(Series::Unemployment, None, indicators.unemployment);
+            push(Series::AverageWage, None, indicators.average_wage);
+        }
+    }
+}
+
+/// Acumula as trocas, colheitas, nascimentos, mortes, empréstimos, impostos e
+/// benefícios de cada tick e, a cada `every_ticks`, registra as séries por
+/// cidade, estado, país e no total.
+#[allow(clippy::too_many_arguments)]
+pub fn record_metrics_system(
+    mut metrics: ResMut<Metrics>,
+    mut trades: EventReader<TradeEvent>,
+    mut harvests: EventReader<HarvestEvent>,
+    mut births: EventReader<BirthEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    (mut loans, mut taxes, mut benefits): (
+        EventReader<LoanEvent>,
+        EventReader<TaxEvent>,
+        EventReader<BenefitEvent>,
+    ),
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    indicators: Res<Indicators>,
+    countries: Query<(Entity, &Country, &Children)>,
+    states: Query<(&State, &Children)>,
+    cities: Query<(&City, &Children)>,
+    persons: Query<(&Person, &Alive)>,
+    households: Query<(&Household, Option<&Credit>)>,
+    shops: Query<(&Shop, Option<&Credit>)>,
+    banks: Query<(Option<&Bank>, Option<&CentralBank>)>,
+) {
+    let demography = &config.demography;
+    let config = &config.metrics;
+    if config.output.is_none() {
+        trades.clear();
+        harvests.clear();
+        births.clear();
+        deaths.clear();
+        loans.clear();
+        taxes.clear();
+        benefits.clear();
+        return;
+    }
+
+    for trade in trades.read() {
+        let counts = metrics
+            .pending_trades
+            .entry(trade.city)
+            .or_default()
+            .entry(trade.item)
+            .or_insert((0, 0));
+        counts.0 += trade.quantity;
+        counts.1 += trade.quantity * trade.unit_price;
+    }
+    for harvest in harvests.read() {
+        let counts = metrics
+            .pending_harvests
+            .entry(harvest.city)
+            .or_default()
+            .entry(harvest.item)
+            .or_insert((0, 0, 0.0));
+        counts.0 += harvest.quantity;
+        counts.1 += 1;
+        counts.2 += harvest.age_secs as f64;
+    }
+    for birth in births.read() {
+        metrics.pending_vitals.entry(birth.city).or_default().0 += 1;
+    }
+    for death in deaths.read() {
+        let counts = metrics.pending_vitals.entry(death.city).or_default();
+        counts.1 += 1;
+        if death.cause == DeathCause::Starvation {
+            counts.2 += 1;
+        }
+    }
+    for loan in loans.read() {
+        let counts = metrics.pending_credit.entry(loan.city).or_default();
+        match loan.outcome {
+            LoanOutcome::Granted => counts.0 += loan.amount,
+            LoanOutcome::Defaulted => counts.1 += 1,
+            LoanOutcome::Repaid => {}
+        }
+    }
+
+    for tax in taxes.read() {
+        *metrics
+            .pending_taxes
+            .entry(tax.city)
+            .or_default()
+            .entry(tax.tax)
+            .or_insert(0) += tax.amount;
+    }
+    for benefit in benefits.read() {
+        *metrics
+            .pending_benefits
+            .entry(benefit.city)
+            .or_default()
+            .entry(benefit.benefit)
+            .or_insert(0) += benefit.amount;
+    }
+
+    if !clock.tick.is_multiple_of(config.every_ticks) {
+        return;
+    }
+
+    let pending = std::mem::take(&mut metrics.pending_trades);
+    let pending_harvests = std::mem::take(&mut metrics.pending_harvests);
+    let pending_vitals = std::mem::take(&mut metrics.pending_vitals);
+    let pending_credit = std::mem::take(&mut metrics.pending_credit);
+    let pending_taxes = std::mem::take(&mut metrics.pending_taxes);
+    let pending_benefits = std::mem::take(&mut metrics.pending_benefits);
+    let rates: HashMap<&str, f64> = countries
+        .iter()
+        .map(|(_, country, _)| (country.currency.name.as_str(), country.currency.rate))
+        .collect();
+    let city_aggregate = |city: Entity, city_component: &City, children: &Children, rate: f64| {
+        let mut aggregate = Aggregate {
+            taxes: pending_taxes.get(&city).cloned().unwrap_or_default(),
+            benefits: pending_benefits.get(&city).cloned().unwrap_or_default(),
+            treasury: city_component.treasury,
+            ..default()
+        };
+        if let Some(&(births, deaths, starvations)) = pending_vitals.get(&city) {
+            aggregate.births = births;
+            aggregate.deaths = deaths;
+            aggregate.starvations = starvations;
+        }
+        if let Some(&(new_loans, defaults)) = pending_credit.get(&city) {
+            aggregate.new_loans = new_loans;
+            aggregate.defaults = defaults;
+        }
+        for (item, (volume, value)) in pending.get(&city).into_iter().flatten() {
+            let entry = aggregate.items.entry(*item).or_default();
+            entry.volume += volume;
+            entry.value += value;
+        }
+        for (item, (units, count, age)) in pending_harvests.get(&city).into_iter().flatten() {
+            let entry = aggregate.items.entry(*item).or_default();
+            entry.harvested += units;
+            entry.harvests += count;
+            entry.harvest_age += age;
+        }
+        for &child in children.iter() {
+            if let Ok((person, alive)) = persons.get(child) {
+                if alive.0 {
+                    aggregate.alive += 1;
+                    aggregate.age += person.age as f64;
+                    if !demography.is_adult(person.age) {
+                        aggregate.children += 1;
+                    }
+                    aggregate.hunger += person.hunger as f64;
+                    aggregate.health += person.health as f64;
+                    aggregate.gold += person.gold as f64;
+                    aggregate.vouchers += person.vouchers;
+                }
+            }
+            let credit = if let Ok((household, credit)) = households.get(child) {
+                aggregate.households += 1;
+                aggregate.gold += household.purse as f64;
+                credit
+            } else if let Ok((shop, credit)) = shops.get(child) {
+                for (item, details) in shop.items.iter() {
+                    let entry = aggregate.items.entry(*item).or_default();
+                    entry.price_sum += details.price as f64;
+                    entry.offers += 1;
+                    entry.stock += details.stock;
+                }
+                credit
+            } else {
+                None
+            };
+            if let Some(credit) = credit {
+                aggregate.deposits += credit.deposit;
+                aggregate.loans += credit.loan.map_or(0, |loan| loan.balance);
+            }
+            if let Ok((Some(bank), _)) = banks.get(child) {
+                aggregate.reserves += bank.cash;
+                for (currency, &amount) in &bank.foreign {
+                    let value = rates
+                        .get(currency.as_str())
+                        .map_or(0.0, |foreign| amount as f64 * foreign / rate);
+                    aggregate.reserves += value.round() as usize;
+                }
+            }
+        }
+        aggregate
+    };
+
+    let tick = clock.tick;
+    let mut rows = Vec::new();
+    let mut global = Aggregate::default();
+    for (country_entity, country, country_children) in countries.iter() {
+        let mut country_total = Aggregate::default();
+        for &state_entity in country_children.iter() {
+            let Ok((state, state_children)) = states.get(state_entity) else {
+                continue;
+            };
+            let mut state_total = Aggregate::default();
+            for &city_entity in state_children.iter() {
+                let Ok((city, city_children)) = cities.get(city_entity) else {
+                    continue;
+                };
+                let city_total =
+                    city_aggregate(city_entity, city, city_children, country.currency.rate);
+                city_total.emit(
+                    &mut rows,
+                    config,
+                    tick,
+                    ScopeLevel::City,
+                    &city.name,
+                    indicators.city(city_entity),
+                );
+                state_total.merge(&city_total);
+            }
+            state_total.treasury += state.treasury;
+            state_total.emit(
+                &mut rows,
+                config,
+                tick,
+                ScopeLevel::State,
+                &state.name,
+                indicators.state(state_entity),
+            );
+            country_total.merge(&state_total);
+        }
+        country_total.policy_rate = country_children
+            .iter()
+            .find_map(|&child| banks.get(child).ok().and_then(|(_, central)| central))
+            .map(|central| central.rate as f64);
+        country_total.treasury += country.treasury;
+        country_total.currency = Some(country.total_gold);
+        country_total.exchange_rate = Some(country.currency.rate);
+        country_total.emit(
+            &mut rows,
+            config,
+            tick,
+            ScopeLevel::Country,
+            &country.name,
+            indicators.country(country_entity),
+        );
+        // O total fica na unidade de conta comum das cotações
+        global.merge_at(&country_total, country.currency.rate);
+    }
+    global.emit(
+        &mut rows,
+        config,
+        tick,
+        ScopeLevel::Global,
+        "world",
+        Some(&indicators.world),
+    );
+
+    metrics.rows.extend(rows);
+}
+
+/// Grava no CSV as amostras novas no primeiro tick de cada dia e no tick em
+/// que o App recebe `AppExit`, para que uma execução interrompida com Ctrl-C
+/// perca no máximo um dia.
+pub fn write_metrics_system(
+    mut exit: EventReader<AppExit>,
+    mut metrics: ResMut<Metrics>,
+    clock: Res<SimClock>,
+    config: Res<ScenarioConfig>,
+    catalog: Res<ItemCatalog>,
+) {
+    let exiting = exit.read().next().is_some();
+    if !exiting && !every_day(clock) {
+        return;
+    }
+    let Some(path) = &config.metrics.output else {
+        return;
+    };
+
+    match metrics.write_csv(path, &catalog, config.tick_secs) {
+        Ok(()) if exiting => println!("Métricas salvas em {}", path.display()),
+        Ok(()) => {}
+        Err(err) => eprintln!("could not write metrics to {}: {}", path.display(), err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(tick: u64, scope: &str, series: Series, item: Option<ItemId>, value: f64) -> MetricRow {
+        MetricRow {
+            tick,
+            level: ScopeLevel::City,
+            scope: scope.to_string(),
+            series,
+            item,
+            value,
+        }
+    }
+
+    fn temp_csv(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "economy-metrics-{}-{}.csv",
+            std::process::id(),
+            name
+        ))
+    }
+
+    #[test]
+    fn the_world_total_converts_each_currency_at_its_rate() {
+        let country = Aggregate {
+            alive: 2,
+            gold: 10.0,
+            deposits: 5,
+            treasury: 3,
+            currency: Some(100),
+            ..default()
+        };
+        let mut global = Aggregate::default();
+        global.merge_at(&country, 1.0);
+        global.merge_at(&country, 2.5);
+        assert_eq!(global.alive, 4);
+        assert_eq!(global.gold, 35.0);
+        assert_eq!(global.deposits, 5 + 13);
+        assert_eq!(global.treasury, 3 + 8);
+        assert_eq!(global.currency, Some(350));
+    }
+
+    #[test]
+    fn taxes_and_benefits_are_broken_down_by_kind() {
+        let city = Aggregate {
+            taxes: BTreeMap::from([(Tax::Sales, 4), (Tax::Land, 6)]),
+            benefits: BTreeMap::from([(Benefit::FoodStamps, 5)]),
+            ..default()
+        };
+        let mut rows = Vec::new();
+        let config = MetricsConfig::default();
+        city.emit(&mut rows, &config, 40, ScopeLevel::City, "City 1", None);
+        let value = |series: Series| {
+            rows.iter()
+                .find(|row| row.series == series)
+                .map(|row| row.value)
+        };
+        assert_eq!(value(Series::TaxRevenue), Some(10.0));
+        assert_eq!(value(Series::SalesTax), Some(4.0));
+        assert_eq!(value(Series::LandTax), Some(6.0));
+        assert_eq!(value(Series::IncomeTax), None);
+        assert_eq!(value(Series::Benefits), Some(5.0));
+        assert_eq!(value(Series::FoodStamps), Some(5.0));
+    }
+
+    #[test]
+    fn write_csv_appends_new_rows_under_a_single_header() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let path = temp_csv("append");
+        let mut metrics = Metrics::default();
+
+        metrics
+            .rows
+            .push(row(4, "City 1", Series::PopulationAlive, None, 10.0));
+        metrics
+            .rows
+            .push(row(4, "Vila, \"Nova\"", Series::Stock, Some(apple), 2.5));
+        metrics.write_csv(&path, &catalog, 0.25).unwrap();
+        assert!(metrics.rows.is_empty());
+        metrics
+            .rows
+            .push(row(8, "City 1", Series::Births, None, 1.0));
+        metrics.write_csv(&path, &catalog, 0.25).unwrap();
+
+        let text = fs_text(&path);
+        assert_eq!(
+            text,
+            "tick,time_secs,level,scope,series,item,value\n\
+             4,1,city,City 1,population_alive,,10\n\
+             4,1,city,\"Vila, \"\"Nova\"\"\",stock,Apple,2.5\n\
+             8,2,city,City 1,births,,1\n"
+        );
+    }
+
+    #[test]
+    fn an_unbounded_run_writes_the_csv_every_day_and_keeps_only_the_rest() {
+        let path = temp_csv("daily");
+        let mut app = crate::build_app(ScenarioConfig {
+            seed: Some(7),
+            num_persons: 50,
+            num_shops: 5,
+            day_secs: 10.0,
+            metrics: MetricsConfig {
+                output: Some(path.clone()),
+                every_ticks: 10,
+                series: vec![Series::PopulationAlive],
+            },
+            ..default()
+        });
+        // Sem --ticks nem --until-day, nada envia AppExit. Dias de 40 ticks:
+        // as amostras até o tick 80 vão para o CSV, as dos ticks 90 e 100
+        // ficam na memória
+        for _ in 0..100 {
+            app.update();
+        }
+        let pending = &app.world().resource::<Metrics>().rows;
+        assert!(pending.iter().all(|row| row.tick > 80));
+        let scopes = pending.len() / 2;
+        assert!(scopes > 0);
+        let text = fs_text(&path);
+        let ticks: Vec<u64> = text
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(ticks.len(), 8 * scopes);
+        assert!(ticks.iter().all(|&tick| tick <= 80));
+    }
+
+    fn fs_text(path: &Path) -> String {
+        let text = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        text
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::banks::Bank;
+use crate::components::{City, Country, Household, Person, Shop, State, Wholesaler};
+use crate::config::ScenarioConfig;
+use crate::events::TransferEvent;
+use crate::indicators::Indicators;
+use crate::ledger::{Account, TransferKind};
+
+/// Política monetária dos bancos centrais. Taxas, crescimento e inflação
+/// contam por dia simulado.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonetaryConfig {
+    /// Política dos países sem uma própria.
+    pub default: MonetaryPolicy,
+    /// Política de cada país, pelo nome. Campos ausentes usam os valores de
+    /// `MonetaryPolicy::default()`, não os de `default`.
+    pub countries: BTreeMap<String, MonetaryPolicy>,
+}
+
+impl MonetaryConfig {
+    pub fn policy(&self, country: &str) -> &MonetaryPolicy {
+        self.countries.get(country).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    /// Taxa básica fixa em `neutral_rate` e moeda crescendo `money_growth`.
+    #[default]
+    FixedGrowth,
+    /// Regra de Taylor sobre o CPI do país: cada ponto de inflação acima da
+    /// meta sobe a taxa e reduz a emissão em `inflation_weight` pontos.
+    Taylor,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonetaryPolicy {
+    pub rule: PolicyRule,
+    /// Taxa básica com a inflação na meta. Os bancos somam a ela os seus
+    /// juros de depósito e de empréstimo.
+    pub neutral_rate: f32,
+    /// Fração da moeda do país emitida por dia com a inflação na meta;
+    /// negativa, recolhida.
+    pub money_growth: f32,
+    pub target_inflation: f32,
+    pub inflation_weight: f32,
+    /// Limites da taxa básica.
+    pub min_rate: f32,
+    pub max_rate: f32,
+    /// Limites do crescimento da moeda em um dia.
+    pub min_growth: f32,
+    pub max_growth: f32,
+}
+
+impl Default for MonetaryPolicy {
+    fn default() -> Self {
+        Self {
+            rule: PolicyRule::FixedGrowth,
+            neutral_rate: 0.01,
+            money_growth: 0.0,
+            target_inflation: 0.0,
+            inflation_weight: 1.5,
+            min_rate: 0.0,
+            max_rate: 0.1,
+            min_growth: -0.05,
+            max_growth: 0.05,
+        }
+    }
+}
+
+impl MonetaryPolicy {
+    /// Taxa básica e crescimento da moeda para a inflação do último dia.
+    pub fn decide(&self, inflation: f32) -> (f32, f32) {
+        let gap = match self.rule {
+            PolicyRule::FixedGrowth => 0.0,
+            PolicyRule::Taylor => (inflation - self.target_inflation) * self.inflation_weight,
+        };
+        let rate = (self.neutral_rate + gap).clamp(self.min_rate, self.max_rate);
+        let growth = (self.money_growth - gap).clamp(self.min_growth, self.max_growth);
+        (rate, growth)
+    }
+}
+
+/// Banco central de um país: fixa a taxa básica dos bancos comerciais e
+/// emite ou recolhe moeda pelo caixa deles.
+#[derive(Component, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CentralBank {
+    pub name: String,
+    /// Taxa básica por dia em vigor.
+    pub rate: f32,
+    /// CPI do país na última decisão, base da inflação do dia seguinte.
+    pub reference_cpi: f64,
+    /// Moeda emitida e recolhida desde o início.
+    pub issued: usize,
+    pub retired: usize,
+}
+
+/// Reunião diária de cada banco central. Mede a inflação do país pelo CPI
+/// desde a véspera, decide a taxa básica e o crescimento da moeda pela regra
+/// do país, repassa a taxa aos bancos comerciais e emite (ou recolhe) moeda
+/// dividida entre o caixa deles, em ordem de nome. `Country.total_gold` passa
+/// a ser todo o ouro (e vale-alimentação) em poder de Persons, casas, lojas,
+/// atacadistas, bancos e tesouros das cidades, dos estados e do próprio país,
+/// mais a moeda do país guardada por bancos de fora.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn monetary_policy_system(
+    mut countries: Query<(Entity, &mut Country, &Children)>,
+    states: Query<(&State, &Children)>,
+    cities: Query<(&City, &Children)>,
+    holders: Query<(
+        Option<&Person>,
+        Option<&Household>,
+        Option<&Shop>,
+        Option<&Wholesaler>,
+    )>,
+    mut banks: Query<(Entity, &mut Bank)>,
+    mut central_banks: Query<&mut CentralBank>,
+    indicators: Res<Indicators>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    let mut held_abroad: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, bank) in banks.iter() {
+        for (currency, amount) in &bank.foreign {
+            *held_abroad.entry(currency.clone()).or_insert(0) += amount;
+        }
+    }
+    for (country_entity, mut country, country_children) in countries.iter_mut() {
+        let Some(&central_entity) = country_children
+            .iter()
+            .find(|&&child| central_banks.contains(child))
+        else {
+            continue;
+        };
+        let mut currency = country.treasury
+            + held_abroad
+                .get(&country.currency.name)
+                .copied()
+                .unwrap_or(0);
+        let mut country_banks = Vec::new();
+        let country_states: Vec<(&State, &Children)> = country_children
+            .iter()
+            .filter_map(|&state| states.get(state).ok())
+            .collect();
+        currency += country_states
+            .iter()
+            .map(|(state, _)| state.treasury)
+            .sum::<usize>();
+        let city_children = country_states
+            .iter()
+            .flat_map(|(_, children)| children.iter())
+            .filter_map(|&city| cities.get(city).ok());
+        for (city, children) in city_children {
+            currency += city.treasury;
+            for &child in children.iter() {
+                if let Ok((entity, bank)) = banks.get(child) {
+                    currency += bank.cash;
+                    country_banks.push((bank.name.clone(), entity));
+                }
+                let Ok((person, household, shop, wholesaler)) = holders.get(child) else {
+                    continue;
+                };
+                currency += person.map_or(0, |person| person.gold + person.vouchers)
+                    + household.map_or(0, |household| household.purse)
+                    + shop.map_or(0, |shop| shop.cash)
+                    + wholesaler.map_or(0, |wholesaler| wholesaler.cash);
+            }
+        }
+        country_banks.sort();
+
+        let mut central = central_banks.get_mut(central_entity).unwrap();
+        let cpi = indicators.country(country_entity).map(|scope| scope.cpi);
+        let inflation = match cpi {
+            Some(cpi) if central.reference_cpi > 0.0 => (cpi / central.reference_cpi - 1.0) as f32,
+            _ => 0.0,
+        };
+        if let Some(cpi) = cpi.filter(|&cpi| cpi > 0.0) {
+            central.reference_cpi = cpi;
+        }
+        let (rate, growth) = config.monetary.policy(&country.name).decide(inflation);
+        central.rate = rate;
+
+        let amount = (currency as f32 * growth.abs()).round() as usize;
+        let count = country_banks.len().max(1);
+        for (i, (_, entity)) in country_banks.iter().enumerate() {
+            let (_, mut bank) = banks.get_mut(*entity).unwrap();
+            bank.base_rate = rate;
+            let share = amount / count + usize::from(i < amount % count);
+            let (moved, from, to, kind) = if growth >= 0.0 {
+                bank.cash += share;
+                central.issued += share;
+                currency += share;
+                (
+                    share,
+                    Account::Outside,
+                    Account::Agent(*entity),
+                    TransferKind::Issuance,
+                )
+            } else {
+                let share = share.min(bank.cash);
+                bank.cash -= share;
+                central.retired += share;
+                currency -= share;
+                (
+                    share,
+                    Account::Agent(*entity),
+                    Account::Outside,
+                    TransferKind::Retirement,
+                )
+            };
+            if moved > 0 {
+                transfers.send(TransferEvent {
+                    from,
+                    to,
+                    amount: moved,
+                    kind,
+                });
+            }
+        }
+        country.total_gold = currency;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn taylor_rule_tightens_above_target_and_fixed_growth_ignores_inflation() {
+        let fixed = MonetaryPolicy {
+            money_growth: 0.02,
+            ..default()
+        };
+        assert_eq!(fixed.decide(0.5), (0.01, 0.02));
+
+        let taylor = MonetaryPolicy {
+            rule: PolicyRule::Taylor,
+            money_growth: 0.02,
+            target_inflation: 0.01,
+            inflation_weight: 2.0,
+            ..default()
+        };
+        let (rate, growth) = taylor.decide(0.03);
+        assert!((rate - 0.05).abs() < 1e-6);
+        assert!((growth + 0.02).abs() < 1e-6);
+        // Deflação: a taxa para no piso e a emissão, no teto
+        assert_eq!(taylor.decide(-0.1), (0.0, 0.05));
+        // Inflação alta: a taxa para no teto e o recolhimento, no piso
+        assert_eq!(taylor.decide(0.5), (0.1, -0.05));
+    }
+
+    #[test]
+    fn issuance_is_split_across_banks_by_name_and_counted_in_total_gold() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::indicators::ScopeIndicators;
+
+        let mut world = World::new();
+        let mut config = ScenarioConfig::default();
+        config.monetary.default = MonetaryPolicy {
+            rule: PolicyRule::Taylor,
+            money_growth: 0.02,
+            ..default()
+        };
+        world.insert_resource(config);
+        world.init_resource::<Indicators>();
+        world.init_resource::<Events<TransferEvent>>();
+
+        let country = world.spawn(Country::default()).id();
+        let central = world.spawn(CentralBank::default()).id();
+        let state = world.spawn(State::default()).id();
+        let city = world.spawn(City::default()).id();
+        let banks: Vec<Entity> = ["C", "A", "B"]
+            .into_iter()
+            .map(|name| {
+                world
+                    .spawn(Bank {
+                        name: name.to_string(),
+                        cash: 100,
+                        ..default()
+                    })
+                    .id()
+            })
+            .collect();
+        let person = world
+            .spawn(Person {
+                gold: 700,
+                ..default()
+            })
+            .id();
+        world.entity_mut(country).add_children(&[central, state]);
+        world.entity_mut(state).add_child(city);
+        world
+            .entity_mut(city)
+            .add_children(&banks)
+            .add_child(person);
+        world.resource_mut::<Indicators>().countries.insert(
+            country,
+            ScopeIndicators {
+                cpi: 100.0,
+                ..default()
+            },
+        );
+        let cash = |world: &World| -> Vec<usize> {
+            let cash = |&bank: &Entity| world.get::<Bank>(bank).unwrap().cash;
+            banks.iter().map(cash).collect()
+        };
+
+        // Sem CPI anterior, inflação zero: emite 2% de 1000, com a sobra da
+        // divisão indo para os primeiros em ordem de nome (A, B, C)
+        world.run_system_once(monetary_policy_system).unwrap();
+        assert_eq!(cash(&world), vec![106, 107, 107]);
+        assert_eq!(world.get::<Country>(country).unwrap().total_gold, 1020);
+        let central_bank = world.get::<CentralBank>(central).unwrap();
+        assert_eq!(
+            (central_bank.issued, central_bank.reference_cpi),
+            (20, 100.0)
+        );
+
+        // CPI 50% mais alto: a regra pediria recolher 73% da moeda, mas o
+        // piso de crescimento limita a 5% de 1020, 51
+        world
+            .resource_mut::<Indicators>()
+            .countries
+            .get_mut(&country)
+            .unwrap()
+            .cpi = 150.0;
+        world.run_system_once(monetary_policy_system).unwrap();
+        assert_eq!(cash(&world), vec![89, 90, 90]);
+        assert_eq!(world.get::<Country>(country).unwrap().total_gold, 969);
+        let central_bank = world.get::<CentralBank>(central).unwrap();
+        assert_eq!((central_bank.retired, central_bank.rate), (51, 0.1));
+        assert!(banks
+            .iter()
+            .all(|&bank| world.get::<Bank>(bank).unwrap().base_rate == 0.1));
+        let retired: usize = world
+            .resource_mut::<Events<TransferEvent>>()
+            .drain()
+            .filter(|event| event.kind == TransferKind::Retirement)
+            .map(|event| event.amount)
+            .sum();
+        assert_eq!(retired, 51);
+    }
+}
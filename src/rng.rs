@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+/// Gerador de números aleatórios compartilhado por todos os sistemas.
+///
+/// Com a mesma semente e os sistemas rodando sempre na mesma ordem, duas
+/// execuções produzem exatamente o mesmo mundo e a mesma trajetória. O estado
+/// é serializável para que um snapshot retome a mesma sequência de sorteios.
+#[derive(Resource, Deref, DerefMut, Serialize, Deserialize, Clone)]
+pub struct SimRng(pub ChaCha12Rng);
+
+impl SimRng {
+    pub fn from_seed(seed: u64) -> Self {
+        SimRng(ChaCha12Rng::seed_from_u64(seed))
+    }
+}
@@ -0,0 +1,439 @@
+use std::fmt;
+
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::banks::{self, Bank, Credit};
+use crate::clock::SimClock;
+use crate::components::{Alive, City, Country, Household, Person, Shop, State, Wholesaler};
+use crate::config::ScenarioConfig;
+use crate::events::{LoanEvent, StockShortfallEvent, TransferEvent};
+use crate::items::ItemCatalog;
+use crate::ledger::{Account, TransferKind};
+use crate::shops;
+use crate::taxes::{Tax, TaxLevel, TaxRates};
+use crate::welfare::{Benefit, WelfarePolicy};
+
+/// Um evento da linha do tempo do cenário, como
+/// `{ day = 30, target = { state = "State 2" }, event = { destroy_stock = { item = "Apple", fraction = 0.5 } } }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Shock {
+    /// Dia simulado em que o choque acontece; pode ser fracionário.
+    pub day: f32,
+    /// Onde o choque acontece; sem alvo, no mundo todo.
+    #[serde(default)]
+    pub target: ShockTarget,
+    pub event: ShockEvent,
+}
+
+/// Parte da hierarquia atingida por um choque, pelo nome.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShockTarget {
+    #[default]
+    World,
+    Country(String),
+    State(String),
+    City(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShockEvent {
+    /// Destrói uma fração do estoque de um item nas lojas, nos inventários
+    /// das Persons e nas despensas, como numa praga ou num incêndio.
+    DestroyStock { item: String, fraction: f32 },
+    /// Dá ouro vindo de fora da economia a cada Person viva.
+    GiveGold { amount: usize },
+    /// Fecha todas as lojas; a conta no banco é encerrada e o caixa vai
+    /// para o atacadista da cidade.
+    CloseShops,
+    /// Muda uma alíquota das cidades do alvo, ou dos estados ou países a
+    /// que elas pertencem.
+    SetTax {
+        level: TaxLevel,
+        tax: Tax,
+        rate: f32,
+    },
+    /// Muda o valor diário de um benefício, como `set_tax`.
+    SetBenefit {
+        level: TaxLevel,
+        benefit: Benefit,
+        amount: usize,
+    },
+}
+
+impl Shock {
+    /// Tick em que o choque acontece.
+    pub fn tick(&self, clock: &SimClock) -> u64 {
+        clock.ticks_for(self.day * clock.day_secs)
+    }
+}
+
+impl fmt::Display for ShockTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShockTarget::World => write!(f, "todo o mundo"),
+            ShockTarget::Country(name) | ShockTarget::State(name) | ShockTarget::City(name) => {
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ShockEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShockEvent::DestroyStock { item, fraction } => {
+                write!(
+                    f,
+                    "{:.0}% do estoque de {} destruído",
+                    fraction * 100.0,
+                    item
+                )
+            }
+            ShockEvent::GiveGold { amount } => write!(f, "{} de ouro para cada Person", amount),
+            ShockEvent::CloseShops => write!(f, "todas as lojas fechadas"),
+            ShockEvent::SetTax {
+                level,
+                tax: Tax::Land,
+                rate,
+            } => write!(f, "imposto territorial {} passa a {} por lote", level, rate),
+            ShockEvent::SetTax { level, tax, rate } => {
+                write!(f, "{} {} passa a {}%", tax, level, rate * 100.0)
+            }
+            ShockEvent::SetBenefit {
+                level,
+                benefit,
+                amount,
+            } => write!(f, "{} {} passa a {} por dia", benefit, level, amount),
+        }
+    }
+}
+
+/// Aplica, no tick marcado, os choques da linha do tempo do cenário às
+/// cidades do alvo. Um país atinge as cidades de todos os seus estados.
+#[allow(clippy::too_many_arguments)]
+pub fn shock_system(
+    mut commands: Commands,
+    mut countries: Query<(&mut Country, &Children)>,
+    mut states: Query<(Entity, &mut State, &Children)>,
+    mut cities: Query<(Entity, &mut City)>,
+    mut persons: Query<(Entity, &mut Person, &Alive, &Parent)>,
+    mut households: Query<(&mut Household, &Parent)>,
+    mut shops: Query<(Entity, &mut Shop, &Parent, Option<&mut Credit>)>,
+    mut wholesalers: Query<(Entity, &mut Wholesaler, &Parent)>,
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    catalog: Res<ItemCatalog>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut loans: EventWriter<LoanEvent>,
+    mut shortfalls: EventWriter<StockShortfallEvent>,
+) {
+    for shock in config
+        .shocks
+        .iter()
+        .filter(|s| s.tick(&clock) == clock.tick)
+    {
+        let targets: HashSet<Entity> = match &shock.target {
+            ShockTarget::World => cities.iter().map(|(entity, _)| entity).collect(),
+            ShockTarget::Country(name) => countries
+                .iter()
+                .filter(|(country, _)| &country.name == name)
+                .flat_map(|(_, children)| children.iter())
+                .filter_map(|&state| states.get(state).ok())
+                .flat_map(|(_, _, children)| children.iter().copied())
+                .collect(),
+            ShockTarget::State(name) => states
+                .iter()
+                .filter(|(_, state, _)| &state.name == name)
+                .flat_map(|(_, _, children)| children.iter().copied())
+                .collect(),
+            ShockTarget::City(name) => cities
+                .iter()
+                .filter(|(_, city)| &city.name == name)
+                .map(|(entity, _)| entity)
+                .collect(),
+        };
+        if targets.is_empty() {
+            eprintln!(
+                "shock on day {}: no city matches {:?}",
+                shock.day, shock.target
+            );
+            continue;
+        }
+        println!(
+            "Choque no dia {} em {}: {}",
+            shock.day, shock.target, shock.event
+        );
+
+        match &shock.event {
+            ShockEvent::DestroyStock { item, fraction } => {
+                let Some(item) = catalog.find(item) else {
+                    eprintln!("shock on day {}: unknown item `{}`", shock.day, item);
+                    continue;
+                };
+                let destroyed = |units: usize| (units as f32 * fraction).round() as usize;
+                for (entity, mut shop, city, _) in shops.iter_mut() {
+                    if !targets.contains(&city.get()) {
+                        continue;
+                    }
+                    if let Some(details) = shop.items.get_mut(&item) {
+                        let units = destroyed(details.stock);
+                        shops::take_stock(details, entity, item, units, &mut shortfalls);
+                    }
+                }
+                for (_, mut person, _, city) in persons.iter_mut() {
+                    if !targets.contains(&city.get()) {
+                        continue;
+                    }
+                    if let Some(units) = person.inventory.get_mut(&item) {
+                        *units -= destroyed((*units).max(0) as usize) as i32;
+                    }
+                }
+                for (mut household, city) in households.iter_mut() {
+                    if !targets.contains(&city.get()) {
+                        continue;
+                    }
+                    if let Some(units) = household.pantry.get_mut(&item) {
+                        *units -= destroyed((*units).max(0) as usize) as i32;
+                    }
+                }
+            }
+            ShockEvent::GiveGold { amount } => {
+                for (entity, mut person, alive, city) in persons.iter_mut() {
+                    if !alive.0 || !targets.contains(&city.get()) || *amount == 0 {
+                        continue;
+                    }
+                    person.gold += amount;
+                    transfers.send(TransferEvent {
+                        from: Account::Outside,
+                        to: Account::Agent(entity),
+                        amount: *amount,
+                        kind: TransferKind::Windfall,
+                    });
+                }
+            }
+            ShockEvent::CloseShops => {
+                for (entity, mut shop, city, credit) in shops.iter_mut() {
+                    if !targets.contains(&city.get()) {
+                        continue;
+                    }
+                    if let Some(mut credit) = credit {
+                        let bank = banks
+                            .iter_mut()
+                            .find(|(_, _, parent)| parent.get() == city.get())
+                            .map(|(bank_entity, bank, _)| (bank_entity, bank));
+                        banks::close_account(
+                            bank,
+                            entity,
+                            city.get(),
+                            &mut credit,
+                            &mut shop.cash,
+                            &mut transfers,
+                            &mut loans,
+                        );
+                    }
+                    let wholesaler = wholesalers
+                        .iter_mut()
+                        .find(|(_, _, parent)| parent.get() == city.get())
+                        .map(|(entity, wholesaler, _)| (entity, wholesaler));
+                    let city = cities.get_mut(city.get()).ok().map(|(_, city)| city);
+                    shops::close_shop(
+                        &mut commands,
+                        entity,
+                        &mut shop,
+                        wholesaler,
+                        city,
+                        &mut transfers,
+                    );
+                }
+            }
+            ShockEvent::SetTax { level, tax, rate } => set_policy(
+                *level,
+                &targets,
+                &mut cities,
+                &mut states,
+                &mut countries,
+                |taxes, _| taxes.set(*tax, *rate),
+            ),
+            ShockEvent::SetBenefit {
+                level,
+                benefit,
+                amount,
+            } => set_policy(
+                *level,
+                &targets,
+                &mut cities,
+                &mut states,
+                &mut countries,
+                |_, welfare| welfare.set(*benefit, *amount),
+            ),
+        }
+    }
+}
+
+/// Muda os impostos e benefícios do nível `level` em cada cidade do alvo, no
+/// estado ou no país de cada uma delas.
+fn set_policy(
+    level: TaxLevel,
+    targets: &HashSet<Entity>,
+    cities: &mut Query<(Entity, &mut City)>,
+    states: &mut Query<(Entity, &mut State, &Children)>,
+    countries: &mut Query<(&mut Country, &Children)>,
+    change: impl Fn(&mut TaxRates, &mut WelfarePolicy),
+) {
+    let governs = |children: &Children| children.iter().any(|city| targets.contains(city));
+    match level {
+        TaxLevel::City => {
+            for (entity, mut city) in cities.iter_mut() {
+                if targets.contains(&entity) {
+                    let city = &mut *city;
+                    change(&mut city.taxes, &mut city.welfare);
+                }
+            }
+        }
+        TaxLevel::State => {
+            for (_, mut state, children) in states.iter_mut() {
+                if governs(children) {
+                    let state = &mut *state;
+                    change(&mut state.taxes, &mut state.welfare);
+                }
+            }
+        }
+        TaxLevel::Country => {
+            let hit: HashSet<Entity> = states
+                .iter()
+                .filter(|(_, _, children)| governs(children))
+                .map(|(entity, ..)| entity)
+                .collect();
+            for (mut country, children) in countries.iter_mut() {
+                if children.iter().any(|state| hit.contains(state)) {
+                    let country = &mut *country;
+                    change(&mut country.taxes, &mut country.welfare);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn stress_test_timeline_parses() {
+        let config = ScenarioConfig::load(Path::new("scenarios/stress_test.toml")).unwrap();
+        let clock = SimClock::new(config.tick_secs, config.day_secs);
+
+        let [famine, windfall, closure] = config.shocks.as_slice() else {
+            panic!("expected three shocks, got {:?}", config.shocks);
+        };
+        assert_eq!(famine.target, ShockTarget::State("State 2".to_string()));
+        assert_eq!(famine.tick(&clock), 2400);
+        assert_eq!(windfall.target, ShockTarget::World);
+        assert_eq!(windfall.event, ShockEvent::GiveGold { amount: 100 });
+        assert_eq!(closure.event, ShockEvent::CloseShops);
+    }
+
+    #[test]
+    fn shocks_hit_only_their_target_at_their_tick() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let mut world = World::new();
+        let shock = |target: ShockTarget, event: ShockEvent| Shock {
+            day: 1.0,
+            target,
+            event,
+        };
+        let config = ScenarioConfig {
+            shocks: vec![
+                shock(
+                    ShockTarget::State("North".to_string()),
+                    ShockEvent::DestroyStock {
+                        item: "Apple".to_string(),
+                        fraction: 0.5,
+                    },
+                ),
+                shock(ShockTarget::City("C".to_string()), ShockEvent::CloseShops),
+            ],
+            ..default()
+        };
+        world.insert_resource(config);
+        world.insert_resource(catalog);
+        // Um dia de 10 ticks: os choques caem no tick 10
+        world.insert_resource(SimClock::new(1.0, 10.0));
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<LoanEvent>>();
+        world.init_resource::<Events<StockShortfallEvent>>();
+
+        // North tem as cidades A e B; South, a cidade C
+        let country = world.spawn(Country::default()).id();
+        let mut cities = Vec::new();
+        for (state, names) in [("North", &["A", "B"][..]), ("South", &["C"][..])] {
+            let state = world
+                .spawn(State {
+                    name: state.to_string(),
+                    ..default()
+                })
+                .id();
+            world.entity_mut(country).add_child(state);
+            for name in names {
+                let city = world
+                    .spawn(City {
+                        name: name.to_string(),
+                        ..default()
+                    })
+                    .id();
+                world.entity_mut(state).add_child(city);
+                let mut shop = Shop {
+                    cash: 50,
+                    ..default()
+                };
+                shop.stock_item(apple, 10, 10);
+                let shop = world.spawn(shop).id();
+                let wholesaler = world.spawn(Wholesaler::default()).id();
+                let mut person = Person::default();
+                person.inventory.insert(apple, 4);
+                let person = world.spawn((person, Alive(true))).id();
+                world
+                    .entity_mut(city)
+                    .add_children(&[shop, wholesaler, person]);
+                world.get_mut::<City>(city).unwrap().shops.push(shop);
+                cities.push((city, shop, wholesaler, person));
+            }
+        }
+        let apples = |world: &World| -> Vec<(Option<usize>, i32)> {
+            cities
+                .iter()
+                .map(|&(_, shop, _, person)| {
+                    let stock = world.get::<Shop>(shop).map(|shop| shop.items[&apple].stock);
+                    (
+                        stock,
+                        world.get::<Person>(person).unwrap().inventory[&apple],
+                    )
+                })
+                .collect()
+        };
+
+        world.resource_mut::<SimClock>().tick = 9;
+        world.run_system_once(shock_system).unwrap();
+        assert_eq!(apples(&world), vec![(Some(10), 4); 3]);
+
+        world.resource_mut::<SimClock>().tick = 10;
+        world.run_system_once(shock_system).unwrap();
+        assert_eq!(apples(&world), vec![(Some(5), 2), (Some(5), 2), (None, 4)]);
+        let (c, _, wholesaler, _) = cities[2];
+        assert!(world.get::<City>(c).unwrap().shops.is_empty());
+        assert_eq!(world.get::<Wholesaler>(wholesaler).unwrap().cash, 50);
+        for &(city, shop, ..) in &cities[..2] {
+            assert_eq!(world.get::<City>(city).unwrap().shops, vec![shop]);
+        }
+    }
+}
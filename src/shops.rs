@@ -0,0 +1,435 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::banks::{self, Bank, Credit};
+use crate::components::{City, Employer, ItemDetails, Shop, Wholesaler};
+use crate::config::ScenarioConfig;
+use crate::events::{LoanEvent, StockShortfallEvent, TransferEvent};
+use crate::items::{ItemCatalog, ItemId};
+use crate::ledger::{Account, TransferKind};
+
+/// Parâmetros das lojas e dos atacadistas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShopConfig {
+    /// Caixa inicial de cada loja.
+    pub start_cash: usize,
+    /// Fração abaixo do preço de venda em que a loja compra.
+    pub spread: f32,
+    /// Estoque de um item a partir do qual a loja para de comprar.
+    pub max_stock: usize,
+    /// Abaixo deste estoque a loja encomenda ao atacadista.
+    pub restock_below: usize,
+    /// Unidades de cada encomenda.
+    pub restock_batch: usize,
+    /// Preço do atacado, como fração do preço base do catálogo.
+    pub wholesale_price: f32,
+    /// Quanto o atacadista paga pelas colheitas, como fração do preço base.
+    pub crop_price: f32,
+    /// Caixa inicial do atacadista de cada cidade.
+    pub wholesaler_start_cash: usize,
+}
+
+impl Default for ShopConfig {
+    fn default() -> Self {
+        Self {
+            start_cash: 200,
+            spread: 0.2,
+            max_stock: 60,
+            restock_below: 5,
+            restock_batch: 20,
+            wholesale_price: 0.8,
+            crop_price: 0.7,
+            wholesaler_start_cash: 500,
+        }
+    }
+}
+
+impl ShopConfig {
+    /// Preço unitário de um item no atacado.
+    pub fn wholesale_price(&self, catalog: &ItemCatalog, item: ItemId) -> usize {
+        ((catalog.get(item).base_price as f32 * self.wholesale_price).ceil() as usize).max(1)
+    }
+
+    /// Preço que o atacadista oferece por uma colheita.
+    pub fn crop_price(&self, catalog: &ItemCatalog, item: ItemId) -> usize {
+        (catalog.get(item).base_price as f32 * self.crop_price).floor() as usize
+    }
+}
+
+/// Tira `quantity` unidades de `item` do estoque de uma loja. Se ela não
+/// tiver tanto, fica sem nada e a falta vai para a auditoria.
+pub fn take_stock(
+    details: &mut ItemDetails,
+    shop: Entity,
+    item: ItemId,
+    quantity: usize,
+    shortfalls: &mut EventWriter<StockShortfallEvent>,
+) {
+    match details.stock.checked_sub(quantity) {
+        Some(left) => details.stock = left,
+        None => {
+            shortfalls.send(StockShortfallEvent {
+                shop,
+                item,
+                wanted: quantity,
+                stock: details.stock,
+            });
+            details.stock = 0;
+        }
+    }
+}
+
+/// Atacadista de cada cidade.
+fn wholesalers_by_city(
+    wholesalers: &Query<(Entity, &mut Wholesaler, &Parent)>,
+) -> HashMap<Entity, Entity> {
+    wholesalers
+        .iter()
+        .map(|(entity, _, city)| (city.get(), entity))
+        .collect()
+}
+
+/// Lojas com pouco estoque compram um lote do atacadista da cidade, até onde
+/// o caixa alcança sem tocar na reserva para os salários.
+pub fn restock_system(
+    mut shops: Query<(Entity, &mut Shop, &Parent)>,
+    mut wholesalers: Query<(Entity, &mut Wholesaler, &Parent)>,
+    employers: Query<&Employer>,
+    catalog: Res<ItemCatalog>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    let labor = &config.labor;
+    let config = &config.shops;
+    let by_city = wholesalers_by_city(&wholesalers);
+
+    for (entity, mut shop, city) in shops.iter_mut() {
+        let Some(&wholesaler_entity) = by_city.get(&city.get()) else {
+            continue;
+        };
+        let Ok((_, mut wholesaler, _)) = wholesalers.get_mut(wholesaler_entity) else {
+            continue;
+        };
+
+        let reserve = labor.payroll_reserve(employers.get(entity).ok(), labor.shop_jobs);
+        let shop = &mut *shop;
+        for (item, details) in shop.items.iter_mut() {
+            if details.stock >= config.restock_below {
+                continue;
+            }
+            let unit_price = config.wholesale_price(&catalog, *item);
+            let budget = shop.cash.saturating_sub(reserve);
+            let quantity = config.restock_batch.min(budget / unit_price);
+            if quantity == 0 {
+                continue;
+            }
+            shop.cash -= unit_price * quantity;
+            wholesaler.cash += unit_price * quantity;
+            details.stock += quantity;
+            transfers.send(TransferEvent {
+                from: Account::Agent(entity),
+                to: Account::Agent(wholesaler_entity),
+                amount: unit_price * quantity,
+                kind: TransferKind::Restock,
+            });
+        }
+    }
+}
+
+/// Remove as lojas insolventes: sem nada em estoque e sem caixa, fora a
+/// reserva para os salários, para encomendar nem uma unidade. A conta no banco
+/// fecha antes, e o que sobra no caixa fica com o atacadista da cidade, o
+/// credor dela.
+#[allow(clippy::too_many_arguments)]
+pub fn bankruptcy_system(
+    mut commands: Commands,
+    mut shops: Query<(Entity, &mut Shop, &Parent, Option<&mut Credit>)>,
+    mut wholesalers: Query<(Entity, &mut Wholesaler, &Parent)>,
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    mut cities: Query<&mut City>,
+    employers: Query<&Employer>,
+    catalog: Res<ItemCatalog>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut loans: EventWriter<LoanEvent>,
+) {
+    let labor = &config.labor;
+    let by_city = wholesalers_by_city(&wholesalers);
+
+    for (entity, mut shop, city, credit) in shops.iter_mut() {
+        let empty = shop.items.values().all(|details| details.stock == 0);
+        let cheapest_order = shop
+            .items
+            .keys()
+            .map(|item| config.shops.wholesale_price(&catalog, *item))
+            .min()
+            .unwrap_or(0);
+        let reserve = labor.payroll_reserve(employers.get(entity).ok(), labor.shop_jobs);
+        let budget = shop.cash.saturating_sub(reserve);
+        if !empty || budget >= cheapest_order {
+            continue;
+        }
+
+        if let Some(mut credit) = credit {
+            let bank = banks
+                .iter_mut()
+                .find(|(_, _, parent)| parent.get() == city.get())
+                .map(|(bank_entity, bank, _)| (bank_entity, bank));
+            banks::close_account(
+                bank,
+                entity,
+                city.get(),
+                &mut credit,
+                &mut shop.cash,
+                &mut transfers,
+                &mut loans,
+            );
+        }
+        let wholesaler = by_city
+            .get(&city.get())
+            .and_then(|&wholesaler| wholesalers.get_mut(wholesaler).ok())
+            .map(|(wholesaler_entity, wholesaler, _)| (wholesaler_entity, wholesaler));
+        let city = cities.get_mut(city.get()).ok();
+        if let Some(city) = &city {
+            println!("Uma loja de {} faliu!", city.name);
+        }
+        close_shop(
+            &mut commands,
+            entity,
+            &mut shop,
+            wholesaler,
+            city,
+            &mut transfers,
+        );
+    }
+}
+
+/// Fecha uma loja: o que sobra no caixa fica com o atacadista da cidade, o
+/// credor dela, e a loja sai da cidade.
+pub fn close_shop(
+    commands: &mut Commands,
+    entity: Entity,
+    shop: &mut Shop,
+    wholesaler: Option<(Entity, Mut<Wholesaler>)>,
+    city: Option<Mut<City>>,
+    transfers: &mut EventWriter<TransferEvent>,
+) {
+    if let Some((wholesaler_entity, mut wholesaler)) = wholesaler {
+        if shop.cash > 0 {
+            transfers.send(TransferEvent {
+                from: Account::Agent(entity),
+                to: Account::Agent(wholesaler_entity),
+                amount: shop.cash,
+                kind: TransferKind::Liquidation,
+            });
+        }
+        wholesaler.cash += shop.cash;
+        shop.cash = 0;
+    }
+    if let Some(mut city) = city {
+        city.shops.retain(|shop| *shop != entity);
+    }
+    commands.entity(entity).despawn_recursive();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// Mundo com uma cidade e o seu atacadista, pronto para rodar um sistema.
+    fn world() -> (World, Entity, Entity) {
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(ItemCatalog::default());
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<LoanEvent>>();
+        let city = world.spawn(City::default()).id();
+        let wholesaler = world
+            .spawn(Wholesaler {
+                cash: 500,
+                ..default()
+            })
+            .id();
+        world.entity_mut(city).add_child(wholesaler);
+        (world, city, wholesaler)
+    }
+
+    fn spawn_shop(
+        world: &mut World,
+        city: Entity,
+        cash: usize,
+        stock: &[(ItemId, usize)],
+    ) -> Entity {
+        let catalog = ItemCatalog::default();
+        let mut shop = Shop { cash, ..default() };
+        for (item, stock) in stock {
+            shop.stock_item(*item, catalog.get(*item).base_price, *stock);
+        }
+        let entity = world.spawn(shop).id();
+        world.entity_mut(city).add_child(entity);
+        world.get_mut::<City>(city).unwrap().shops.push(entity);
+        entity
+    }
+
+    #[test]
+    fn buys_below_the_ask_by_the_spread() {
+        let details = ItemDetails {
+            price: 10,
+            stock: 0,
+            transactions: (0, 0),
+        };
+        assert_eq!(details.ask_price(), 10);
+        assert_eq!(details.bid_price(0.2), 8);
+        assert_eq!(details.bid_price(0.25), 7);
+        assert_eq!(details.bid_price(0.0), 10);
+    }
+
+    #[test]
+    fn taking_more_than_the_stock_empties_it_and_reports_the_shortfall() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let (mut world, city, _) = world();
+        world.init_resource::<Events<StockShortfallEvent>>();
+        let shop = spawn_shop(&mut world, city, 0, &[(apple, 5)]);
+
+        let take = move |quantity: usize| {
+            move |mut shops: Query<&mut Shop>, mut shortfalls: EventWriter<StockShortfallEvent>| {
+                let mut shop_component = shops.get_mut(shop).unwrap();
+                let details = shop_component.items.get_mut(&apple).unwrap();
+                take_stock(details, shop, apple, quantity, &mut shortfalls);
+            }
+        };
+        world.run_system_once(take(4)).unwrap();
+        assert_eq!(world.get::<Shop>(shop).unwrap().items[&apple].stock, 1);
+        assert!(world.resource::<Events<StockShortfallEvent>>().is_empty());
+
+        world.run_system_once(take(3)).unwrap();
+        assert_eq!(world.get::<Shop>(shop).unwrap().items[&apple].stock, 0);
+        let shortfalls: Vec<_> = world
+            .resource_mut::<Events<StockShortfallEvent>>()
+            .drain()
+            .map(|shortfall| {
+                (
+                    shortfall.shop,
+                    shortfall.item,
+                    shortfall.wanted,
+                    shortfall.stock,
+                )
+            })
+            .collect();
+        assert_eq!(shortfalls, vec![(shop, apple, 3, 1)]);
+    }
+
+    #[test]
+    fn restocks_low_items_in_batches_the_cash_can_pay() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let potato = catalog.find("Potato").unwrap();
+        let (mut world, city, wholesaler) = world();
+        let config = ScenarioConfig::default().shops;
+        assert_eq!(config.wholesale_price(&catalog, apple), 8);
+
+        // Caixa para 12 maçãs das 20 do lote; as batatas ainda têm estoque
+        let rich = spawn_shop(&mut world, city, 100, &[(apple, 2), (potato, 10)]);
+        let poor = spawn_shop(&mut world, city, 10, &[(apple, 0)]);
+        world.run_system_once(restock_system).unwrap();
+
+        let shop = world.get::<Shop>(rich).unwrap();
+        assert_eq!(shop.items[&apple].stock, 14);
+        assert_eq!(shop.items[&potato].stock, 10);
+        assert_eq!(shop.cash, 4);
+        let shop = world.get::<Shop>(poor).unwrap();
+        assert_eq!(shop.items[&apple].stock, 1);
+        assert_eq!(shop.cash, 2);
+        assert_eq!(
+            world.get::<Wholesaler>(wholesaler).unwrap().cash,
+            500 + 96 + 8
+        );
+
+        let transfers: Vec<_> = world
+            .resource_mut::<Events<TransferEvent>>()
+            .drain()
+            .map(|transfer| (transfer.from, transfer.amount, transfer.kind))
+            .collect();
+        assert_eq!(transfers.len(), 2);
+        assert!(transfers.contains(&(Account::Agent(rich), 96, TransferKind::Restock)));
+        assert!(transfers.contains(&(Account::Agent(poor), 8, TransferKind::Restock)));
+    }
+
+    #[test]
+    fn restocking_leaves_the_payroll_reserve_untouched() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let (mut world, city, _) = world();
+        let config = ScenarioConfig::default().labor;
+
+        // Duas vagas a 0,5/s por 60 s guardam 60 dos 100 do caixa
+        let shop = spawn_shop(&mut world, city, 100, &[(apple, 2)]);
+        let employer = config.employer(100, config.shop_jobs);
+        assert_eq!(
+            config.payroll_reserve(Some(&employer), config.shop_jobs),
+            60
+        );
+        world.entity_mut(shop).insert(employer);
+        world.run_system_once(restock_system).unwrap();
+
+        let shop = world.get::<Shop>(shop).unwrap();
+        assert_eq!(shop.items[&apple].stock, 7);
+        assert_eq!(shop.cash, 60);
+    }
+
+    #[test]
+    fn closes_empty_shops_that_cannot_order_a_unit() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let (mut world, city, wholesaler) = world();
+
+        let broke = spawn_shop(&mut world, city, 3, &[(apple, 0)]);
+        let can_order = spawn_shop(&mut world, city, 8, &[(apple, 0)]);
+        let stocked = spawn_shop(&mut world, city, 0, &[(apple, 1)]);
+        world.run_system_once(bankruptcy_system).unwrap();
+
+        assert!(world.get_entity(broke).is_err());
+        assert_eq!(
+            world.get::<City>(city).unwrap().shops,
+            vec![can_order, stocked]
+        );
+        assert_eq!(world.get::<Wholesaler>(wholesaler).unwrap().cash, 503);
+        assert_eq!(world.get::<Shop>(can_order).unwrap().cash, 8);
+        let transfers: Vec<_> = world
+            .resource_mut::<Events<TransferEvent>>()
+            .drain()
+            .map(|transfer| (transfer.from, transfer.to, transfer.amount, transfer.kind))
+            .collect();
+        assert_eq!(
+            transfers,
+            vec![(
+                Account::Agent(broke),
+                Account::Agent(wholesaler),
+                3,
+                TransferKind::Liquidation
+            )]
+        );
+    }
+
+    #[test]
+    fn the_payroll_reserve_does_not_keep_an_empty_shop_open() {
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let (mut world, city, wholesaler) = world();
+        let config = ScenarioConfig::default().labor;
+
+        // 50 no caixa pagariam 6 maçãs, mas a reserva das duas vagas é 60
+        let shop = spawn_shop(&mut world, city, 50, &[(apple, 0)]);
+        let employer = config.employer(100, config.shop_jobs);
+        world.entity_mut(shop).insert(employer);
+        world.run_system_once(bankruptcy_system).unwrap();
+
+        assert!(world.get_entity(shop).is_err());
+        assert!(world.get::<City>(city).unwrap().shops.is_empty());
+        assert_eq!(world.get::<Wholesaler>(wholesaler).unwrap().cash, 550);
+    }
+}
@@ -0,0 +1,996 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::banks::{Bank, Credit};
+use crate::clock::SimClock;
+use crate::components::{
+    Alive, City, Country, Employer, Employment, Family, Household, Job, JobSeeker, Person, Plant,
+    Plot, Shop, State, Wholesaler,
+};
+use crate::config::ScenarioConfig;
+use crate::events::TransferEvent;
+use crate::indicators::Indicators;
+use crate::items::{ItemCatalog, ItemId, MarketPrices, PriceTable};
+use crate::ledger::{Account, TransferKind};
+use crate::market::{CityMarkets, Quote};
+use crate::monetary::CentralBank;
+use crate::rng::SimRng;
+use crate::weather::Weather;
+
+/// Estado completo do mundo, gravado em RON.
+///
+/// As referências entre entidades (a hierarquia `Children` montada em
+/// `setup`) são guardadas como índices nos vetores do próprio snapshot e
+/// remapeadas para novas entidades ao restaurar.
+#[derive(Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub config: ScenarioConfig,
+    pub tick: u64,
+    pub rng: SimRng,
+    pub catalog: ItemCatalog,
+    /// Preços de mercado de cada país, na ordem de `countries`.
+    #[serde(default)]
+    pub prices: Vec<PriceTable>,
+    /// Pesos do CPI acumulados até aqui.
+    #[serde(default)]
+    pub cpi_basket: BTreeMap<ItemId, f64>,
+    pub countries: Vec<Country>,
+    #[serde(default)]
+    pub central_banks: Vec<CentralBankRecord>,
+    pub states: Vec<StateRecord>,
+    pub cities: Vec<CityRecord>,
+    #[serde(default)]
+    pub households: Vec<HouseholdRecord>,
+    pub persons: Vec<PersonRecord>,
+    pub shops: Vec<ShopRecord>,
+    #[serde(default)]
+    pub wholesalers: Vec<WholesalerRecord>,
+    #[serde(default)]
+    pub banks: Vec<BankRecord>,
+    #[serde(default)]
+    pub plots: Vec<PlotRecord>,
+    #[serde(default)]
+    pub plants: Vec<PlantRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StateRecord {
+    pub state: State,
+    pub country: Option<usize>,
+    #[serde(default)]
+    pub weather: Weather,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CityRecord {
+    pub city: City,
+    pub state: Option<usize>,
+    /// Último leilão de cada item na cidade.
+    #[serde(default)]
+    pub quotes: BTreeMap<ItemId, Quote>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PersonRecord {
+    pub person: Person,
+    pub alive: bool,
+    pub city: Option<usize>,
+    #[serde(default)]
+    pub employment: Option<EmploymentRecord>,
+    #[serde(default)]
+    pub job_seeker: bool,
+    /// Pais e filhos, como índices em `persons`.
+    #[serde(default)]
+    pub parents: Vec<usize>,
+    #[serde(default)]
+    pub children: Vec<usize>,
+    #[serde(default)]
+    pub spouse: Option<usize>,
+    /// Casa, como índice em `households`.
+    #[serde(default)]
+    pub household: Option<usize>,
+}
+
+/// Casa, com os membros como índices em `persons`, na ordem da casa.
+#[derive(Serialize, Deserialize)]
+pub struct HouseholdRecord {
+    pub household: Household,
+    pub city: Option<usize>,
+    pub members: Vec<usize>,
+    #[serde(default)]
+    pub credit: Credit,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShopRecord {
+    pub shop: Shop,
+    pub city: Option<usize>,
+    #[serde(default)]
+    pub employer: Option<Employer>,
+    #[serde(default)]
+    pub credit: Credit,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BankRecord {
+    pub bank: Bank,
+    pub city: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CentralBankRecord {
+    pub bank: CentralBank,
+    pub country: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WholesalerRecord {
+    pub wholesaler: Wholesaler,
+    pub city: Option<usize>,
+    #[serde(default)]
+    pub employer: Option<Employer>,
+}
+
+/// Lote de cultivo, com o dono e quem planta nele como índices em `persons`.
+#[derive(Serialize, Deserialize)]
+pub struct PlotRecord {
+    pub plot: Plot,
+    pub city: Option<usize>,
+    pub owner: Option<usize>,
+    pub farmer: Option<usize>,
+    /// Banco que tomou o lote de um devedor, como índice em `banks`.
+    #[serde(default)]
+    pub bank: Option<usize>,
+    /// Cidade que herdou o lote de quem morreu sem herdeiros, como índice em
+    /// `cities`.
+    #[serde(default)]
+    pub city_owner: Option<usize>,
+}
+
+/// Planta semeada, com o lote como índice em `plots`.
+#[derive(Serialize, Deserialize)]
+pub struct PlantRecord {
+    pub plant: Plant,
+    pub plot: Option<usize>,
+}
+
+/// Empregador de uma Person, como índice em `shops` ou `wholesalers`.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub enum EmployerRef {
+    Shop(usize),
+    Wholesaler(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EmploymentRecord {
+    pub employer: EmployerRef,
+    pub worked_secs: f32,
+    pub accrued: f32,
+    #[serde(default)]
+    pub withheld: [f32; 3],
+}
+
+/// Snapshot passado por `--load`; quando presente, substitui `setup`.
+#[derive(Resource)]
+pub struct LoadedSnapshot(pub WorldSnapshot);
+
+/// Arquivo onde o mundo é gravado quando o App encerra (`--save`).
+#[derive(Resource)]
+pub struct SaveSnapshotOnExit(pub PathBuf);
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Format {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io { path, source } => {
+                write!(
+                    f,
+                    "could not access snapshot {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            SnapshotError::Format { path, message } => {
+                write!(f, "invalid snapshot {}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl WorldSnapshot {
+    /// Copia o estado atual do mundo.
+    pub fn capture(world: &mut World) -> Self {
+        let mut country_index = bevy::utils::HashMap::new();
+        let mut countries = Vec::new();
+        let mut prices = Vec::new();
+        let market = world.resource::<MarketPrices>().clone();
+        for (entity, country) in world.query::<(Entity, &Country)>().iter(world) {
+            country_index.insert(entity, countries.len());
+            countries.push(country.clone());
+            prices.push(market.country(entity).clone());
+        }
+        let central_banks = world
+            .query::<(&CentralBank, Option<&Parent>)>()
+            .iter(world)
+            .map(|(bank, parent)| CentralBankRecord {
+                bank: bank.clone(),
+                country: parent.and_then(|p| country_index.get(&p.get()).copied()),
+            })
+            .collect();
+
+        let mut state_index = bevy::utils::HashMap::new();
+        let mut states = Vec::new();
+        for (entity, state, weather, parent) in world
+            .query::<(Entity, &State, &Weather, Option<&Parent>)>()
+            .iter(world)
+        {
+            state_index.insert(entity, states.len());
+            states.push(StateRecord {
+                state: state.clone(),
+                country: parent.and_then(|p| country_index.get(&p.get()).copied()),
+                weather: *weather,
+            });
+        }
+
+        let mut city_index = bevy::utils::HashMap::new();
+        let mut cities = Vec::new();
+        let markets = world.resource::<CityMarkets>().quotes.clone();
+        for (entity, city, parent) in world
+            .query::<(Entity, &City, Option<&Parent>)>()
+            .iter(world)
+        {
+            city_index.insert(entity, cities.len());
+            cities.push(CityRecord {
+                city: city.clone(),
+                state: parent.and_then(|p| state_index.get(&p.get()).copied()),
+                quotes: markets.get(&entity).cloned().unwrap_or_default(),
+            });
+        }
+
+        // Lojas na ordem de `City.shops`, que a restauração refaz
+        let shop_order: bevy::utils::HashMap<Entity, usize> = world
+            .query::<&City>()
+            .iter(world)
+            .flat_map(|city| city.shops.iter().copied().enumerate())
+            .map(|(i, shop)| (shop, i))
+            .collect();
+        let mut employer_index = bevy::utils::HashMap::new();
+        let mut shop_entities: Vec<Entity> = world
+            .query_filtered::<Entity, With<Shop>>()
+            .iter(world)
+            .collect();
+        shop_entities.sort_by_key(|shop| shop_order.get(shop).copied().unwrap_or(usize::MAX));
+        let mut shops = Vec::new();
+        for entity in shop_entities {
+            let (shop, employer, credit, parent) = world
+                .query::<(&Shop, Option<&Employer>, Option<&Credit>, Option<&Parent>)>()
+                .get(world, entity)
+                .unwrap();
+            employer_index.insert(entity, EmployerRef::Shop(shops.len()));
+            shops.push(ShopRecord {
+                shop: shop.clone(),
+                city: parent.and_then(|p| city_index.get(&p.get()).copied()),
+                employer: employer.cloned(),
+                credit: credit.cloned().unwrap_or_default(),
+            });
+        }
+
+        let mut wholesalers = Vec::new();
+        for (entity, wholesaler, employer, parent) in world
+            .query::<(Entity, &Wholesaler, Option<&Employer>, Option<&Parent>)>()
+            .iter(world)
+        {
+            employer_index.insert(entity, EmployerRef::Wholesaler(wholesalers.len()));
+            wholesalers.push(WholesalerRecord {
+                wholesaler: wholesaler.clone(),
+                city: parent.and_then(|p| city_index.get(&p.get()).copied()),
+                employer: employer.cloned(),
+            });
+        }
+
+        let mut household_index = bevy::utils::HashMap::new();
+        let mut households = Vec::new();
+        let mut members = Vec::new();
+        for (entity, household, credit, parent) in world
+            .query::<(Entity, &Household, Option<&Credit>, Option<&Parent>)>()
+            .iter(world)
+        {
+            household_index.insert(entity, households.len());
+            members.push(household.members.clone());
+            households.push(HouseholdRecord {
+                household: household.clone(),
+                city: parent.and_then(|p| city_index.get(&p.get()).copied()),
+                members: Vec::new(),
+                credit: credit.cloned().unwrap_or_default(),
+            });
+        }
+
+        let mut bank_index = bevy::utils::HashMap::new();
+        let banks = world
+            .query::<(Entity, &Bank, Option<&Parent>)>()
+            .iter(world)
+            .enumerate()
+            .map(|(i, (entity, bank, parent))| {
+                bank_index.insert(entity, i);
+                BankRecord {
+                    bank: bank.clone(),
+                    city: parent.and_then(|p| city_index.get(&p.get()).copied()),
+                }
+            })
+            .collect();
+
+        let mut person_index = bevy::utils::HashMap::new();
+        let mut persons = Vec::new();
+        let mut families = Vec::new();
+        for (entity, person, alive, parent, employment, job_seeker, family) in world
+            .query::<(
+                Entity,
+                &Person,
+                &Alive,
+                Option<&Parent>,
+                &Employment,
+                &JobSeeker,
+                &Family,
+            )>()
+            .iter(world)
+        {
+            families.push(family.clone());
+            person_index.insert(entity, persons.len());
+            persons.push(PersonRecord {
+                person: person.clone(),
+                alive: alive.0,
+                city: parent.and_then(|p| city_index.get(&p.get()).copied()),
+                employment: employment.0.as_ref().and_then(|job| {
+                    Some(EmploymentRecord {
+                        employer: *employer_index.get(&job.employer)?,
+                        worked_secs: job.worked_secs,
+                        accrued: job.accrued,
+                        withheld: job.withheld,
+                    })
+                }),
+                job_seeker: job_seeker.0,
+                parents: Vec::new(),
+                children: Vec::new(),
+                spouse: None,
+                household: None,
+            });
+        }
+        // Parentes que já morreram ficam de fora
+        let indices = |relatives: &[Entity]| -> Vec<usize> {
+            relatives
+                .iter()
+                .filter_map(|relative| person_index.get(relative).copied())
+                .collect()
+        };
+        for (record, family) in persons.iter_mut().zip(&families) {
+            record.parents = indices(&family.parents);
+            record.children = indices(&family.children);
+            record.spouse = family
+                .spouse
+                .and_then(|spouse| person_index.get(&spouse).copied());
+            record.household = family
+                .household
+                .and_then(|household| household_index.get(&household).copied());
+        }
+        for (record, members) in households.iter_mut().zip(&members) {
+            record.members = indices(members);
+        }
+
+        let mut plot_index = bevy::utils::HashMap::new();
+        let plots = world
+            .query::<(Entity, &Plot, Option<&Parent>)>()
+            .iter(world)
+            .enumerate()
+            .map(|(i, (entity, plot, parent))| {
+                plot_index.insert(entity, i);
+                PlotRecord {
+                    plot: plot.clone(),
+                    city: parent.and_then(|p| city_index.get(&p.get()).copied()),
+                    owner: plot
+                        .owner
+                        .and_then(|owner| person_index.get(&owner).copied()),
+                    farmer: plot
+                        .farmer
+                        .and_then(|farmer| person_index.get(&farmer).copied()),
+                    bank: plot.owner.and_then(|owner| bank_index.get(&owner).copied()),
+                    city_owner: plot.owner.and_then(|owner| city_index.get(&owner).copied()),
+                }
+            })
+            .collect();
+
+        let plants = world
+            .query::<(&Plant, Option<&Parent>)>()
+            .iter(world)
+            .map(|(plant, parent)| PlantRecord {
+                plant: plant.clone(),
+                plot: parent.and_then(|p| plot_index.get(&p.get()).copied()),
+            })
+            .collect();
+
+        Self {
+            config: world.resource::<ScenarioConfig>().clone(),
+            tick: world.resource::<SimClock>().tick,
+            rng: world.resource::<SimRng>().clone(),
+            catalog: world.resource::<ItemCatalog>().clone(),
+            prices,
+            cpi_basket: world.resource::<Indicators>().basket.clone(),
+            countries,
+            central_banks,
+            states,
+            cities,
+            households,
+            persons,
+            shops,
+            wholesalers,
+            banks,
+            plots,
+            plants,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let text = fs::read_to_string(path).map_err(|source| SnapshotError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        ron::from_str(&text).map_err(|e| SnapshotError::Format {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        let text =
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| {
+                SnapshotError::Format {
+                    path: path.to_path_buf(),
+                    message: e.to_string(),
+                }
+            })?;
+        fs::write(path, text).map_err(|source| SnapshotError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Recria as entidades do snapshot, remapeando os índices para as novas
+    /// entidades e reconstruindo a hierarquia e os vetores de `City`. O
+    /// diário do `Ledger` não vai no snapshot: o ouro de cada agente volta
+    /// como lançamento de abertura.
+    pub fn restore(self, world: &mut World) {
+        world.resource_mut::<SimClock>().tick = self.tick;
+        world.insert_resource(self.rng);
+        world.insert_resource(self.catalog);
+        world.resource_mut::<Indicators>().basket = self.cpi_basket;
+
+        let mut countries = Vec::new();
+        let mut issuer = bevy::utils::HashMap::new();
+        for country in self.countries {
+            let treasury = country.treasury;
+            let currency = country.currency.name.clone();
+            let entity = world.spawn(country).id();
+            opening(world, entity, treasury);
+            issuer.insert(currency, entity);
+            countries.push(entity);
+        }
+        for record in self.central_banks {
+            let entity = world.spawn(record.bank).id();
+            if let Some(&country) = record.country.and_then(|i| countries.get(i)) {
+                world.entity_mut(country).add_child(entity);
+            }
+        }
+
+        let mut states = Vec::new();
+        for record in self.states {
+            let treasury = record.state.treasury;
+            let entity = world.spawn((record.state, record.weather)).id();
+            opening(world, entity, treasury);
+            if let Some(&country) = record.country.and_then(|i| countries.get(i)) {
+                world.entity_mut(country).add_child(entity);
+                world
+                    .get_mut::<Country>(country)
+                    .unwrap()
+                    .estates
+                    .push(entity);
+            }
+            states.push(entity);
+        }
+
+        let mut cities = Vec::new();
+        for record in self.cities {
+            let treasury = record.city.treasury;
+            let entity = world.spawn(record.city).id();
+            opening(world, entity, treasury);
+            if !record.quotes.is_empty() {
+                world
+                    .resource_mut::<CityMarkets>()
+                    .quotes
+                    .insert(entity, record.quotes);
+            }
+            if let Some(&state) = record.state.and_then(|i| states.get(i)) {
+                world.entity_mut(state).add_child(entity);
+                world.get_mut::<State>(state).unwrap().cities.push(entity);
+            }
+            cities.push(entity);
+        }
+        let mut country_by_city = bevy::utils::HashMap::new();
+        for &country in &countries {
+            for state in &world.get::<Country>(country).unwrap().estates {
+                for &city in &world.get::<State>(*state).unwrap().cities {
+                    country_by_city.insert(city, country);
+                }
+            }
+        }
+        world.insert_resource(MarketPrices::new(
+            countries.iter().copied().zip(self.prices).collect(),
+            country_by_city,
+        ));
+
+        // Lojas e atacadistas antes das Persons, que apontam para os
+        // empregadores
+        let mut shops = Vec::new();
+        for record in self.shops {
+            let cash = record.shop.cash;
+            let mut entity = world.spawn((record.shop, record.credit));
+            if let Some(employer) = record.employer {
+                entity.insert(employer);
+            }
+            let entity = entity.id();
+            opening(world, entity, cash);
+            if let Some(&city) = record.city.and_then(|i| cities.get(i)) {
+                world.entity_mut(city).add_child(entity);
+                world.get_mut::<City>(city).unwrap().shops.push(entity);
+            }
+            shops.push(entity);
+        }
+
+        let mut wholesalers = Vec::new();
+        for record in self.wholesalers {
+            let cash = record.wholesaler.cash;
+            let mut entity = world.spawn(record.wholesaler);
+            if let Some(employer) = record.employer {
+                entity.insert(employer);
+            }
+            let entity = entity.id();
+            opening(world, entity, cash);
+            if let Some(&city) = record.city.and_then(|i| cities.get(i)) {
+                world.entity_mut(city).add_child(entity);
+            }
+            wholesalers.push(entity);
+        }
+
+        let mut banks = Vec::with_capacity(self.banks.len());
+        for record in self.banks {
+            let cash = record.bank.cash;
+            let foreign: Vec<(Entity, usize)> = record
+                .bank
+                .foreign
+                .iter()
+                .filter_map(|(currency, &amount)| Some((*issuer.get(currency)?, amount)))
+                .collect();
+            let entity = world.spawn(record.bank).id();
+            opening(world, entity, cash);
+            for (country, amount) in foreign {
+                world.send_event(TransferEvent {
+                    from: Account::Outside,
+                    to: Account::Foreign(entity, country),
+                    amount,
+                    kind: TransferKind::Opening,
+                });
+            }
+            if let Some(&city) = record.city.and_then(|i| cities.get(i)) {
+                world.entity_mut(city).add_child(entity);
+            }
+            banks.push(entity);
+        }
+
+        let mut households = Vec::new();
+        let mut members = Vec::new();
+        for record in self.households {
+            let purse = record.household.purse;
+            let entity = world.spawn((record.household, record.credit)).id();
+            opening(world, entity, purse);
+            if let Some(&city) = record.city.and_then(|i| cities.get(i)) {
+                world.entity_mut(city).add_child(entity);
+            }
+            households.push(entity);
+            members.push(record.members);
+        }
+
+        let mut persons = Vec::new();
+        let mut families = Vec::new();
+        for record in self.persons {
+            let gold = record.person.gold + record.person.vouchers;
+            let job = record.employment.and_then(|employment| {
+                let employer = match employment.employer {
+                    EmployerRef::Shop(i) => shops.get(i),
+                    EmployerRef::Wholesaler(i) => wholesalers.get(i),
+                };
+                Some(Job {
+                    employer: *employer?,
+                    worked_secs: employment.worked_secs,
+                    accrued: employment.accrued,
+                    withheld: employment.withheld,
+                })
+            });
+            let entity = world
+                .spawn((
+                    record.person,
+                    Alive(record.alive),
+                    Employment(job),
+                    JobSeeker(record.job_seeker),
+                    Family::default(),
+                ))
+                .id();
+            families.push((
+                record.parents,
+                record.children,
+                record.spouse,
+                record.household,
+            ));
+            opening(world, entity, gold);
+            if let Some(&city) = record.city.and_then(|i| cities.get(i)) {
+                world.entity_mut(city).add_child(entity);
+                world.get_mut::<City>(city).unwrap().persons.push(entity);
+            }
+            persons.push(entity);
+        }
+        let entities = |indices: Vec<usize>| -> Vec<Entity> {
+            indices
+                .into_iter()
+                .filter_map(|i| persons.get(i).copied())
+                .collect()
+        };
+        for (&entity, (parents, children, spouse, household)) in persons.iter().zip(families) {
+            *world.get_mut::<Family>(entity).unwrap() = Family {
+                parents: entities(parents),
+                children: entities(children),
+                spouse: spouse.and_then(|i| persons.get(i)).copied(),
+                household: household.and_then(|i| households.get(i)).copied(),
+            };
+        }
+        for (&entity, members) in households.iter().zip(members) {
+            world.get_mut::<Household>(entity).unwrap().members = entities(members);
+        }
+
+        let mut plots = Vec::with_capacity(self.plots.len());
+        for record in self.plots {
+            let mut plot = record.plot;
+            plot.owner = record
+                .owner
+                .and_then(|i| persons.get(i))
+                .or_else(|| record.bank.and_then(|i| banks.get(i)))
+                .or_else(|| record.city_owner.and_then(|i| cities.get(i)))
+                .copied();
+            plot.farmer = record.farmer.and_then(|i| persons.get(i)).copied();
+            let entity = world.spawn(plot).id();
+            if let Some(&city) = record.city.and_then(|i| cities.get(i)) {
+                world.entity_mut(city).add_child(entity);
+            }
+            plots.push(entity);
+        }
+
+        for record in self.plants {
+            let entity = world.spawn(record.plant).id();
+            if let Some(&plot) = record.plot.and_then(|i| plots.get(i)) {
+                world.entity_mut(plot).add_child(entity);
+            }
+        }
+    }
+}
+
+fn opening(world: &mut World, entity: Entity, amount: usize) {
+    if amount > 0 {
+        world.send_event(TransferEvent {
+            from: Account::Outside,
+            to: Account::Agent(entity),
+            amount,
+            kind: TransferKind::Opening,
+        });
+    }
+}
+
+/// Substitui `setup` quando o App foi iniciado a partir de um snapshot.
+pub fn restore_snapshot_system(world: &mut World) {
+    if let Some(LoadedSnapshot(snapshot)) = world.remove_resource::<LoadedSnapshot>() {
+        snapshot.restore(world);
+    }
+}
+
+/// Grava o snapshot no tick em que o App recebe `AppExit`.
+pub fn save_snapshot_on_exit_system(world: &mut World) {
+    if world.resource::<Events<AppExit>>().is_empty() {
+        return;
+    }
+    let Some(path) = world
+        .get_resource::<SaveSnapshotOnExit>()
+        .map(|target| target.0.clone())
+    else {
+        return;
+    };
+
+    match WorldSnapshot::capture(world).save(&path) {
+        Ok(()) => println!("Snapshot salvo em {}", path.display()),
+        Err(err) => eprintln!("{}", err),
+    }
+}
+
+/// Serializa um `HashMap` como lista de pares, já que as chaves dos mapas dos
+/// componentes não são strings.
+pub mod map_as_vec {
+    use std::hash::{BuildHasher, Hash};
+
+    use bevy::utils::hashbrown::HashMap;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, H, S>(map: &HashMap<K, V, H>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, K, V, H, D>(deserializer: D) -> Result<HashMap<K, V, H>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        H: BuildHasher + Default,
+        D: Deserializer<'de>,
+    {
+        let entries: Vec<(K, V)> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::welfare::WelfarePolicy;
+
+    fn sorted<K: Ord + Copy, V: Clone>(map: &bevy::utils::HashMap<K, V>) -> Vec<(K, V)> {
+        let mut entries: Vec<_> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+        entries.sort_by_key(|(k, _)| *k);
+        entries
+    }
+
+    /// Estado das Persons, lojas, casas, lotes, plantas, bancos e tesouros,
+    /// com as referências entre entidades trocadas pelos nomes, para comparar
+    /// mundos cujas entidades têm ids diferentes.
+    fn describe(world: &mut World) -> Vec<String> {
+        let mut names: bevy::utils::HashMap<Entity, String> = bevy::utils::HashMap::new();
+        for (entity, person) in world.query::<(Entity, &Person)>().iter(world) {
+            names.insert(entity, person.name.clone());
+        }
+        for (entity, household) in world.query::<(Entity, &Household)>().iter(world) {
+            names.insert(entity, household.name.clone());
+        }
+        for (entity, city) in world.query::<(Entity, &City)>().iter(world) {
+            names.insert(entity, city.name.clone());
+        }
+        for (entity, state) in world.query::<(Entity, &State)>().iter(world) {
+            names.insert(entity, state.name.clone());
+        }
+        for (entity, bank) in world.query::<(Entity, &Bank)>().iter(world) {
+            names.insert(entity, bank.name.clone());
+        }
+        let name = |entity: &Entity| names.get(entity).cloned().unwrap_or_default();
+        let list = |entities: &[Entity]| {
+            let mut list: Vec<String> = entities.iter().map(name).collect();
+            list.sort();
+            list
+        };
+
+        let mut lines = Vec::new();
+        for (person, alive, employment, seeker, family, parent) in world
+            .query::<(&Person, &Alive, &Employment, &JobSeeker, &Family, &Parent)>()
+            .iter(world)
+        {
+            let inventory = sorted(&person.inventory);
+            let person = Person {
+                inventory: default(),
+                ..person.clone()
+            };
+            let job = employment
+                .0
+                .as_ref()
+                .map(|job| (job.worked_secs, job.accrued, job.withheld));
+            lines.push(format!(
+                "person {:?} {:?} {} {:?} {:?} {} {:?} {:?} {:?} {:?}",
+                person,
+                inventory,
+                alive.0,
+                job,
+                seeker,
+                name(&parent.get()),
+                list(&family.parents),
+                list(&family.children),
+                family.spouse.as_ref().map(name),
+                family.household.as_ref().map(name),
+            ));
+        }
+        for (shop, employer, credit, parent) in world
+            .query::<(&Shop, &Employer, &Credit, &Parent)>()
+            .iter(world)
+        {
+            let items: Vec<_> = sorted(&shop.items)
+                .into_iter()
+                .map(|(item, details)| (item, details.price, details.stock, details.transactions))
+                .collect();
+            lines.push(format!(
+                "shop {} {:?} {} {:?} {:?} {:?} {:?}",
+                name(&parent.get()),
+                items,
+                shop.cash,
+                shop.position,
+                sorted(&shop.price_history),
+                employer,
+                credit
+            ));
+        }
+        for (household, credit, parent) in
+            world.query::<(&Household, &Credit, &Parent)>().iter(world)
+        {
+            lines.push(format!(
+                "household {} {} {:?} {} {:?} {:?}",
+                household.name,
+                name(&parent.get()),
+                household.members.iter().map(name).collect::<Vec<_>>(),
+                household.purse,
+                household.pantry,
+                credit
+            ));
+        }
+        for (plot, parent, children) in world
+            .query::<(&Plot, &Parent, Option<&Children>)>()
+            .iter(world)
+        {
+            lines.push(format!(
+                "plot {} {:?} {:?} {:?} {} {:?} {}",
+                name(&parent.get()),
+                plot.terrain,
+                plot.fertility,
+                plot.crop,
+                plot.sharecropped,
+                (
+                    plot.owner.as_ref().map(name),
+                    plot.farmer.as_ref().map(name)
+                ),
+                children.map_or(0, |children| children.len())
+            ));
+        }
+        for (plant, parent) in world.query::<(&Plant, &Parent)>().iter(world) {
+            let owner = world.get::<Plot>(parent.get()).and_then(|plot| plot.owner);
+            lines.push(format!("plant {:?} {:?}", plant, owner.as_ref().map(name)));
+        }
+        for (bank, parent) in world.query::<(&Bank, &Parent)>().iter(world) {
+            lines.push(format!("bank {:?} {}", bank, name(&parent.get())));
+        }
+        for (city, parent) in world.query::<(&City, &Parent)>().iter(world) {
+            lines.push(format!(
+                "city {} {} {} {:?} {:?} {:?} {:?}",
+                city.name,
+                name(&parent.get()),
+                city.treasury,
+                list(&city.persons),
+                city.shops.len(),
+                city.taxes,
+                city.welfare
+            ));
+        }
+        for state in world.query::<&State>().iter(world) {
+            lines.push(format!(
+                "state {} {} {:?} {:?} {:?}",
+                state.name,
+                state.treasury,
+                list(&state.cities),
+                state.taxes,
+                state.welfare
+            ));
+        }
+        for country in world.query::<&Country>().iter(world) {
+            lines.push(format!(
+                "country {} {} {:?} {:?} {:?} {:?}",
+                country.name,
+                country.treasury,
+                list(&country.estates),
+                country.currency,
+                country.taxes,
+                country.welfare
+            ));
+        }
+        lines.sort();
+        lines
+    }
+
+    #[test]
+    fn restored_world_matches_the_saved_one_and_runs_the_same() {
+        let config = ScenarioConfig {
+            seed: Some(7),
+            num_persons: 200,
+            num_shops: 10,
+            // Dias curtos, para que juros, impostos e benefícios já tenham
+            // corrido
+            day_secs: 30.0,
+            taxes: crate::taxes::TaxConfig {
+                city: crate::taxes::TaxRates {
+                    sales: 0.05,
+                    income: 0.1,
+                    land: 0.5,
+                    ..default()
+                },
+                ..default()
+            },
+            welfare: crate::welfare::WelfareConfig {
+                city: WelfarePolicy {
+                    food_stamps: 2,
+                    unemployment: 1,
+                    ..default()
+                },
+                ..default()
+            },
+            ..default()
+        };
+        let mut original = crate::build_app(config.clone());
+        for _ in 0..1200 {
+            original.update();
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("economy-snapshot-{}.ron", std::process::id()));
+        WorldSnapshot::capture(original.world_mut())
+            .save(&path)
+            .unwrap();
+        let load = || WorldSnapshot::load(&path).unwrap();
+
+        // Logo depois de restaurar, o mundo é o mesmo
+        let expected = describe(original.world_mut());
+        assert!(expected.iter().any(|line| line.starts_with("plant ")));
+        assert!(expected
+            .iter()
+            .any(|line| line.starts_with("plot ") && line.contains("(Some(")));
+        let mut restored = crate::build_app(config.clone());
+        load().restore(restored.world_mut());
+        assert_eq!(describe(restored.world_mut()), expected);
+
+        // E, com a mesma semente, continua o mesmo nos ticks seguintes
+        let mut resumed = crate::build_app(load().config);
+        resumed.insert_resource(LoadedSnapshot(load()));
+        fs::remove_file(&path).unwrap();
+        for tick in 0..600 {
+            original.update();
+            resumed.update();
+            if tick % 200 == 0 {
+                assert_eq!(
+                    describe(resumed.world_mut()),
+                    describe(original.world_mut()),
+                    "after {} ticks",
+                    tick + 1
+                );
+            }
+        }
+        assert_eq!(
+            describe(resumed.world_mut()),
+            describe(original.world_mut())
+        );
+    }
+}
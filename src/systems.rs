@@ -1,35 +1,80 @@
-use bevy::{prelude::*, utils::HashMap};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 
 use rand::seq::IndexedRandom;
-use rand::seq::SliceRandom;
-use rand::{self, Rng};
+use rand::Rng;
 
+use crate::banks::{self, Bank, Credit};
+use crate::calendar::Calendar;
+use crate::clock::SimClock;
 use crate::components::{
-    default_apple, Alive, City, Country, State, Item, ItemDetails, ItemType, Person,
-    PersonActions, PersonState, Position, PriceRecord, Shop, TerrainType,
+    Alive, City, Country, Employer, Employment, Family, GrowthStage, Household, JobSeeker, Person,
+    PersonActions, PersonState, Plant, Plot, Position, PriceRecord, Shop, State, TerrainType,
+    Wholesaler,
 };
-use crate::constants::*;
-
-pub fn setup(mut commands: Commands) {
-    // Use the ThreadRng alternative since thread_rng() is deprecated.
-    let mut rng = rand::rng();
+use crate::config::ScenarioConfig;
+use crate::demography::{self, DeathCause};
+use crate::events::{ConsumptionEvent, DeathEvent, LoanEvent, TransferEvent};
+use crate::exchange::{self, Currency};
+use crate::household;
+use crate::indicators::{Indicators, ScopeIndicators};
+use crate::items::{ItemCatalog, ItemId, MarketPrices};
+use crate::ledger::{Account, Ledger, TransferKind};
+use crate::market::CityMarkets;
+use crate::monetary::CentralBank;
+use crate::rng::SimRng;
+use crate::weather::{self, Weather};
+
+pub fn setup(
+    mut commands: Commands,
+    config: Res<ScenarioConfig>,
+    catalog: Res<ItemCatalog>,
+    mut rng: ResMut<SimRng>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    // Todo o ouro inicial entra na economia por um lançamento de abertura
+    let mut opening = |entity: Entity, amount: usize| {
+        if amount > 0 {
+            transfers.send(TransferEvent {
+                from: Account::Outside,
+                to: Account::Agent(entity),
+                amount,
+                kind: TransferKind::Opening,
+            });
+        }
+    };
 
+    let mut countries_map: HashMap<Entity, Country> = HashMap::new();
     let mut countries: Vec<Entity> = Vec::new();
 
     // Create Countries
-    for i in 0..NUM_COUNTRIES {
-        let country_entity = commands
-            .spawn(Country {
-                name: format!("Country {}", i),
+    for i in 0..config.num_countries {
+        let country = Country {
+            name: format!("Country {}", i),
+            currency: Currency {
+                name: format!("Currency {}", i),
                 ..default()
-            })
-            .id();
+            },
+            taxes: config.taxes.country,
+            welfare: config.welfare.country,
+            ..default()
+        };
+        let country_entity = commands.spawn(country.clone()).id();
+        // Com o seu banco central
+        let name = format!("Central Bank {}", i);
+        let central_bank = commands.spawn(CentralBank { name, ..default() }).id();
+        commands.entity(country_entity).add_child(central_bank);
         countries.push(country_entity);
+        countries_map.insert(country_entity, country);
     }
 
     // Create States (formerly Estates) and assign each to a random Country
+    let mut states_map: HashMap<Entity, State> = HashMap::new();
     let mut states: Vec<Entity> = Vec::new();
-    for i in 0..NUM_STATES {
+    let mut terrains: HashMap<Entity, TerrainType> = HashMap::new();
+    for i in 0..config.num_states {
         let terrain_type = match i % 4 {
             0 => TerrainType::Grassland,
             1 => TerrainType::Forest,
@@ -37,46 +82,105 @@ pub fn setup(mut commands: Commands) {
             _ => TerrainType::Desert,
         };
 
-        let state_entity = commands
-            .spawn(State {
-                name: format!("State {}", i),
-                terrain_type,
-                ..default()
-            })
-            .id();
+        let state = State {
+            name: format!("State {}", i),
+            terrain_type,
+            taxes: config.taxes.state,
+            welfare: config.welfare.state,
+            ..default()
+        };
+        let state_entity = commands.spawn((state.clone(), Weather::default())).id();
 
         // Randomly assign this state to one of the countries
-        if let Some(&country_entity) = countries.choose(&mut rng) {
+        if let Some(&country_entity) = countries.choose(&mut rng.0) {
             commands.entity(country_entity).add_child(state_entity);
+            if let Some(country) = countries_map.get_mut(&country_entity) {
+                country.estates.push(state_entity);
+            }
         }
         states.push(state_entity);
+        states_map.insert(state_entity, state);
+        terrains.insert(state_entity, terrain_type);
     }
 
 
     // Create the cities and also store their components in the map
     let mut cities_map: HashMap<Entity, City> = HashMap::new();
     let mut cities: Vec<Entity> = Vec::new();
-    for i in 0..NUM_CITIES {
+    for i in 0..config.num_cities {
         let city = City {
             name: format!("City {}", i),
             position: Position {
                 x: rng.random_range(0.0..200.0),
                 y: rng.random_range(0.0..200.0),
             },
+            taxes: config.taxes.city,
+            welfare: config.welfare.city,
             ..default()
         };
         let city_entity = commands.spawn(city.clone()).id();
+        // Cada cidade tem um atacadista que abastece as lojas
+        let wholesaler = commands
+            .spawn((
+                Wholesaler {
+                    cash: config.shops.wholesaler_start_cash,
+                    position: Position {
+                        x: rng.random_range(0.0..100.0),
+                        y: rng.random_range(0.0..100.0),
+                    },
+                },
+                config.labor.employer(
+                    config.shops.wholesaler_start_cash,
+                    config.labor.wholesaler_jobs,
+                ),
+            ))
+            .id();
+        opening(wholesaler, config.shops.wholesaler_start_cash);
+        commands.entity(city_entity).add_child(wholesaler);
+        // E um banco, que começa só com o capital
+        let bank = commands
+            .spawn(Bank {
+                name: format!("Bank {}", i),
+                cash: config.banks.capital,
+                ..default()
+            })
+            .id();
+        opening(bank, config.banks.capital);
+        commands.entity(city_entity).add_child(bank);
         // Update parent's children later (assign to a random state)
-        if let Some(&state) = states.choose(&mut rng) {
+        if let Some(&state) = states.choose(&mut rng.0) {
             commands.entity(state).add_child(city_entity);
+            if let Some(estate) = states_map.get_mut(&state) {
+                estate.cities.push(city_entity);
+            }
+
+            // Os lotes da cidade têm a fertilidade do terreno do estado,
+            // variando de lote para lote
+            let terrain = terrains[&state];
+            let spread = config.land.fertility_spread;
+            for _ in 0..config.land.plots_per_city {
+                let fertility = config.land.fertility.get(terrain)
+                    * rng.random_range(1.0 - spread..=1.0 + spread);
+                let plot = commands
+                    .spawn(Plot {
+                        terrain,
+                        fertility,
+                        owner: None,
+                        farmer: None,
+                        crop: None,
+                        sharecropped: false,
+                    })
+                    .id();
+                commands.entity(city_entity).add_child(plot);
+            }
         }
         cities.push(city_entity);
         cities_map.insert(city_entity, city);
     }
 
     // Randomly distribute Persons among the Cities
-    for i in 0..NUM_PERSONS {
-        if let Some(&city_entity) = cities.choose(&mut rng) {
+    for i in 0..config.num_persons {
+        if let Some(&city_entity) = cities.choose(&mut rng.0) {
             let person_entity = commands
                 .spawn((
                     Person {
@@ -85,98 +189,182 @@ pub fn setup(mut commands: Commands) {
                             x: rng.random_range(0.0..100.0),
                             y: rng.random_range(0.0..100.0),
                         },
-                        gold: START_GOLD,
+                        gold: config.start_gold,
+                        skill: rng.random_range(0.0..1.0),
+                        age: rng.random_range(
+                            config.demography.start_age_min..=config.demography.start_age_max,
+                        ),
                         ..default()
                     },
                     Alive(true),
+                    Employment::default(),
+                    JobSeeker::default(),
+                    Family::default(),
                 ))
                 .id();
+            opening(person_entity, config.start_gold);
 
             // Add person as a child of the city
             commands.entity(city_entity).add_child(person_entity);
 
             // Also update the City component's persons vector
-            if let Some(mut city) = cities_map.get_mut(&city_entity) {
+            if let Some(city) = cities_map.get_mut(&city_entity) {
                 city.persons.push(person_entity);
             }
         }
     }
 
+    // Em cada cidade, as Persons moram sozinhas ou em casal
+    let mut households = 0;
+    for city_entity in &cities {
+        let Some(city) = cities_map.get(city_entity) else {
+            continue;
+        };
+        let mut persons = city.persons.iter().copied();
+        while let Some(person) = persons.next() {
+            let mut members = vec![person];
+            if rng.random_bool(config.households.couple_share as f64) {
+                members.extend(persons.next());
+            }
+            let household = commands
+                .spawn((
+                    Household {
+                        name: format!("Household {}", households),
+                        members: members.clone(),
+                        ..default()
+                    },
+                    Credit::default(),
+                ))
+                .id();
+            households += 1;
+            commands.entity(*city_entity).add_child(household);
+            for &member in &members {
+                let spouse = members.iter().copied().find(|&other| other != member);
+                commands.entity(member).insert(Family {
+                    spouse,
+                    household: Some(household),
+                    ..default()
+                });
+            }
+        }
+    }
+
     // Randomly distribute Shops among the Cities
-    for _ in 0..NUM_SHOPS {
-        if let Some(&city_entity) = cities.choose(&mut rng) {
-            // Prepare the items for the shop (for example, the default apple)
-            let mut items = HashMap::new();
-            let mut price_history = HashMap::new();
-
-            let apple = default_apple();
-            let apple_details = ItemDetails {
-                price: 10,
-                stock: 10,
-                transactions: (0, 0),
+    for _ in 0..config.num_shops {
+        if let Some(&city_entity) = cities.choose(&mut rng.0) {
+            let mut shop = Shop {
+                cash: config.shops.start_cash,
+                position: Position {
+                    x: rng.random_range(0.0..100.0),
+                    y: rng.random_range(0.0..100.0),
+                },
+                ..default()
             };
-
-            items.insert(apple.clone(), apple_details);
-            price_history.insert(
-                apple,
-                vec![PriceRecord {
-                    timestamp: 0.0,
-                    price: 10,
-                }],
-            );
+            // A loja vende todas as comidas e, com 50% de chance, cada um dos
+            // demais itens, com o preço inicial variando 20% em torno do preço base
+            for item in catalog.iter() {
+                let is_food = catalog.nutritional_value(item.id).is_some();
+                if is_food || rng.random_bool(0.5) {
+                    let price = (item.base_price as f32 * rng.random_range(0.8..1.2))
+                        .round()
+                        .max(1.0) as usize;
+                    shop.stock_item(item.id, price, item.initial_stock);
+                }
+            }
 
             let shop_entity = commands
-                .spawn(Shop {
-                    items,
-                    position: Position {
-                        x: rng.random_range(0.0..100.0),
-                        y: rng.random_range(0.0..100.0),
-                    },
-                    price_history,
-                })
+                .spawn((
+                    shop,
+                    config
+                        .labor
+                        .employer(config.shops.start_cash, config.labor.shop_jobs),
+                    Credit::default(),
+                ))
                 .id();
+            opening(shop_entity, config.shops.start_cash);
 
             // Add shop as a child of the city
             commands.entity(city_entity).add_child(shop_entity);
 
             // Also update the City component's shops vector
-            if let Some(mut city) = cities_map.get_mut(&city_entity) {
+            if let Some(city) = cities_map.get_mut(&city_entity) {
                 city.shops.push(shop_entity);
             }
         }
     }
+
+    // Só agora os vetores de cada City estão completos, assim como os de
+    // cada State e Country
+    for city_entity in cities {
+        if let Some(city) = cities_map.remove(&city_entity) {
+            commands.entity(city_entity).insert(city);
+        }
+    }
+    for state_entity in states {
+        if let Some(state) = states_map.remove(&state_entity) {
+            commands.entity(state_entity).insert(state);
+        }
+    }
+    for country_entity in countries {
+        if let Some(country) = countries_map.remove(&country_entity) {
+            commands.entity(country_entity).insert(country);
+        }
+    }
 }
 
 // --- Sistema de Fome ---
 // Atualiza o estado da Person para Hungry se a saciedade (hunger) estiver baixa.
-pub fn hunger_system(mut persons: Query<(&mut Person, &mut Alive), With<Person>>, time: Res<Time>) {
-    for (mut person, mut alive) in persons.iter_mut() {
+pub fn hunger_system(
+    mut persons: Query<(&mut Person, &mut Alive, Option<&Parent>), With<Person>>,
+    cities: Query<(Entity, &Parent), With<City>>,
+    states: Query<&Weather>,
+    clock: Res<SimClock>,
+    calendar: Res<Calendar>,
+    config: Res<ScenarioConfig>,
+    mut deaths: EventWriter<DeathEvent>,
+) {
+    // No inverno a fome aperta mais rápido, e o tempo de cada estado também pesa
+    let hunger_rate = 2.0 * config.calendar.hunger.get(calendar.season());
+    let weather = weather::city_effects(&cities, &states, &config.weather);
+    for (mut person, mut alive, city) in persons.iter_mut() {
+        let effects = city
+            .and_then(|city| weather.get(&city.get()).copied())
+            .unwrap_or_default();
         // A saciedade diminui com o passar do tempo
-        person.hunger -= 2.0 * time.delta_secs();
+        person.hunger -= hunger_rate * effects.hunger * clock.delta_secs();
         if person.hunger < 0.0 {
             person.hunger = 0.0;
         }
 
         // Pessoa perde saúde se faminta
         if person.hunger <= 0.0 {
-            person.health -= 1.0 * time.delta_secs();
+            person.health -= 1.0 * clock.delta_secs();
             if person.health < 0.0 {
                 person.health = 0.0;
+                if let Some(city) = city.filter(|_| alive.0) {
+                    deaths.send(DeathEvent {
+                        city: city.get(),
+                        cause: DeathCause::Starvation,
+                    });
+                }
                 alive.0 = false;
             }
         } else {
             // A saude aumenta com o passar do tempo se saciado
-            person.health += 1.0 * time.delta_secs();
+            person.health += 1.0 * clock.delta_secs();
             if person.health > 100.0 {
                 person.health = 100.0;
             }
         }
 
         // Estado da pessoa muda para com fome se abaixo do limite
-        person.state = match person.hunger {
-            ..PERSON_HUNGRY_THRESHOLD => PersonState::Hungry,
-            PERSON_HUNGRY_THRESHOLD..100.0 => PersonState::Healthy,
-            _ => person.state,
+        let threshold = config.person_hungry_threshold;
+        person.state = if person.hunger < threshold {
+            PersonState::Hungry
+        } else if person.hunger < 100.0 {
+            PersonState::Healthy
+        } else {
+            person.state
         };
     }
 }
@@ -186,19 +374,30 @@ pub fn hunger_system(mut persons: Query<(&mut Person, &mut Alive), With<Person>>
 /// - Em ações que consomem energia (Walking, Buying, Selling), a energia diminui.
 /// - Ao repor energia, se a pessoa estiver em ação, a taxa de decréscimo da fome aumenta.
 /// - Se a fome estiver zerada, a energia se repõe mais rápido, mas utilizando a saúde.
-pub fn energy_system(mut query: Query<&mut Person>, time: Res<Time>) {
-    for mut person in query.iter_mut() {
+/// - O tempo do estado acelera ou freia a reposição, como numa onda de calor.
+pub fn energy_system(
+    mut query: Query<(&mut Person, Option<&Parent>)>,
+    cities: Query<(Entity, &Parent), With<City>>,
+    states: Query<&Weather>,
+    clock: Res<SimClock>,
+    config: Res<ScenarioConfig>,
+) {
+    let weather = weather::city_effects(&cities, &states, &config.weather);
+    for (mut person, city) in query.iter_mut() {
+        let rate = city
+            .and_then(|city| weather.get(&city.get()))
+            .map_or(1.0, |effects| effects.energy);
         match person.state {
             PersonState::Healthy => {
                 // Sem ação: energia se recupera lentamente.
-                person.energy += 1.0 * time.delta_secs();
+                person.energy += 1.0 * rate * clock.delta_secs();
             }
 
             PersonState::Hungry => {
                 // Se energia não estiver completa, há uma reposição com efeito colateral na fome.
                 if person.hunger == 0.0 {
                     // Se a saciedade estiver zerada, a energia é reposta mais rápido, mas consumindo saúde.
-                    let energy_boost = 10.0 * time.delta_secs();
+                    let energy_boost = 10.0 * rate * clock.delta_secs();
                     person.energy += energy_boost;
                     person.health -= energy_boost * 0.5;
                     if person.health < 0.0 {
@@ -206,7 +405,7 @@ pub fn energy_system(mut query: Query<&mut Person>, time: Res<Time>) {
                     }
                 } else {
                     // Se estiver com fome mas não estiver faminto recupera energia mais lentamente
-                    person.energy += 0.7 * time.delta_secs();
+                    person.energy += 0.7 * rate * clock.delta_secs();
                 }
             }
         }
@@ -218,179 +417,314 @@ pub fn energy_system(mut query: Query<&mut Person>, time: Res<Time>) {
     }
 }
 
-pub fn reasoning_system(mut persons: Query<(&mut Person, &mut Alive), With<Person>>) {
-    // Get the default apple key for inventory lookups
-    let apple_key = default_apple();
+/// Quantidade total de comida (de qualquer tipo) no inventário.
+pub fn food_units(person: &Person, catalog: &ItemCatalog) -> i32 {
+    person
+        .inventory
+        .iter()
+        .filter(|(item, _)| catalog.nutritional_value(**item).is_some())
+        .map(|(_, count)| *count)
+        .sum()
+}
+
+/// Comida do inventário com maior valor nutricional.
+fn best_food_in_inventory(person: &Person, catalog: &ItemCatalog) -> Option<ItemId> {
+    person
+        .inventory
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .filter_map(|(item, _)| catalog.nutritional_value(*item).map(|value| (*item, value)))
+        .max_by_key(|(item, value)| (*value, *item))
+        .map(|(item, _)| item)
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn reasoning_system(
+    mut persons: Query<(
+        Entity,
+        &mut Person,
+        &mut Alive,
+        Option<&Parent>,
+        &Employment,
+        &Family,
+    )>,
+    mut households: Query<&mut Household>,
+    shops: Query<(&Shop, &Parent)>,
+    plots: Query<(&Plot, Option<&Children>)>,
+    plants: Query<&Plant>,
+    employers: Query<(&Employer, &Parent)>,
+    catalog: Res<ItemCatalog>,
+    prices: Res<MarketPrices>,
+    markets: Res<CityMarkets>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    calendar: Res<Calendar>,
+    mut rng: ResMut<SimRng>,
+    mut transfers: EventWriter<TransferEvent>,
+) {
+    // Cidades em que alguma loja ainda tem comida para vender
+    let cities_with_food: HashSet<Entity> = shops
+        .iter()
+        .filter(|(shop, _)| {
+            shop.items.iter().any(|(item, details)| {
+                details.stock > 0 && catalog.nutritional_value(*item).is_some()
+            })
+        })
+        .map(|(_, city)| city.get())
+        .collect();
+
+    // Maior salário entre as vagas abertas de cada cidade
+    let mut open_wages: HashMap<Entity, f32> = HashMap::new();
+    for (employer, city) in employers.iter() {
+        if employer.vacancies > 0 {
+            let best = open_wages.entry(city.get()).or_insert(0.0);
+            *best = best.max(employer.wage);
+        }
+    }
+
+    // A planta no lote de cada lavrador, se já foi semeada
+    let mut fields: HashMap<Entity, Option<&Plant>> = HashMap::new();
+    for (plot, children) in plots.iter() {
+        if let Some(farmer) = plot.farmer {
+            let plant =
+                children.and_then(|children| children.iter().find_map(|&c| plants.get(c).ok()));
+            fields.insert(farmer, plant);
+        }
+    }
 
-    for (mut person, alive) in persons.iter_mut() {
+    // Quanto renderia plantar para vender: só conta se a colheita mais
+    // valiosa no país da cidade foi negociada nela há pouco tempo
+    let recent = clock.ticks_for(config.labor.adjust_secs);
+    let farm_value = |city: Option<&Parent>| {
+        city.and_then(|city| {
+            let crop = prices
+                .city(city.get())
+                .most_valuable_crop(&catalog, calendar.season())?;
+            Some((crop, markets.quote(city.get(), crop)?))
+        })
+        .filter(|(_, quote)| clock.tick.saturating_sub(quote.tick) <= recent)
+        .map_or(0.0, |(crop, quote)| {
+            (quote.price * catalog.get(crop).harvest) as f32
+        })
+    };
+
+    for (entity, mut person, alive, city, employment, family) in persons.iter_mut() {
         // Skip processing for dead persons
         if !alive.0 {
             continue;
         }
 
-        // Retrieve the number of apples in the person's inventory
-        let number_apples = *person.inventory.get(&apple_key).unwrap_or(&0);
-
-        // If the person is healthy, idle, has at least one apple, and gold is less than 30,
-        // change the action to selling.
-        if person.state == PersonState::Healthy
-            && person.action == PersonActions::Idle
-            && number_apples > 0
-            && person.gold < 30
+        // A Person decide pela casa: conta a comida da despensa e a sua parte
+        // do caixa, e recebe da casa o que vai comer ou gastar
+        let household_entity = family.household;
+        let mut household =
+            household_entity.and_then(|household| households.get_mut(household).ok());
+        let own_food = food_units(&person, &catalog);
+        let food = own_food
+            + household
+                .as_ref()
+                .map_or(0, |household| household.pantry_food(&catalog));
+        let gold = person.gold + household.as_ref().map_or(0, |household| household.budget());
+
+        let open_wage = city.and_then(|city| open_wages.get(&city.get()).copied());
+        let farm_value = farm_value(city);
+        let prices = city.map_or(prices.base(), |city| prices.city(city.get()));
+        let field = fields.get(&entity).copied();
+        let plant = field.flatten();
+        // Crianças só comem e compram comida; não trabalham, vendem nem plantam
+        let adult = config.demography.is_adult(person.age);
+
+        // Quem tem uma planta madura vai colhê-la; se o solo secou, vai regá-la
+        if person.action == PersonActions::Idle
+            && plant.is_some_and(|plant| plant.stage == GrowthStage::Ripe)
         {
-            person.action = PersonActions::Selling;
+            person.action = PersonActions::Harvesting;
         }
-
-        // If the person is hungry, idle, and has at least one apple,
-        // change the action to eating.
         if person.action == PersonActions::Idle
-            && person.state == PersonState::Hungry
-            && number_apples > 0
+            && plant.is_some_and(|plant| plant.water < config.crops.tend_below)
         {
-            person.action = PersonActions::Eating;
+            person.action = PersonActions::Tending;
         }
 
-        // If the person is hungry, idle, has no apple, and has more than 30 gold,
-        // change the action to buying.
-        if person.action == PersonActions::Idle
-            && person.state == PersonState::Hungry
-            && number_apples == 0
-            && person.gold > 30
+        // Quem tem emprego e está saciado vai cumprir um turno
+        if person.state == PersonState::Healthy
+            && person.action == PersonActions::Idle
+            && employment.0.is_some()
         {
-            person.action = PersonActions::Buying;
+            person.action = PersonActions::Working;
         }
 
-        // If the person has less than 30 gold, is idle, and has no apple,
-        // change the action to planting.
-        if person.gold < 30 && number_apples == 0 && person.action == PersonActions::Idle {
-            person.action = PersonActions::Planting;
+        // Sem emprego, mas com comida ou ouro para se manter, procura uma vaga
+        // em vez de vender a colheita se o turno rende mais que ela
+        if person.state == PersonState::Healthy
+            && person.action == PersonActions::Idle
+            && adult
+            && employment.0.is_none()
+            && (food > 0 || gold > 30)
+            && open_wage.is_some_and(|wage| config.labor.shift_pay(wage) > farm_value)
+        {
+            person.action = PersonActions::SeekingWork;
         }
 
-        // If the person has more than 30 gold, is idle, and has at least one apple,
-        // then with a 5% chance change the action to planting.
-        if person.gold > 30 && person.action == PersonActions::Idle && number_apples > 0 {
-            let mut rng = rand::rngs::ThreadRng::default();
-            if rng.gen_range(0..100) < 5 {
-                person.action = PersonActions::Planting;
+        // If the person is healthy, idle, has some food, and gold is less than 30,
+        // change the action to selling what the household can spare.
+        if person.state == PersonState::Healthy
+            && person.action == PersonActions::Idle
+            && adult
+            && food > 0
+            && gold < 30
+        {
+            if let Some(household) = household.as_mut() {
+                let surplus = household.take_surplus(&catalog, config.households.pantry_reserve);
+                for (item, count) in surplus {
+                    *person.inventory.entry(item).or_insert(0) += count;
+                }
+            }
+            if food_units(&person, &catalog) > 0 {
+                person.action = PersonActions::Selling;
             }
         }
-    }
-}
 
-// 3. Sistema de Planting: se o Person estiver no estado Planting por mais de 20 segundos consecutivos, ele recebe 10 maçãs.
-pub fn planting_system(mut persons: Query<&mut Person>, time: Res<Time>) {
-    let apple_key = default_apple();
-    for mut person in persons.iter_mut() {
-        if person.action == PersonActions::Planting {
-            person.planting_time += time.delta_secs();
-            if person.planting_time >= 10.0 {
-                // Adiciona 10 maçãs ao inventário do Person usando o apple_key
-                *person.inventory.entry(apple_key.clone()).or_insert(0) += 10;
-                // Reseta o timer e retorna ao estado Idle
-                person.planting_time = 0.0;
-                person.action = PersonActions::Idle;
+        // If the person is hungry, idle, and has some food,
+        // change the action to eating the most nutritious one.
+        if person.action == PersonActions::Idle && person.state == PersonState::Hungry && food > 0 {
+            if own_food == 0 {
+                if let Some(item) = household
+                    .as_mut()
+                    .and_then(|household| household.take_food(&catalog))
+                {
+                    *person.inventory.entry(item).or_insert(0) += 1;
+                }
             }
-        } else {
-            // Se o estado não for Planting, reseta o contador de tempo
-            person.planting_time = 0.0;
+            person.action = PersonActions::Eating;
+            person.target_item = best_food_in_inventory(&person, &catalog);
         }
-    }
-}
-
-// --- Sistema de Interação com a Loja ---
-// Se o estado da Person for Buying, ela tenta comprar uma maçã de um Shop aleatório.
-pub fn shop_interaction_system(mut persons: Query<&mut Person>, mut shops: Query<&mut Shop>) {
-    use rand::Rng;
-    let mut rng = rand::rngs::ThreadRng::default();
-    let shop_count = shops.iter_mut().count();
-    if shop_count == 0 {
-        return;
-    }
 
-    for mut person in persons.iter_mut() {
-        match person.action {
-            PersonActions::Buying => {
-                // Seleciona aleatoriamente um Shop
-                let random_index = rng.random_range(0..shop_count);
-                if let Some(mut shop) = shops.iter_mut().nth(random_index) {
-                    // Obtém a "chave" para o item Apple
-                    let apple_key = default_apple();
-                    if let Some(details) = shop.items.get_mut(&apple_key) {
-                        // Se o Person tiver gold suficiente para comprar o item
-                        if person.gold >= details.price {
-                            if details.stock > 0 {
-                                details.stock -= 1;
-                                person.gold -= details.price;
-                                // Atualiza o inventário do Person
-                                *person.inventory.entry(apple_key.clone()).or_insert(0) += 1;
-                                // Registra a transação de compra (incrementa o contador de compras)
-                                details.transactions.0 += 1;
-                            }
-                        }
-                        person.action = PersonActions::Idle;
-                    }
+        // If the person is hungry, idle, has no food, and has more than 30 gold
+        // (or food stamps, which only buy food), change the action to buying the
+        // food with the best nutrition per gold, as long as someone in the city
+        // still sells food.
+        let budget = if gold > 30 {
+            gold + person.vouchers
+        } else {
+            person.vouchers
+        };
+        if person.action == PersonActions::Idle
+            && person.state == PersonState::Hungry
+            && food == 0
+            && budget > 0
+            && city.is_some_and(|city| cities_with_food.contains(&city.get()))
+        {
+            if let Some(item) = prices.best_food_for(&catalog, budget) {
+                if let (Some(household), Some(household_entity)) =
+                    (household.as_mut(), household_entity)
+                {
+                    let budget = household.budget();
+                    household::allowance(
+                        household,
+                        household_entity,
+                        &mut person,
+                        entity,
+                        budget,
+                        &mut transfers,
+                    );
                 }
+                person.action = PersonActions::Buying;
+                person.target_item = Some(item);
             }
-            PersonActions::Selling => {
-                // Seleciona aleatoriamente um Shop
-                let random_index = rng.gen_range(0..shop_count);
-                if let Some(mut shop) = shops.iter_mut().nth(random_index) {
-                    let apple_key = default_apple();
-                    // Verifica se o Person possui o item "Apple" em seu inventário
-                    if let Some(count) = person.inventory.get_mut(&apple_key) {
-                        if *count > 0 {
-                            let total_items = *count;
-                            // Remove todas as "Apple" do inventário do Person
-                            *count = 0;
-                            if let Some(details) = shop.items.get_mut(&apple_key) {
-                                person.gold += details.price * (total_items as usize);
-                                details.stock += total_items as usize;
-                                // Registra a transação de venda (incrementa o contador de vendas)
-                                details.transactions.1 += total_items as usize;
-                            }
-                            person.action = PersonActions::Idle;
-                        }
-                    }
+        }
+
+        // If the person has up to 30 gold (or is hungry and could not buy any
+        // food), is idle, and has no food, change the action to planting.
+        let sow = if !adult {
+            false
+        } else if (gold <= 30 || person.state == PersonState::Hungry)
+            && food == 0
+            && person.action == PersonActions::Idle
+        {
+            true
+        } else {
+            // If the person has more than 30 gold, is idle, and has some food,
+            // then with a 5% chance change the action to planting.
+            gold > 30
+                && person.action == PersonActions::Idle
+                && food > 0
+                && rng.random_range(0..100) < 5
+        };
+        // Quem já cultiva um lote espera a planta crescer
+        if sow && field.is_none() {
+            // Planta a cultura que hoje vale mais no mercado, com o aluguel
+            // pago pela casa se o lote for alheio
+            if let Some(crop) = prices.most_valuable_crop(&catalog, calendar.season()) {
+                if let (Some(household), Some(household_entity)) =
+                    (household.as_mut(), household_entity)
+                {
+                    household::allowance(
+                        household,
+                        household_entity,
+                        &mut person,
+                        entity,
+                        config.land.rent,
+                        &mut transfers,
+                    );
                 }
+                person.action = PersonActions::Seeding;
+                person.target_item = Some(crop);
             }
-            _ => {}
         }
     }
 }
 
 // --- Sistema de Alimentação ---
-// Se o estado da Person for Eating, ela consome uma maçã para recuperar a saciedade.
-pub fn feeding_system(mut persons: Query<&mut Person>, _time: Res<Time>) {
-    // Obtém a chave padrão para o item Apple.
-    let apple_key = default_apple();
-
+// Se o estado da Person for Eating, ela consome uma unidade da comida escolhida para recuperar a saciedade.
+pub fn feeding_system(
+    mut persons: Query<&mut Person>,
+    catalog: Res<ItemCatalog>,
+    _clock: Res<SimClock>,
+    mut consumption: EventWriter<ConsumptionEvent>,
+) {
     for mut person in persons.iter_mut() {
         if person.action == PersonActions::Eating {
-            if let Some(count) = person.inventory.get_mut(&apple_key) {
-                if *count > 0 {
-                    *count -= 1;
-                    // Extraí o valor nutricional do item Apple.
-                    if let ItemType::Food { nutritional_value } = &apple_key.item_type {
-                        person.hunger += *nutritional_value as f32;
-                    }
-                    if person.hunger > 100.0 {
-                        person.hunger = 100.0;
-                    }
-                    // Após comer, o Person retorna ao estado Idle.
-                    person.action = PersonActions::Idle;
+            let Some(food) = person
+                .target_item
+                .filter(|item| person.inventory.get(item).is_some_and(|count| *count > 0))
+                .or_else(|| best_food_in_inventory(&person, &catalog))
+            else {
+                continue;
+            };
+            if let Some(count) = person.inventory.get_mut(&food) {
+                *count -= 1;
+                person.hunger += catalog.nutritional_value(food).unwrap_or(0) as f32;
+                if person.hunger > 100.0 {
+                    person.hunger = 100.0;
                 }
+                consumption.send(ConsumptionEvent {
+                    item: food,
+                    quantity: 1,
+                });
+                // Após comer, o Person retorna ao estado Idle.
+                person.action = PersonActions::Idle;
+                person.target_item = None;
             }
         }
     }
 }
 
 // Função auxiliar de atualização de preço
-fn update_price(old_price: usize, sales: usize, purchases: usize, stock: i32) -> usize {
+fn update_price(
+    old_price: usize,
+    sales: usize,
+    purchases: usize,
+    stock: i32,
+    transaction_threshold: usize,
+) -> usize {
     let total = sales + purchases;
-    if total < TRANSACTION_THRESHOLD {
+    if total < transaction_threshold {
         return old_price;
     }
-    let ratio = sales as f32 / (purchases as f32 + 1.0);
+    // Limitada porque o leilão manda todos os compradores da cidade para a
+    // loja mais barata, e um lote grande de vendas multiplicaria o preço
+    let ratio = (sales as f32 / (purchases as f32 + 1.0)).min(2.0);
     let mut adjustment_factor = if ratio > 1.0 {
         1.0 + 0.1 * (ratio - 1.0)
     } else {
@@ -406,9 +740,17 @@ fn update_price(old_price: usize, sales: usize, purchases: usize, stock: i32) ->
 }
 
 // Updated price update system
-pub fn price_update_system(mut shops: Query<&mut Shop>, time: Res<Time>) {
-    for mut shop in shops.iter_mut() {
-        let elapsed_secs = time.elapsed_secs();
+pub fn price_update_system(
+    mut shops: Query<(&mut Shop, Option<&Parent>)>,
+    countries: Query<(Entity, &Children), With<Country>>,
+    states: Query<&Children, With<State>>,
+    clock: Res<SimClock>,
+    calendar: Res<Calendar>,
+    config: Res<ScenarioConfig>,
+    mut prices: ResMut<MarketPrices>,
+) {
+    for (mut shop, _) in shops.iter_mut() {
+        let elapsed_secs = clock.elapsed_secs();
         // Vetor temporário para armazenar os itens que terão seu preço atualizado
         let mut updates = Vec::new();
 
@@ -416,11 +758,21 @@ pub fn price_update_system(mut shops: Query<&mut Shop>, time: Res<Time>) {
         for (item, details) in shop.items.iter_mut() {
             let (sales, purchases) = details.transactions;
             let total = sales + purchases;
-            if total >= TRANSACTION_THRESHOLD || elapsed_secs > 20.0 {
-                let new_price = update_price(details.price, sales, purchases, details.stock as i32);
-                details.price = new_price;
+            if total >= config.transaction_threshold || elapsed_secs > 20.0 {
+                let new_price = update_price(
+                    details.price,
+                    sales,
+                    purchases,
+                    details.stock as i32,
+                    config.transaction_threshold,
+                );
                 details.transactions = (0, 0); // Reseta os contadores de transações
-                updates.push((item.clone(), new_price));
+
+                // Só registra no histórico quando o preço realmente muda
+                if new_price != details.price {
+                    details.price = new_price;
+                    updates.push((*item, new_price));
+                }
             }
         }
 
@@ -432,20 +784,232 @@ pub fn price_update_system(mut shops: Query<&mut Shop>, time: Res<Time>) {
                 .push(PriceRecord {
                     timestamp: elapsed_secs,
                     price: new_price,
+                    date: calendar.date,
                 });
         }
     }
+
+    *prices = MarketPrices::from_shops(
+        shops
+            .iter()
+            .filter_map(|(shop, city)| Some((city?.get(), shop))),
+        exchange::country_by_city(countries.iter(), &states),
+    );
 }
 
-pub fn despawn_dead_person_system(mut commands: Commands, query: Query<(Entity, &Person, &Alive)>) {
-    for (entity, person, alive) in query.iter() {
-        if !alive.0 {
-            println!("Destruindo {} pois não está mais vivo!", person.name);
-            commands.entity(entity).despawn_recursive();
+/// Tira do mundo as Persons mortas e da casa em que moravam. A casa que
+/// fica vazia fecha a conta no banco, e o caixa e a despensa entram na
+/// herança. O ouro e o inventário vão, em partes iguais, para os filhos vivos
+/// ou, sem eles, para o cônjuge ou os pais vivos, e os lotes ficam com o
+/// primeiro herdeiro. Sem herdeiros, tudo fica com a cidade: o ouro vai para
+/// o tesouro, o atacadista compra o inventário pelo preço das colheitas, até
+/// onde o caixa dele alcança, e paga ao tesouro, e os lotes passam a ser da
+/// cidade. O vale-alimentação não gasto vence. O lote que a Person cultivava
+/// fica sem lavrador, com a planta, se houver, órfã.
+#[allow(clippy::too_many_arguments)]
+pub fn despawn_dead_person_system(
+    mut commands: Commands,
+    mut persons: Query<(Entity, &mut Person, &Alive, &mut Family, Option<&Parent>)>,
+    mut households: Query<(&mut Household, Option<&mut Credit>)>,
+    mut banks: Query<(Entity, &mut Bank, &Parent)>,
+    mut wholesalers: Query<(Entity, &mut Wholesaler, &Parent)>,
+    mut cities: Query<&mut City>,
+    mut plots: Query<&mut Plot>,
+    catalog: Res<ItemCatalog>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut loans: EventWriter<LoanEvent>,
+) {
+    let dead: Vec<Entity> = persons
+        .iter()
+        .filter(|(_, _, alive, _, _)| !alive.0)
+        .map(|(entity, ..)| entity)
+        .collect();
+
+    for entity in dead {
+        let Ok((_, mut person, _, family, parent)) = persons.get_mut(entity) else {
+            continue;
+        };
+        println!("Destruindo {} pois não está mais vivo!", person.name);
+        let family = family.clone();
+        let city = parent.map(|parent| parent.get());
+
+        if let Some((household_entity, (mut household, credit))) = family
+            .household
+            .and_then(|household| Some((household, households.get_mut(household).ok()?)))
+        {
+            household.members.retain(|member| *member != entity);
+            if household.members.is_empty() {
+                // A conta no banco fecha antes, e o que sobra entra na herança
+                if let (Some(mut credit), Some(city)) = (credit, city) {
+                    let bank = banks
+                        .iter_mut()
+                        .find(|(_, _, parent)| parent.get() == city)
+                        .map(|(bank_entity, bank, _)| (bank_entity, bank));
+                    banks::close_account(
+                        bank,
+                        household_entity,
+                        city,
+                        &mut credit,
+                        &mut household.purse,
+                        &mut transfers,
+                        &mut loans,
+                    );
+                }
+                let purse = household.purse;
+                household::allowance(
+                    &mut household,
+                    household_entity,
+                    &mut person,
+                    entity,
+                    purse,
+                    &mut transfers,
+                );
+                for (item, count) in std::mem::take(&mut household.pantry) {
+                    *person.inventory.entry(item).or_insert(0) += count;
+                }
+                commands.entity(household_entity).despawn_recursive();
+            }
+        }
+        // O vale-alimentação que sobrou vence: só paga comida nas lojas e não
+        // vira ouro na herança
+        let vouchers = std::mem::take(&mut person.vouchers);
+        if vouchers > 0 {
+            transfers.send(TransferEvent {
+                from: Account::Agent(entity),
+                to: Account::Outside,
+                amount: vouchers,
+                kind: TransferKind::Death,
+            });
         }
+        let gold = std::mem::take(&mut person.gold);
+        let inventory = std::mem::take(&mut person.inventory);
+
+        let living = |relatives: &[Entity]| -> Vec<Entity> {
+            relatives
+                .iter()
+                .copied()
+                .filter(|&relative| persons.get(relative).is_ok_and(|(_, _, alive, ..)| alive.0))
+                .collect()
+        };
+        let mut heirs = living(&family.children);
+        if heirs.is_empty() {
+            heirs = living(family.spouse.as_slice());
+        }
+        if heirs.is_empty() {
+            heirs = living(&family.parents);
+        }
+
+        for (&heir, share) in heirs.iter().zip(demography::shares(gold, heirs.len())) {
+            if let Ok((_, mut heir_person, ..)) = persons.get_mut(heir) {
+                heir_person.gold += share;
+            }
+            if share > 0 {
+                transfers.send(TransferEvent {
+                    from: Account::Agent(entity),
+                    to: Account::Agent(heir),
+                    amount: share,
+                    kind: TransferKind::Inheritance,
+                });
+            }
+        }
+        for (&item, &count) in inventory.iter().filter(|(_, count)| **count > 0) {
+            let shares = demography::shares(count as usize, heirs.len());
+            for (&heir, share) in heirs.iter().zip(shares) {
+                if let Ok((_, mut heir_person, ..)) = persons.get_mut(heir) {
+                    *heir_person.inventory.entry(item).or_insert(0) += share as i32;
+                }
+            }
+        }
+        if heirs.is_empty() {
+            // O atacadista da cidade compra o espólio para o tesouro
+            let wholesaler = city.and_then(|city| {
+                wholesalers
+                    .iter_mut()
+                    .find(|(_, _, parent)| parent.get() == city)
+            });
+            if let (Some(city), Some((wholesaler_entity, mut wholesaler, _))) = (city, wholesaler) {
+                let mut estate: Vec<(ItemId, usize)> = inventory
+                    .iter()
+                    .filter(|(_, count)| **count > 0)
+                    .map(|(item, count)| (*item, *count as usize))
+                    .collect();
+                estate.sort();
+                let mut proceeds = 0;
+                for (item, count) in estate {
+                    let price = config.shops.crop_price(&catalog, item);
+                    let quantity = wholesaler
+                        .cash
+                        .checked_div(price)
+                        .map_or(count, |n| n.min(count));
+                    wholesaler.cash -= quantity * price;
+                    proceeds += quantity * price;
+                }
+                if proceeds > 0 {
+                    if let Ok(mut city_component) = cities.get_mut(city) {
+                        city_component.treasury += proceeds;
+                    }
+                    transfers.send(TransferEvent {
+                        from: Account::Agent(wholesaler_entity),
+                        to: Account::Agent(city),
+                        amount: proceeds,
+                        kind: TransferKind::Trade,
+                    });
+                }
+            }
+        }
+        for mut plot in plots.iter_mut() {
+            if plot.owner == Some(entity) {
+                plot.owner = heirs.first().copied().or(city);
+            }
+            if plot.farmer == Some(entity) {
+                plot.farmer = None;
+            }
+        }
+
+        if let Some(mut city) = city.and_then(|city| cities.get_mut(city).ok()) {
+            city.persons.retain(|person| *person != entity);
+        }
+        if heirs.is_empty() && gold > 0 {
+            // Sem herdeiros, o tesouro da cidade recebe o ouro; sem cidade,
+            // ele some junto com a Person
+            let treasury = city.filter(|&city| {
+                cities
+                    .get_mut(city)
+                    .map(|mut city| city.treasury += gold)
+                    .is_ok()
+            });
+            transfers.send(TransferEvent {
+                from: Account::Agent(entity),
+                to: treasury.map_or(Account::Outside, Account::Agent),
+                amount: gold,
+                kind: if treasury.is_some() {
+                    TransferKind::Inheritance
+                } else {
+                    TransferKind::Death
+                },
+            });
+        }
+
+        for relative in family
+            .parents
+            .iter()
+            .chain(&family.children)
+            .chain(&family.spouse)
+        {
+            if let Ok((_, _, _, mut relatives, _)) = persons.get_mut(*relative) {
+                relatives.parents.retain(|person| *person != entity);
+                relatives.children.retain(|person| *person != entity);
+                if relatives.spouse == Some(entity) {
+                    relatives.spouse = None;
+                }
+            }
+        }
+        commands.entity(entity).despawn_recursive();
     }
 }
 
+#[allow(dead_code)]
 pub fn get_people_stats(people: Query<(&Person, &Alive), With<Person>>) {
     // get average people hunger, gold and health
     let mut average_hunger = 0.0;
@@ -482,28 +1046,14 @@ pub fn get_people_stats(people: Query<(&Person, &Alive), With<Person>>) {
 //     }
 // }
 
-// Calculate inflation for a specific item
-fn calculate_item_inflation(price_history: &[PriceRecord]) -> Option<f32> {
-    if price_history.len() < 2 {
-        return None;
-    }
-
-    let first_price = price_history.first().unwrap().price as f32;
-    let last_price = price_history.last().unwrap().price as f32;
-
-    Some(((last_price - first_price) / first_price) * 100.0)
-}
-
-pub fn get_shops_stats(shops: Query<&Shop, With<Shop>>) {
+#[allow(dead_code)]
+pub fn get_shops_stats(shops: Query<&Shop, With<Shop>>, indicators: Res<Indicators>) {
     let mut total_stock: f32 = 0.0;
     let mut total_price: usize = 0;
     let mut total_sales: usize = 0;
     let mut total_purchases: usize = 0;
     let mut count_items = 0;
 
-    let mut total_inflation: f32 = 0.0;
-    let mut count_inflation: usize = 0;
-
     // Iterate over all shops
     for shop in shops.iter() {
         // Iterate over each item in the shop for basic stats
@@ -514,22 +1064,6 @@ pub fn get_shops_stats(shops: Query<&Shop, With<Shop>>) {
             total_purchases += details.transactions.1;
             count_items += 1;
         }
-
-        // Iterate over each item's price history to calculate inflation
-        for (_item, history) in shop.price_history.iter() {
-            if let Some(first_record) = history.first() {
-                if let Some(last_record) = history.last() {
-                    if first_record.price > 0 {
-                        // Compute inflation as the percentage change from the first price to the last price
-                        let inflation = (last_record.price as f32 - first_record.price as f32)
-                            / first_record.price as f32
-                            * 100.0;
-                        total_inflation += inflation;
-                        count_inflation += 1;
-                    }
-                }
-            }
-        }
     }
 
     if count_items > 0 {
@@ -537,74 +1071,66 @@ pub fn get_shops_stats(shops: Query<&Shop, With<Shop>>) {
         let average_price = total_price / count_items;
         let average_sales = total_sales / count_items;
         let average_purchases = total_purchases / count_items;
-        let average_inflation = if count_inflation > 0 {
-            total_inflation / count_inflation as f32
-        } else {
-            0.0
-        };
 
         println!(
-            "Shops stats -> Average stock: {}, Average price: {}, Average sales: {}, Average purchases: {}, Average inflation: {:.2}%",
-            average_stock, average_price, average_sales, average_purchases, average_inflation
+            "Shops stats -> Average stock: {}, Average price: {}, Average sales: {}, Average purchases: {}, CPI: {:.1}, Inflation: {:.2}%",
+            average_stock, average_price, average_sales, average_purchases, indicators.world.cpi, indicators.world.inflation
         );
     } else {
         println!("No items found in shops.");
     }
 }
 
+/// Abre o relatório de cada dia simulado com a data do calendário.
+pub fn print_day_header(calendar: Res<Calendar>, clock: Res<SimClock>) {
+    println!("=== Dia {} ({}) ===", clock.day(), calendar.date);
+}
+
+fn print_indicators(indicators: Option<&ScopeIndicators>) {
+    if let Some(indicators) = indicators {
+        println!(
+            "  CPI: {:.1}, Inflation: {:.2}%, Output: {:.0} (harvests {:.0}, trade {:.0}), Money supply: {:.0}, Velocity: {:.2}, Gini (wealth/income): {:.2}/{:.2}",
+            indicators.cpi,
+            indicators.inflation,
+            indicators.nominal_output,
+            indicators.production_value,
+            indicators.trade_value,
+            indicators.money_supply,
+            indicators.velocity,
+            indicators.wealth_gini,
+            indicators.income_gini
+        );
+        println!(
+            "  Household income: {:.1} on average, Gini {:.2}",
+            indicators.average_household_income, indicators.household_income_gini
+        );
+        println!(
+            "  Unemployment: {:.1}%, Average wage: {:.2}/s",
+            indicators.unemployment * 100.0,
+            indicators.average_wage
+        );
+    }
+}
+
+#[allow(dead_code)]
 pub fn get_city_stats(
-    cities: Query<(&City, &Children), With<City>>,
+    cities: Query<(Entity, &City, &Children), With<City>>,
     shops: Query<&Shop, With<Shop>>,
     people: Query<(&Person, &Alive), With<Person>>,
+    indicators: Res<Indicators>,
 ) {
-    use std::collections::HashMap;
-
     // Iterate over all cities
-    for (city, children) in cities.iter() {
+    for (city_entity, city, children) in cities.iter() {
         // Total number of persons and shops in the city
         let mut total_persons = 0;
         let mut total_shops = 0;
 
-        // Map to accumulate inflation data per product.
-        // Key: product name, Value: (total inflation, count of records)
-        let mut inflation_data: HashMap<String, (f32, usize)> = HashMap::new();
-
         for child in children.iter() {
-            if let Ok(person) = people.get(*child) {
+            if people.get(*child).is_ok() {
                 total_persons += 1;
-            }    
-        }
-
-        for child in children.iter() {
-            if let Ok(shop) = shops.get(*child) {
-                total_shops += 1;
-
-                for (item, history) in shop.price_history.iter() {
-                    // Ensure there is at least one record and a valid starting price.
-                    if let (Some(first_record), Some(last_record)) =
-                        (history.first(), history.last())
-                    {
-                        if first_record.price > 0 {
-                            // Calculate inflation as percentage change
-                            let inflation = (last_record.price as f32 - first_record.price as f32)
-                                / first_record.price as f32
-                                * 100.0;
-                            // Accumulate inflation data for the product (using product name as key)
-                            let entry = inflation_data.entry(item.name.clone()).or_insert((0.0, 0));
-                            entry.0 += inflation;
-                            entry.1 += 1;
-                        }
-                    }
-                }
             }
-
-        }
-        
-        // Compute the average inflation per product
-        let mut average_inflation: HashMap<String, f32> = HashMap::new();
-        for (product, (total_inflation, count)) in inflation_data.iter() {
-            if *count > 0 {
-                average_inflation.insert(product.clone(), total_inflation / (*count as f32));
+            if shops.get(*child).is_ok() {
+                total_shops += 1;
             }
         }
 
@@ -613,34 +1139,30 @@ pub fn get_city_stats(
             "City: {} - Total persons: {}, Total shops: {}",
             city.name, total_persons, total_shops
         );
-        println!("Average inflation per product:");
-        for (product, inflation) in average_inflation.iter() {
-            println!("  {}: {:.2}%", product, inflation);
-        }
+        print_indicators(indicators.city(city_entity));
     }
 }
 
 
 pub fn get_state_stats(
-    states: Query<(&State, &Children), With<State>>,
+    states: Query<(Entity, &State, &Weather, &Children), With<State>>,
     cities: Query<(&City, &Children), With<City>>,
     shops: Query<&Shop, With<Shop>>,
     people: Query<&Person, With<Person>>,
+    households: Query<&Household>,
+    indicators: Res<Indicators>,
 ) {
 
     // Iterate over all states (estates)
-    for (state, state_children) in states.iter() {
+    for (state_entity, state, weather, state_children) in states.iter() {
         let mut total_cities = 0;
         let mut total_persons = 0;
         let mut total_gold = 0;
         let mut total_shops = 0;
-        // Map to accumulate inflation data per product.
-        // Key: product name, Value: (total inflation, count)
-        let mut inflation_data: HashMap<String, (f32, usize)> = HashMap::new();
 
         // Each child of the state is a City
         for &city_entity in state_children.iter() {
-            if let Ok((city, city_children)) = cities.get(city_entity) {
+            if let Ok((_city, city_children)) = cities.get(city_entity) {
                 total_cities += 1;
                 // For each child of the city, check if it's a Person or a Shop
                 for &child in city_children.iter() {
@@ -648,92 +1170,63 @@ pub fn get_state_stats(
                         total_persons += 1;
                         total_gold += person.gold;
                     }
-                    if let Ok(shop) = shops.get(child) {
+                    if let Ok(household) = households.get(child) {
+                        total_gold += household.purse;
+                    }
+                    if shops.get(child).is_ok() {
                         total_shops += 1;
-                        // Process each shop's price history
-                        for (item, history) in shop.price_history.iter() {
-                            if let (Some(first_record), Some(last_record)) = (history.first(), history.last()) {
-                                if first_record.price > 0 {
-                                    let inflation = (last_record.price as f32 - first_record.price as f32)
-                                        / first_record.price as f32
-                                        * 100.0;
-                                    let entry = inflation_data.entry(item.name.clone()).or_insert((0.0, 0));
-                                    entry.0 += inflation;
-                                    entry.1 += 1;
-                                }
-                            }
-                        }
                     }
                 }
             }
         }
 
-        // Compute average inflation per product for this state
-        let mut average_inflation: HashMap<String, f32> = HashMap::new();
-        for (product, (total_inflation, count)) in inflation_data.iter() {
-            if *count > 0 {
-                average_inflation.insert(product.clone(), total_inflation / (*count as f32));
-            }
-        }
-
         println!(
-            "State: {} - Total cities: {} - Total persons: {} - Total gold: {}, Total shops: {}",
-            state.name, total_cities, total_persons, total_gold, total_shops
+            "State: {} - Total cities: {} - Total persons: {} - Total gold: {}, Total shops: {}, Weather: {}",
+            state.name, total_cities, total_persons, total_gold, total_shops, weather.kind.name()
         );
-        println!("Average inflation per product:");
-        for (product, inflation) in average_inflation.iter() {
-            println!("  {}: {:.2}%", product, inflation);
-        }
+        print_indicators(indicators.state(state_entity));
     }
 }
 
-
+#[allow(clippy::too_many_arguments)]
 pub fn get_country_stats(
-    countries: Query<(&Country, &Children), With<Country>>,
+    countries: Query<(Entity, &Country, &Children), With<Country>>,
     estates: Query<(&State, &Children), With<State>>,
     cities: Query<(&City, &Children), With<City>>,
     shops: Query<&Shop, With<Shop>>,
     people: Query<(&Person, &Alive), With<Person>>,
+    central_banks: Query<&CentralBank>,
+    indicators: Res<Indicators>,
+    ledger: Res<Ledger>,
+    clock: Res<SimClock>,
 ) {
-    use std::collections::HashMap;
-
+    // O dia anterior inteiro, já lançado no livro-razão
+    let yesterday =
+        clock.tick.saturating_sub(clock.ticks_for(clock.day_secs))..=clock.tick.saturating_sub(1);
     // Iterate over all countries
-    for (country, country_children) in countries.iter() {
+    for (country_entity, country, country_children) in countries.iter() {
         let mut total_persons = 0;
         let mut total_shops = 0;
-        let mut total_states = 0;
         let mut total_cities = 0;
-        // Map to accumulate inflation data per product.
-        let mut inflation_data: HashMap<String, (f32, usize)> = HashMap::new();
+        let mut state_treasuries = 0;
+        let mut city_treasuries = 0;
 
         // Each child of a country is a State (Estate)
         for &state_entity in country_children.iter() {
             if let Ok((estate, estate_children)) = estates.get(state_entity) {
-                total_states += 1;
+                state_treasuries += estate.treasury;
                 // Each child of the state is a City
                 for &city_entity in estate_children.iter() {
                     if let Ok((city, city_children)) = cities.get(city_entity) {
                         total_cities += 1;
+                        city_treasuries += city.treasury;
                         // Process each child of the city
                         for &child in city_children.iter() {
                             if people.get(child).is_ok() {
                                 total_persons += 1;
                             }
-                            if let Ok(shop) = shops.get(child) {
+                            if shops.get(child).is_ok() {
                                 total_shops += 1;
-                                // Process the shop's price history
-                                for (item, history) in shop.price_history.iter() {
-                                    if let (Some(first_record), Some(last_record)) = (history.first(), history.last()) {
-                                        if first_record.price > 0 {
-                                            let inflation = (last_record.price as f32 - first_record.price as f32)
-                                                / first_record.price as f32
-                                                * 100.0;
-                                            let entry = inflation_data.entry(item.name.clone()).or_insert((0.0, 0));
-                                            entry.0 += inflation;
-                                            entry.1 += 1;
-                                        }
-                                    }
-                                }
                             }
                         }
                     }
@@ -741,22 +1234,38 @@ pub fn get_country_stats(
             }
         }
 
-        // Compute average inflation per product for the country
-        let mut average_inflation: HashMap<String, f32> = HashMap::new();
-        for (product, (total_inflation, count)) in inflation_data.iter() {
-            if *count > 0 {
-                average_inflation.insert(product.clone(), total_inflation / (*count as f32));
-            }
-        }
-
         println!(
             "Country: {} - Total cities: {} - Total persons: {}, Total shops: {}",
             country.name, total_cities, total_persons, total_shops
         );
-        println!("Average inflation per product:");
-        for (product, inflation) in average_inflation.iter() {
-            println!("  {}: {:.2}%", product, inflation);
+        // Banco central do país, entre os filhos
+        let central = country_children
+            .iter()
+            .find_map(|&child| central_banks.get(child).ok());
+        if let Some(central) = central {
+            println!(
+                "{} - Taxa básica: {:.2}% ao dia - Ouro em circulação: {}",
+                central.name,
+                central.rate * 100.0,
+                country.total_gold
+            );
         }
+        println!(
+            "Moeda: {} - Cotação: {:.4}",
+            country.currency.name, country.currency.rate
+        );
+        println!(
+            "Tesouros - País: {} - Estados: {} - Cidades: {}",
+            country.treasury, state_treasuries, city_treasuries
+        );
+        let statement = ledger.income_statement(Account::Agent(country_entity), yesterday.clone());
+        println!(
+            "Tesouro do país ontem - Receitas: {} - Despesas: {} - Resultado: {:+}",
+            statement.total_revenue(),
+            statement.total_expenses(),
+            statement.net()
+        );
+        print_indicators(indicators.country(country_entity));
     }
 }
 
@@ -0,0 +1,336 @@
+use std::fmt;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Alive, City, Country, Household, Person, Plot, State};
+use crate::config::ScenarioConfig;
+use crate::events::{TaxEvent, TransferEvent};
+use crate::ledger::{Account, TransferKind};
+
+/// Alíquotas com que cada nível de governo começa. Um choque `set_tax`
+/// muda as de um nível durante a execução.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaxConfig {
+    pub city: TaxRates,
+    pub state: TaxRates,
+    pub country: TaxRates,
+    /// Ouro de cada Person ou casa livre do imposto sobre a fortuna.
+    pub wealth_exemption: usize,
+}
+
+impl TaxConfig {
+    pub fn rates(&self, level: TaxLevel) -> TaxRates {
+        match level {
+            TaxLevel::City => self.city,
+            TaxLevel::State => self.state,
+            TaxLevel::Country => self.country,
+        }
+    }
+}
+
+/// Alíquotas de um nível de governo, guardadas na cidade, no estado ou no
+/// país.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaxRates {
+    /// Fração do valor de cada venda no mercado, retida do vendedor.
+    pub sales: f32,
+    /// Fração do salário, retida pelo empregador.
+    pub income: f32,
+    /// Fração por dia do ouro acima da isenção, cobrada das Persons e das
+    /// casas.
+    pub wealth: f32,
+    /// Ouro por dia por lote, cobrado do dono.
+    pub land: f32,
+}
+
+impl TaxRates {
+    pub fn rate(&self, tax: Tax) -> f32 {
+        match tax {
+            Tax::Sales => self.sales,
+            Tax::Income => self.income,
+            Tax::Wealth => self.wealth,
+            Tax::Land => self.land,
+        }
+    }
+
+    pub fn set(&mut self, tax: Tax, rate: f32) {
+        match tax {
+            Tax::Sales => self.sales = rate,
+            Tax::Income => self.income = rate,
+            Tax::Wealth => self.wealth = rate,
+            Tax::Land => self.land = rate,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Tax {
+    Sales,
+    Income,
+    Wealth,
+    Land,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxLevel {
+    City,
+    State,
+    Country,
+}
+
+impl Tax {
+    /// Nome da chave no cenário.
+    pub fn key(self) -> &'static str {
+        match self {
+            Tax::Sales => "sales",
+            Tax::Income => "income",
+            Tax::Wealth => "wealth",
+            Tax::Land => "land",
+        }
+    }
+}
+
+impl TaxLevel {
+    pub fn key(self) -> &'static str {
+        match self {
+            TaxLevel::City => "city",
+            TaxLevel::State => "state",
+            TaxLevel::Country => "country",
+        }
+    }
+}
+
+impl fmt::Display for Tax {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tax::Sales => write!(f, "imposto sobre vendas"),
+            Tax::Income => write!(f, "imposto de renda"),
+            Tax::Wealth => write!(f, "imposto sobre fortunas"),
+            Tax::Land => write!(f, "imposto territorial"),
+        }
+    }
+}
+
+impl fmt::Display for TaxLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaxLevel::City => write!(f, "das cidades"),
+            TaxLevel::State => write!(f, "dos estados"),
+            TaxLevel::Country => write!(f, "dos países"),
+        }
+    }
+}
+
+/// Tesouros que cobram impostos em cada cidade, com as suas alíquotas: o
+/// da cidade, o do estado e o do país, nessa ordem.
+#[derive(Resource, Default)]
+pub struct TaxTable(HashMap<Entity, Vec<(Entity, TaxRates)>>);
+
+impl TaxTable {
+    /// Parte de `base` devida a cada tesouro da cidade. Se as alíquotas de
+    /// um imposto proporcional somam mais que 1, o último nível fica só com
+    /// o que sobra; o territorial é por lote e não tem teto.
+    pub fn shares(&self, city: Entity, tax: Tax, base: f32) -> Vec<(Entity, f32)> {
+        let mut left = base;
+        self.0
+            .get(&city)
+            .into_iter()
+            .flatten()
+            .map(|(treasury, rates)| {
+                let mut share = base * rates.rate(tax);
+                if tax != Tax::Land {
+                    share = share.min(left);
+                }
+                left -= share;
+                (*treasury, share)
+            })
+            .collect()
+    }
+
+    /// O mesmo em ouro, arredondado para baixo, só com os tesouros que
+    /// recebem algo.
+    pub fn withhold(&self, city: Entity, tax: Tax, base: usize) -> Vec<(Entity, usize)> {
+        self.shares(city, tax, base as f32)
+            .into_iter()
+            .map(|(treasury, share)| (treasury, share.floor() as usize))
+            .filter(|(_, amount)| *amount > 0)
+            .collect()
+    }
+}
+
+/// Lança o imposto que `payer`, de `city`, já pagou a `treasury`.
+pub fn pay(
+    transfers: &mut EventWriter<TransferEvent>,
+    taxes: &mut EventWriter<TaxEvent>,
+    payer: Entity,
+    city: Entity,
+    treasury: Entity,
+    tax: Tax,
+    amount: usize,
+) {
+    transfers.send(TransferEvent {
+        from: Account::Agent(payer),
+        to: Account::Agent(treasury),
+        amount,
+        kind: TransferKind::Tax,
+    });
+    taxes.send(TaxEvent {
+        city,
+        treasury,
+        tax,
+        amount,
+    });
+}
+
+/// Monta a `TaxTable` no começo do tick, com as alíquotas em vigor depois
+/// dos choques.
+pub fn tax_table_system(
+    mut table: ResMut<TaxTable>,
+    countries: Query<(Entity, &Country, &Children)>,
+    states: Query<(Entity, &State, &Children)>,
+    cities: Query<(Entity, &City)>,
+) {
+    table.0.clear();
+    for (country_entity, country, country_children) in countries.iter() {
+        for (state_entity, state, state_children) in country_children
+            .iter()
+            .filter_map(|&state| states.get(state).ok())
+        {
+            for (city_entity, city) in state_children
+                .iter()
+                .filter_map(|&city| cities.get(city).ok())
+            {
+                table.0.insert(
+                    city_entity,
+                    vec![
+                        (city_entity, city.taxes),
+                        (state_entity, state.taxes),
+                        (country_entity, country.taxes),
+                    ],
+                );
+            }
+        }
+    }
+}
+
+/// Impostos diários sobre o patrimônio: sobre o ouro das Persons vivas e
+/// das casas acima da isenção, e sobre cada lote, cobrado do dono. Quem não
+/// tem o ouro paga o que tem.
+#[allow(clippy::too_many_arguments)]
+pub fn property_tax_system(
+    mut persons: Query<(Entity, &mut Person, &Alive, &Parent)>,
+    mut households: Query<(Entity, &mut Household, &Parent)>,
+    plots: Query<(&Plot, &Parent)>,
+    table: Res<TaxTable>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut taxes: EventWriter<TaxEvent>,
+) {
+    let exemption = config.taxes.wealth_exemption;
+    let mut charge = |payer: Entity, city: Entity, gold: &mut usize, tax: Tax, base: f32| {
+        for (treasury, share) in table.shares(city, tax, base) {
+            let amount = (share.floor() as usize).min(*gold);
+            if amount > 0 {
+                *gold -= amount;
+                pay(
+                    &mut transfers,
+                    &mut taxes,
+                    payer,
+                    city,
+                    treasury,
+                    tax,
+                    amount,
+                );
+            }
+        }
+    };
+
+    for (entity, mut household, city) in households.iter_mut() {
+        let base = household.purse.saturating_sub(exemption) as f32;
+        charge(entity, city.get(), &mut household.purse, Tax::Wealth, base);
+    }
+    // Lotes de cada dono, por cidade
+    let mut owned: HashMap<Entity, Vec<(Entity, usize)>> = HashMap::new();
+    for (plot, city) in plots.iter() {
+        let Some(owner) = plot.owner else {
+            continue;
+        };
+        let lots = owned.entry(owner).or_default();
+        match lots
+            .iter_mut()
+            .find(|(lot_city, _)| *lot_city == city.get())
+        {
+            Some((_, count)) => *count += 1,
+            None => lots.push((city.get(), 1)),
+        }
+    }
+    for (entity, mut person, alive, city) in persons.iter_mut() {
+        if !alive.0 {
+            continue;
+        }
+        let base = person.gold.saturating_sub(exemption) as f32;
+        charge(entity, city.get(), &mut person.gold, Tax::Wealth, base);
+        // Os lotes de cada cidade pagam ao governo de lá, os da própria
+        // cidade primeiro
+        let mut lots = owned.remove(&entity).unwrap_or_default();
+        lots.sort_by_key(|(lot_city, _)| (*lot_city != city.get(), *lot_city));
+        for (lot_city, count) in lots {
+            charge(entity, lot_city, &mut person.gold, Tax::Land, count as f32);
+        }
+    }
+}
+
+/// Credita os impostos do tick no tesouro da cidade, do estado ou do país.
+pub fn treasury_system(
+    mut taxes: EventReader<TaxEvent>,
+    mut cities: Query<&mut City>,
+    mut states: Query<&mut State>,
+    mut countries: Query<&mut Country>,
+) {
+    for tax in taxes.read() {
+        if let Ok(mut city) = cities.get_mut(tax.treasury) {
+            city.treasury += tax.amount;
+        } else if let Ok(mut state) = states.get_mut(tax.treasury) {
+            state.treasury += tax.amount;
+        } else if let Ok(mut country) = countries.get_mut(tax.treasury) {
+            country.treasury += tax.amount;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_level_withholds_its_share_rounded_down() {
+        let [city, state, country] = [0, 1, 2].map(Entity::from_raw);
+        let rates = |sales: f32| TaxRates { sales, ..default() };
+        let mut table = TaxTable::default();
+        table.0.insert(
+            city,
+            vec![
+                (city, rates(0.125)),
+                (state, rates(0.0)),
+                (country, rates(0.25)),
+            ],
+        );
+
+        assert_eq!(
+            table.withhold(city, Tax::Sales, 10),
+            vec![(city, 1), (country, 2)]
+        );
+        assert!(table.withhold(city, Tax::Income, 10).is_empty());
+        assert!(table.withhold(state, Tax::Sales, 10).is_empty());
+
+        // Acima de 100% somados, o país só leva o que a cidade deixou
+        table.0.get_mut(&city).unwrap()[0].1.set(Tax::Sales, 0.875);
+        let shares = table.shares(city, Tax::Sales, 8.0);
+        assert_eq!(shares, vec![(city, 7.0), (state, 0.0), (country, 1.0)]);
+    }
+}
@@ -0,0 +1,280 @@
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::SimClock;
+use crate::components::{City, State, TerrainType};
+use crate::config::ScenarioConfig;
+use crate::rng::SimRng;
+
+/// Parâmetros do tempo em cada estado.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherConfig {
+    /// Duração média de cada período de tempo; a real varia de metade a uma
+    /// vez e meia disso.
+    pub spell_secs: f32,
+    /// Chance de cada tempo em cada terreno; o que sobra é tempo firme.
+    pub grassland: WeatherOdds,
+    pub forest: WeatherOdds,
+    pub mountain: WeatherOdds,
+    pub desert: WeatherOdds,
+    /// Efeitos de cada tempo; o tempo firme não muda nada.
+    pub rain: WeatherEffects,
+    pub drought: WeatherEffects,
+    pub frost: WeatherEffects,
+    pub heatwave: WeatherEffects,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            spell_secs: 30.0,
+            grassland: WeatherOdds {
+                rain: 0.3,
+                drought: 0.05,
+                frost: 0.05,
+                heatwave: 0.05,
+            },
+            forest: WeatherOdds {
+                rain: 0.4,
+                drought: 0.03,
+                frost: 0.07,
+                heatwave: 0.03,
+            },
+            mountain: WeatherOdds {
+                rain: 0.25,
+                drought: 0.05,
+                frost: 0.15,
+                heatwave: 0.02,
+            },
+            desert: WeatherOdds {
+                rain: 0.05,
+                drought: 0.2,
+                frost: 0.05,
+                heatwave: 0.15,
+            },
+            rain: WeatherEffects {
+                growth: 1.2,
+                drying: -1.0,
+                energy: 0.9,
+                ..default()
+            },
+            drought: WeatherEffects {
+                drying: 1.5,
+                hunger: 1.1,
+                ..default()
+            },
+            frost: WeatherEffects {
+                growth: 0.5,
+                drying: 0.5,
+                damage: 0.3,
+                hunger: 1.2,
+                energy: 0.8,
+            },
+            heatwave: WeatherEffects {
+                growth: 0.8,
+                drying: 1.5,
+                damage: 0.2,
+                energy: 0.7,
+                ..default()
+            },
+        }
+    }
+}
+
+impl WeatherConfig {
+    pub fn odds(&self, terrain: TerrainType) -> &WeatherOdds {
+        match terrain {
+            TerrainType::Grassland => &self.grassland,
+            TerrainType::Forest => &self.forest,
+            TerrainType::Mountain => &self.mountain,
+            TerrainType::Desert => &self.desert,
+        }
+    }
+
+    pub fn terrains(&self) -> [(&'static str, &WeatherOdds); 4] {
+        [
+            ("grassland", &self.grassland),
+            ("forest", &self.forest),
+            ("mountain", &self.mountain),
+            ("desert", &self.desert),
+        ]
+    }
+
+    /// Efeitos configuráveis, de todo tempo que não é firme.
+    pub fn kinds(&self) -> [(WeatherKind, &WeatherEffects); 4] {
+        [
+            (WeatherKind::Rain, &self.rain),
+            (WeatherKind::Drought, &self.drought),
+            (WeatherKind::Frost, &self.frost),
+            (WeatherKind::Heatwave, &self.heatwave),
+        ]
+    }
+
+    pub fn effects(&self, kind: WeatherKind) -> WeatherEffects {
+        match kind {
+            WeatherKind::Clear => WeatherEffects::default(),
+            WeatherKind::Rain => self.rain,
+            WeatherKind::Drought => self.drought,
+            WeatherKind::Frost => self.frost,
+            WeatherKind::Heatwave => self.heatwave,
+        }
+    }
+}
+
+/// Chance de cada tempo ao fim de um período.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherOdds {
+    pub rain: f32,
+    pub drought: f32,
+    pub frost: f32,
+    pub heatwave: f32,
+}
+
+impl WeatherOdds {
+    pub fn values(&self) -> [(WeatherKind, f32); 4] {
+        [
+            (WeatherKind::Rain, self.rain),
+            (WeatherKind::Drought, self.drought),
+            (WeatherKind::Frost, self.frost),
+            (WeatherKind::Heatwave, self.heatwave),
+        ]
+    }
+
+    /// Tempo sorteado por `roll`, entre 0 e 1.
+    pub fn pick(&self, roll: f32) -> WeatherKind {
+        let mut total = 0.0;
+        for (kind, chance) in self.values() {
+            total += chance;
+            if roll < total {
+                return kind;
+            }
+        }
+        WeatherKind::Clear
+    }
+}
+
+/// Multiplicadores aplicados pelo tempo às plantas e às Persons do estado.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeatherEffects {
+    /// Ritmo de crescimento das plantas.
+    pub growth: f32,
+    /// Ritmo em que o solo seca; negativo molha o solo, como a chuva.
+    pub drying: f32,
+    /// Vigor perdido pelas plantas, relativo ao de uma planta com sede.
+    pub damage: f32,
+    /// Ritmo da fome.
+    pub hunger: f32,
+    /// Ritmo em que a energia se repõe.
+    pub energy: f32,
+}
+
+impl Default for WeatherEffects {
+    fn default() -> Self {
+        Self {
+            growth: 1.0,
+            drying: 1.0,
+            damage: 0.0,
+            hunger: 1.0,
+            energy: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Drought,
+    Frost,
+    Heatwave,
+}
+
+impl WeatherKind {
+    /// Nome da chave no cenário.
+    pub fn key(self) -> &'static str {
+        match self {
+            WeatherKind::Clear => "clear",
+            WeatherKind::Rain => "rain",
+            WeatherKind::Drought => "drought",
+            WeatherKind::Frost => "frost",
+            WeatherKind::Heatwave => "heatwave",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WeatherKind::Clear => "Firme",
+            WeatherKind::Rain => "Chuva",
+            WeatherKind::Drought => "Seca",
+            WeatherKind::Frost => "Geada",
+            WeatherKind::Heatwave => "Onda de calor",
+        }
+    }
+}
+
+/// Tempo atual de um estado e quanto ainda dura.
+#[derive(Component, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    pub remaining_secs: f32,
+}
+
+/// Efeitos do tempo em cada cidade, pelo tempo do estado a que pertence.
+pub fn city_effects(
+    cities: &Query<(Entity, &Parent), With<City>>,
+    states: &Query<&Weather>,
+    config: &WeatherConfig,
+) -> HashMap<Entity, WeatherEffects> {
+    cities
+        .iter()
+        .filter_map(|(city, state)| {
+            let weather = states.get(state.get()).ok()?;
+            Some((city, config.effects(weather.kind)))
+        })
+        .collect()
+}
+
+/// Sorteia um novo tempo para cada estado cujo período acabou, com as
+/// chances do terreno do estado.
+pub fn weather_system(
+    mut states: Query<(&State, &mut Weather)>,
+    config: Res<ScenarioConfig>,
+    clock: Res<SimClock>,
+    mut rng: ResMut<SimRng>,
+) {
+    let config = &config.weather;
+    for (state, mut weather) in states.iter_mut() {
+        weather.remaining_secs -= clock.delta_secs();
+        if weather.remaining_secs > 0.0 {
+            continue;
+        }
+        let roll: f32 = rng.0.random();
+        weather.kind = config.odds(state.terrain_type).pick(roll);
+        weather.remaining_secs = config.spell_secs * rng.0.random_range(0.5..1.5);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn odds_split_the_roll_by_terrain() {
+        let config = WeatherConfig::default();
+        let desert = config.odds(TerrainType::Desert);
+        assert_eq!(desert.pick(0.0), WeatherKind::Rain);
+        assert_eq!(desert.pick(0.1), WeatherKind::Drought);
+        assert_eq!(desert.pick(0.27), WeatherKind::Frost);
+        assert_eq!(desert.pick(0.4), WeatherKind::Heatwave);
+        assert_eq!(desert.pick(0.9), WeatherKind::Clear);
+        assert_eq!(
+            config.effects(WeatherKind::Clear),
+            WeatherEffects::default()
+        );
+    }
+}
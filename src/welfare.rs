@@ -0,0 +1,393 @@
+use std::fmt;
+
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    Alive, City, Country, Employment, Family, Household, JobSeeker, Person, State,
+};
+use crate::config::ScenarioConfig;
+use crate::events::{BenefitEvent, TransferEvent};
+use crate::ledger::{Account, TransferKind};
+
+/// Programas sociais com que cada nível de governo começa. Um choque
+/// `set_benefit` muda os de um nível durante a execução.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WelfareConfig {
+    pub city: WelfarePolicy,
+    pub state: WelfarePolicy,
+    pub country: WelfarePolicy,
+    /// Ouro, contando a parte no caixa da casa, abaixo do qual a Person
+    /// recebe o vale-alimentação.
+    pub means_test: usize,
+}
+
+impl Default for WelfareConfig {
+    fn default() -> Self {
+        Self {
+            city: WelfarePolicy::default(),
+            state: WelfarePolicy::default(),
+            country: WelfarePolicy::default(),
+            means_test: 30,
+        }
+    }
+}
+
+/// Benefícios pagos por um nível de governo, em ouro por dia para cada
+/// Person que tem direito, guardados na cidade, no estado ou no país.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WelfarePolicy {
+    /// Renda básica de cada adulto vivo.
+    pub basic_income: usize,
+    /// Vale-alimentação de quem passa no teste de renda; só se gasta em
+    /// comida nas lojas.
+    pub food_stamps: usize,
+    /// Seguro de quem procurou emprego e não foi contratado.
+    pub unemployment: usize,
+}
+
+impl WelfarePolicy {
+    pub fn amount(&self, benefit: Benefit) -> usize {
+        match benefit {
+            Benefit::BasicIncome => self.basic_income,
+            Benefit::FoodStamps => self.food_stamps,
+            Benefit::Unemployment => self.unemployment,
+        }
+    }
+
+    pub fn set(&mut self, benefit: Benefit, amount: usize) {
+        match benefit {
+            Benefit::BasicIncome => self.basic_income = amount,
+            Benefit::FoodStamps => self.food_stamps = amount,
+            Benefit::Unemployment => self.unemployment = amount,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Benefit {
+    BasicIncome,
+    FoodStamps,
+    Unemployment,
+}
+
+impl Benefit {
+    pub const ALL: [Benefit; 3] = [
+        Benefit::BasicIncome,
+        Benefit::FoodStamps,
+        Benefit::Unemployment,
+    ];
+}
+
+impl fmt::Display for Benefit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Benefit::BasicIncome => write!(f, "renda básica"),
+            Benefit::FoodStamps => write!(f, "vale-alimentação"),
+            Benefit::Unemployment => write!(f, "seguro-desemprego"),
+        }
+    }
+}
+
+/// Pagamento diário dos benefícios. Cada Person viva recebe de cada nível de
+/// governo da sua cidade os programas a que tem direito, até onde o tesouro
+/// alcança; a renda básica e o seguro entram no ouro dela, e o
+/// vale-alimentação em `Person.vouchers`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn benefits_system(
+    mut persons: Query<(
+        Entity,
+        &mut Person,
+        &Alive,
+        &Parent,
+        &Employment,
+        &JobSeeker,
+        &Family,
+    )>,
+    households: Query<&Household>,
+    mut countries: Query<(Entity, &mut Country, &Children)>,
+    mut states: Query<(Entity, &mut State, &Children)>,
+    mut cities: Query<(Entity, &mut City)>,
+    config: Res<ScenarioConfig>,
+    mut transfers: EventWriter<TransferEvent>,
+    mut benefits: EventWriter<BenefitEvent>,
+) {
+    let demography = &config.demography;
+    let means_test = config.welfare.means_test;
+
+    // Ouro e programas de cada tesouro, e os tesouros de cada cidade
+    let mut funds: HashMap<Entity, (usize, WelfarePolicy)> = HashMap::new();
+    let mut levels: HashMap<Entity, [Entity; 3]> = HashMap::new();
+    for (country_entity, country, country_children) in countries.iter() {
+        funds.insert(country_entity, (country.treasury, country.welfare));
+        for (state_entity, state, state_children) in country_children
+            .iter()
+            .filter_map(|&state| states.get(state).ok())
+        {
+            funds.insert(state_entity, (state.treasury, state.welfare));
+            for (city_entity, city) in state_children
+                .iter()
+                .filter_map(|&city| cities.get(city).ok())
+            {
+                funds.insert(city_entity, (city.treasury, city.welfare));
+                levels.insert(city_entity, [city_entity, state_entity, country_entity]);
+            }
+        }
+    }
+    if funds
+        .values()
+        .all(|(_, policy)| *policy == WelfarePolicy::default())
+    {
+        return;
+    }
+
+    for (entity, mut person, alive, city, employment, seeker, family) in persons.iter_mut() {
+        let Some(treasuries) = levels.get(&city.get()) else {
+            continue;
+        };
+        if !alive.0 {
+            continue;
+        }
+        let adult = demography.is_adult(person.age);
+        let share = family
+            .household
+            .and_then(|household| households.get(household).ok())
+            .map_or(0, |household| household.budget());
+        let poor = person.gold + share < means_test;
+        for benefit in Benefit::ALL {
+            let eligible = match benefit {
+                Benefit::BasicIncome => adult,
+                Benefit::FoodStamps => poor,
+                Benefit::Unemployment => adult && seeker.0 && employment.0.is_none(),
+            };
+            if !eligible {
+                continue;
+            }
+            for &treasury in treasuries {
+                let (gold, policy) = funds.get_mut(&treasury).unwrap();
+                let amount = policy.amount(benefit).min(*gold);
+                if amount == 0 {
+                    continue;
+                }
+                *gold -= amount;
+                match benefit {
+                    Benefit::FoodStamps => person.vouchers += amount,
+                    _ => person.gold += amount,
+                }
+                transfers.send(TransferEvent {
+                    from: Account::Agent(treasury),
+                    to: Account::Agent(entity),
+                    amount,
+                    kind: TransferKind::Benefit,
+                });
+                benefits.send(BenefitEvent {
+                    city: city.get(),
+                    benefit,
+                    amount,
+                });
+            }
+        }
+    }
+
+    for (entity, mut country, _) in countries.iter_mut() {
+        country.treasury = funds[&entity].0;
+    }
+    for (entity, mut state, _) in states.iter_mut() {
+        if let Some(&(gold, _)) = funds.get(&entity) {
+            state.treasury = gold;
+        }
+    }
+    for (entity, mut city) in cities.iter_mut() {
+        if let Some(&(gold, _)) = funds.get(&entity) {
+            city.treasury = gold;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_change_one_program_at_a_time() {
+        let config = WelfareConfig {
+            state: WelfarePolicy {
+                food_stamps: 5,
+                ..default()
+            },
+            ..default()
+        };
+        assert_eq!(config.means_test, 30);
+        assert_eq!(config.city, WelfarePolicy::default());
+
+        let mut policy = config.state;
+        assert_eq!(policy.amount(Benefit::FoodStamps), 5);
+        policy.set(Benefit::BasicIncome, 2);
+        assert_eq!(
+            Benefit::ALL.map(|benefit| policy.amount(benefit)),
+            [2, 5, 0]
+        );
+    }
+
+    #[test]
+    fn benefits_go_to_those_entitled_as_far_as_each_treasury_reaches() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::components::Job;
+
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<BenefitEvent>>();
+
+        // O país paga renda básica com um tesouro que não cobre os dois
+        // adultos; o estado, vale-alimentação sem ouro nenhum; a cidade,
+        // vale-alimentação e seguro-desemprego
+        let country = world
+            .spawn(Country {
+                treasury: 7,
+                welfare: WelfarePolicy {
+                    basic_income: 5,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        let state = world
+            .spawn(State {
+                welfare: WelfarePolicy {
+                    food_stamps: 3,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        let city = world
+            .spawn(City {
+                treasury: 100,
+                welfare: WelfarePolicy {
+                    food_stamps: 2,
+                    unemployment: 4,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        world.entity_mut(country).add_child(state);
+        world.entity_mut(state).add_child(city);
+        let mut spawn = |age: f32, gold: usize, seeking: bool, employment: Employment| {
+            let person = Person {
+                age,
+                gold,
+                ..default()
+            };
+            let person = world
+                .spawn((
+                    person,
+                    Alive(true),
+                    employment,
+                    JobSeeker(seeking),
+                    Family::default(),
+                ))
+                .id();
+            world.entity_mut(city).add_child(person);
+            person
+        };
+        let job = Employment(Some(Job {
+            employer: Entity::from_raw(1000),
+            worked_secs: 0.0,
+            accrued: 0.0,
+            withheld: [0.0; 3],
+        }));
+        let employed = spawn(30.0, 100, false, job);
+        let jobless = spawn(30.0, 10, true, Employment::default());
+        let child = spawn(5.0, 0, true, Employment::default());
+
+        world.run_system_once(benefits_system).unwrap();
+        let paid = |person: Entity| {
+            let person = world.get::<Person>(person).unwrap();
+            (person.gold, person.vouchers)
+        };
+        // Só quem procura emprego sem ter um recebe o seguro, e só abaixo do
+        // teste de renda se recebe o vale, que não vira ouro
+        let (employed_gold, employed_vouchers) = paid(employed);
+        let (jobless_gold, jobless_vouchers) = paid(jobless);
+        assert_eq!((employed_vouchers, jobless_vouchers), (0, 2));
+        assert_eq!(employed_gold - 100 + jobless_gold - 10 - 4, 7);
+        assert_eq!(paid(child), (0, 2));
+        assert_eq!(world.get::<Country>(country).unwrap().treasury, 0);
+        assert_eq!(world.get::<State>(state).unwrap().treasury, 0);
+        assert_eq!(world.get::<City>(city).unwrap().treasury, 100 - 2 - 4 - 2);
+        let benefits = world.resource::<Events<BenefitEvent>>().len();
+        assert_eq!(benefits, 5);
+    }
+
+    #[test]
+    fn vouchers_buy_food_from_shops_only() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        use crate::clock::SimClock;
+        use crate::components::{PersonActions, Shop};
+        use crate::events::{StockShortfallEvent, TaxEvent, TradeEvent};
+        use crate::items::{ItemCatalog, MarketPrices};
+        use crate::market::{market_system, CityMarkets};
+        use crate::taxes::TaxTable;
+
+        let catalog = ItemCatalog::default();
+        let apple = catalog.find("Apple").unwrap();
+        let mut world = World::new();
+        world.insert_resource(ScenarioConfig::default());
+        world.insert_resource(catalog);
+        world.insert_resource(SimClock::new(0.25, 600.0));
+        world.init_resource::<MarketPrices>();
+        world.init_resource::<CityMarkets>();
+        world.init_resource::<TaxTable>();
+        world.init_resource::<Events<TradeEvent>>();
+        world.init_resource::<Events<TransferEvent>>();
+        world.init_resource::<Events<TaxEvent>>();
+        world.init_resource::<Events<StockShortfallEvent>>();
+
+        // Em uma cidade só uma Person vende maçãs; na outra, uma loja
+        let buyer = |world: &mut World, city: Entity| {
+            let person = Person {
+                action: PersonActions::Buying,
+                gold: 0,
+                vouchers: 20,
+                ..default()
+            };
+            let person = world.spawn(person).id();
+            world.entity_mut(city).add_child(person);
+            person
+        };
+        let market = world.spawn(City::default()).id();
+        let mut seller = Person {
+            action: PersonActions::Selling,
+            ..default()
+        };
+        seller.inventory.insert(apple, 10);
+        let seller = world.spawn(seller).id();
+        world.entity_mut(market).add_child(seller);
+        let at_market = buyer(&mut world, market);
+
+        let town = world.spawn(City::default()).id();
+        let mut shop = Shop::default();
+        shop.stock_item(apple, 10, 5);
+        let shop = world.spawn(shop).id();
+        world.entity_mut(town).add_child(shop);
+        let at_shop = buyer(&mut world, town);
+
+        world.run_system_once(market_system).unwrap();
+        let person = world.get::<Person>(at_market).unwrap();
+        assert_eq!((person.vouchers, person.inventory.get(&apple)), (20, None));
+        assert_eq!(world.get::<Person>(seller).unwrap().inventory[&apple], 10);
+
+        let person = world.get::<Person>(at_shop).unwrap();
+        assert_eq!((person.gold, person.inventory[&apple]), (0, 1));
+        let spent = 20 - person.vouchers;
+        assert!(spent > 0);
+        assert_eq!(world.get::<Shop>(shop).unwrap().cash, spent);
+    }
+}